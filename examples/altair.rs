@@ -0,0 +1,45 @@
+use rustyi8080 as cpu;
+use cpu::console::StdioConsole;
+use cpu::machines::altair::{Altair, dcdd};
+use cpu::stats::InstructionStats;
+
+/**
+ * Loads a binary (e.g. Altair BASIC or a monitor ROM) and runs it with the terminal on stdin/stdout.
+ * Any .dsk arguments are mounted in order on an 88-DCDD, e.g. to boot Disk BASIC through the disk boot loader.
+ * --stats prints instruction statistics when the CPU halts.
+ *
+ *  cargo run --example altair -- [--stats] basic.bin [load address] [sense switches] [disk.dsk ...]
+ * */
+fn main() -> std::io::Result<()> {
+    let (flags, args): (Vec<String>, Vec<String>) = std::env::args().skip(1).partition(|a| a == "--stats");
+    let (disks, args): (Vec<String>, Vec<String>) = args.into_iter().partition(|a| a.ends_with(".dsk"));
+    if args.is_empty() {
+        eprintln!("usage: altair [--stats] <image> [load address hex] [sense switches hex] [disk.dsk ...]");
+        std::process::exit(1);
+    }
+
+    let parse_hex = |i: usize| args.get(i).map(|s| u16::from_str_radix(s.trim_start_matches("0x"), 16).unwrap_or(0)).unwrap_or(0);
+
+    let mut altair = Altair::new(StdioConsole::new());
+    altair.load(parse_hex(1), &cpu::read_file(&args[0])?);
+    altair.io.sense_switches = parse_hex(2) as u8;
+
+    if !disks.is_empty() {
+        let mut controller = dcdd::Dcdd::new();
+        for (drive, path) in disks.iter().enumerate().take(dcdd::DRIVES) {
+            controller.mount_file(drive, path)?;
+        }
+        altair.io.plug(dcdd::PORTS, Box::new(controller));
+    }
+
+    if !flags.is_empty() {
+        altair.cpu.stats = Some(InstructionStats::new());
+    }
+    altair.run();
+
+    if let Some(stats) = &altair.cpu.stats {
+        stats.write_report(&mut std::io::stderr(), &altair.cpu.opcode_table)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use rustyi8080 as cpu;
+use cpu::console::StreamConsole;
+use cpu::machines::altair::Altair;
+use cpu::machines::altair::panel::FrontPanel;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc;
+use std::thread;
+
+const HELP: &str = "\
+  sw <hex>   set the address/data switches (A15 - A8 are also the sense switches)
+  x  / xn    EXAMINE / EXAMINE NEXT
+  d  / dn    DEPOSIT / DEPOSIT NEXT
+  r  / s     RUN / STOP
+  ss         SINGLE STEP
+  reset      RESET
+  q          quit";
+
+/**
+ * Text front panel for toggling programs into an Altair. Terminal output from the 88-SIO/2SIO goes
+ * straight to stdout, stdin is reserved for panel commands. An optional image is loaded before starting.
+ *
+ *  cargo run --example altair_panel -- [image] [load address hex]
+ * */
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut altair = Altair::new(StreamConsole::from_streams(io::empty(), io::stdout()));
+    if let Some(path) = args.first() {
+        let addr = args.get(1).map(|s| u16::from_str_radix(s.trim_start_matches("0x"), 16).unwrap_or(0)).unwrap_or(0);
+        altair.load(addr, &cpu::read_file(path)?);
+    }
+    let mut panel = FrontPanel::new(altair);
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            match line {
+                Ok(line) => if tx.send(line).is_err() { break; },
+                Err(_) => break
+            }
+        }
+    });
+
+    println!("{}\n", HELP);
+    print!("{}", panel);
+
+    loop {
+        let line = if panel.is_running() {
+            panel.run_for(20_000);
+            match rx.try_recv() {
+                Ok(line) => line,
+                Err(mpsc::TryRecvError::Empty) => continue,
+                Err(mpsc::TryRecvError::Disconnected) => return Ok(())
+            }
+        } else {
+            print!("> ");
+            io::stdout().flush()?;
+            match rx.recv() {
+                Ok(line) => line,
+                Err(_) => return Ok(())
+            }
+        };
+
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("sw") => {
+                match words.next().and_then(|s| u16::from_str_radix(s.trim_start_matches("0x"), 16).ok()) {
+                    Some(value) => panel.set_switches(value),
+                    None => println!("sw needs a hex value")
+                }
+            },
+            Some("x") => panel.examine(),
+            Some("xn") => panel.examine_next(),
+            Some("d") => panel.deposit(),
+            Some("dn") => panel.deposit_next(),
+            Some("r") => panel.run(),
+            Some("s") => panel.stop(),
+            Some("ss") => panel.single_step(),
+            Some("reset") => panel.reset(),
+            Some("q") => return Ok(()),
+            Some(_) => println!("{}", HELP),
+            None => {}
+        }
+        print!("{}", panel);
+    }
+}
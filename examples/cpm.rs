@@ -0,0 +1,40 @@
+use rustyi8080::console::StdioConsole;
+use rustyi8080::cpm::CpmMachine;
+use rustyi8080::cpm::disk::DiskImage;
+use rustyi8080::stats::InstructionStats;
+
+/**
+ * Boots CP/M 2.2 from the first image, the rest are mounted as B:, C:, ...
+ * A directory instead of an image maps that drive onto the host directory. --stats prints instruction
+ * statistics when the machine halts.
+ *
+ *  cargo run --example cpm -- [--stats] cpma.dsk [b.dsk | dir ...]
+ * */
+fn main() -> std::io::Result<()> {
+    let (flags, images): (Vec<String>, Vec<String>) = std::env::args().skip(1).partition(|a| a == "--stats");
+    if images.is_empty() {
+        eprintln!("usage: cpm [--stats] <boot.dsk> [b.dsk | dir ...]");
+        std::process::exit(1);
+    }
+
+    let mut machine = CpmMachine::new(StdioConsole::new());
+    for (drive, path) in images.iter().enumerate().take(rustyi8080::cpm::DRIVES) {
+        if std::path::Path::new(path).is_dir() {
+            machine.mount_dir(drive, path);
+        } else {
+            machine.mount(drive, DiskImage::open(path)?);
+        }
+    }
+
+    if !flags.is_empty() {
+        machine.cpu.stats = Some(InstructionStats::new());
+    }
+    machine.boot()?;
+    machine.run();
+
+    if let Some(stats) = &machine.cpu.stats {
+        stats.write_report(&mut std::io::stderr(), &machine.cpu.opcode_table)?;
+    }
+
+    Ok(())
+}
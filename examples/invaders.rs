@@ -1,28 +1,30 @@
 use rustyi8080 as cpu;
+use cpu::Cpu8080;
+use cpu::io::NullBus;
+use cpu::stats::InstructionStats;
 
+const ROM_PARTS: [&str; 4] = ["invaders.h", "invaders.g", "invaders.f", "invaders.e"];
 
-
-fn fun(h: u8, l: u8) {
-    let r = register_pair(h, l);
-    let r = r + 1;
-    println!("RP+1: {}", r);
-    let low = r as u8;
-    let high = (r >> 8) as u8;
-    println!("RP: {}", register_pair(high, low));
-}
-
-const fn register_pair(high: u8, low: u8) -> u16 {
-    ((high as u16) << 8) | low as u16
-}
-
+/**
+ * Runs the Space Invaders ROM from invaders_rom/ for a while, --stats prints instruction statistics at the end
+ * */
 fn main() -> std::io::Result<()> {
-    let f = cpu::read_file("invaders_rom/invaders.h")?;
-
-    let x: u16 = 300;
-    println!("AS: {}, AND: {}", x as u8, x & 0xFF);
+    let mut i8080 = Cpu8080::new();
+    if std::env::args().any(|a| a == "--stats") {
+        i8080.stats = Some(InstructionStats::new());
+    }
+    for (i, part) in ROM_PARTS.iter().enumerate() {
+        let rom = cpu::read_file(&format!("invaders_rom/{}", part))?;
+        i8080.load((i * 0x800) as u16, &rom);
+    }
+
+    for _ in 0..50_000 {
+        i8080.step(&mut NullBus);
+    }
+    println!("PC: {:04X} SP: {:04X} cycles: {}", i8080.pc, i8080.sp, i8080.cycles);
+    if let Some(stats) = &i8080.stats {
+        stats.write_report(&mut std::io::stderr(), &i8080.opcode_table)?;
+    }
 
     Ok(())
 }
-
-
-
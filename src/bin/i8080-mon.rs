@@ -0,0 +1,689 @@
+use rustyi8080::{Cpu8080, Reg8, Reg16};
+use rustyi8080::callstack::{CallStack, Frame, FrameKind};
+use rustyi8080::coverage::{self, Coverage};
+use rustyi8080::debugger::history::{self, History};
+use rustyi8080::debugger::{Debugger, Machine, StopReason, WatchKind, PortDirection};
+use rustyi8080::debugger::expr::{Expr, parse_number};
+use rustyi8080::gdbstub::GdbStub;
+use rustyi8080::ihex::HexImage;
+use rustyi8080::inspect::{self, Pattern};
+use rustyi8080::profile::Profiler;
+use rustyi8080::stats::InstructionStats;
+use rustyi8080::savestate::{self, SaveState, Snapshot};
+use rustyi8080::symbols::SymbolTable;
+use rustyi8080::io::NullBus;
+use rustyi8080::trace::{Tracer, TraceFormat, flag_string};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+
+const HELP: &str = "\
+load <file> [addr]       load a ROM image (at addr, default 0), .hex or .com file, or a saved state
+r [reg value]            show registers, or set a|b|c|d|e|h|l|m|f|bc|de|hl|sp|psw|pc
+m <addr> [len]           dump memory
+e <addr> <byte>...       write bytes (hex, or expressions) to memory
+find <pattern>           search memory for hex bytes with ?? wildcards (CD ?? 01) or a \"string\"
+snap                     remember memory for diff
+diff [file]              list memory changed since snap, or since a saved state
+d [addr] [count]         disassemble, from pc by default
+b [addr [if cond]]       add a breakpoint, or list all breakpoints and watchpoints
+w <addr> [len] [r|w|rw]  add a watchpoint, write only by default
+bp <port> [in|out]       add a port breakpoint
+del [id]                 delete one or all points
+on <id> | off <id>       enable or disable a point
+ignore <id> <count>      don't stop on the next count hits
+s [count]                step
+n                        step over CALL/RST
+finish                   run until the current subroutine returns
+c [max steps]            continue
+bs [count]               step back
+rc                       continue backwards to the previous breakpoint
+history [on [KiB] | off] record execution for bs and rc (on, 16384 KiB by default), or show usage
+u <addr>                 run until pc reaches addr
+bt                       backtrace
+trace on [file] | off    log every instruction to stdout or a file
+profile on|off|clear     count cycles per address and routine
+profile [count]          show the top routines and addresses
+profile folded <file>    write the call stacks for flamegraph.pl or inferno
+cover on|off|clear       record which addresses are executed, read and written
+cover                    show coverage by address range
+cover save <file>        save the coverage map, one byte per address
+cover list <file> [a b]  write a disassembly marking never executed code, of a - b or all touched memory
+stats on|off|clear       count executions per opcode, shown again on quit
+stats                    show the instruction mix and how often conditional branches were taken
+save <file>              save CPU state and memory
+sym [name value]         define a symbol, or list them
+symfile <file>           load symbols (.sym or NAME ADDR lists)
+gdb [host:port]          wait for a GDB remote protocol client (default 127.0.0.1:1234)
+bdos on|off              CP/M console calls at 0005 and warm boot at 0000 (load sets it: on for .com, else off)
+q                        quit
+
+Numbers are hex (0100, $FF, 0FFh) or decimal with #. Addresses and values are expressions on registers,
+flags (Z S P CY AC), [memory] and symbols, e.g. 'b 0200 if B==2 && [HL]!=#10'. An empty line repeats s or n.";
+
+const PROFILER_OFF: &str = "the profiler is off, turn it on with 'profile on'";
+const COVERAGE_OFF: &str = "coverage is off, turn it on with 'cover on'";
+const STATS_OFF: &str = "statistics are off, turn them on with 'stats on'";
+// matches find prints
+const FIND_LIMIT: usize = 32;
+
+const BDOS: u16 = 0x0005;
+const TPA: u16 = 0x0100;
+
+/**
+ * Interactive monitor around a bare Cpu8080. IN reads 0xFF and OUT goes nowhere, but with bdos on a .COM
+ * program can print through CP/M's console calls.
+ *
+ *  cargo run --bin i8080-mon -- [file [load address]] [symbols.sym ...]
+ * */
+fn main() {
+    let mut monitor = Monitor::new();
+    let (symbol_files, args): (Vec<String>, Vec<String>) = std::env::args().skip(1)
+        .partition(|a| a.to_ascii_lowercase().ends_with(".sym"));
+    for path in symbol_files.iter() {
+        if let Err(e) = monitor.command(&format!("symfile {}", path)) {
+            eprintln!("{}", e);
+        }
+    }
+    if !args.is_empty() {
+        if let Err(e) = monitor.command(&format!("load {}", args.join(" "))) {
+            eprintln!("{}", e);
+        }
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break
+        };
+        let line = if line.trim().is_empty() { monitor.repeat.clone() } else { line };
+        match monitor.command(&line) {
+            Ok(true) => break,
+            Ok(false) => {},
+            Err(e) => println!("{}", e)
+        }
+    }
+    if let Some(stats) = &monitor.cpu().stats {
+        let _ = stats.write_report(&mut io::stdout(), &monitor.cpu().opcode_table);
+    }
+}
+
+/**
+ * The CPU with the monitor's extras around each step
+ * */
+struct Target {
+    cpu: Cpu8080,
+    bdos: bool,
+    tracer: Option<Tracer<Box<dyn Write>>>,
+    profiler: Option<Profiler>,
+    coverage: Option<Coverage>
+}
+
+impl Machine for Target {
+    fn cpu(&self) -> &Cpu8080 {
+        &self.cpu
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu8080 {
+        &mut self.cpu
+    }
+
+    fn step(&mut self) -> u32 {
+        if let Some(tracer) = &mut self.tracer {
+            if let Err(e) = tracer.trace(&self.cpu) {
+                println!("trace stopped: {}", e);
+                self.tracer = None;
+            }
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin(&self.cpu);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.begin(&mut self.cpu);
+        }
+        let cycles = self.execute();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end(cycles);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.end(&self.cpu);
+        }
+        cycles
+    }
+}
+
+impl Target {
+    fn new(cpu: Cpu8080) -> Self {
+        Target { cpu, bdos: false, tracer: None, profiler: None, coverage: None }
+    }
+
+    fn execute(&mut self) -> u32 {
+        if self.bdos {
+            match self.cpu.pc {
+                0x0000 => {
+                    self.cpu.halted = true;
+                    return 0;
+                },
+                BDOS => {
+                    self.bdos_call();
+                    self.cpu.trap_return();
+                    self.cpu.cycles += 10;
+                    return 10;
+                },
+                _ => {}
+            }
+        }
+        self.cpu.step(&mut NullBus)
+    }
+
+    fn bdos_call(&mut self) {
+        let cpu = &self.cpu;
+        let mut out = io::stdout();
+        let _ = match cpu.c {
+            2 => out.write_all(&[cpu.e]),
+            9 => {
+                let mut addr = cpu.de();
+                let mut text = Vec::new();
+                while cpu.memory[addr as usize] != b'$' && text.len() < Cpu8080::MEMORY_SIZE {
+                    text.push(cpu.memory[addr as usize]);
+                    addr = addr.wrapping_add(1);
+                }
+                out.write_all(&text)
+            },
+            _ => Ok(())
+        };
+        let _ = out.flush();
+    }
+}
+
+struct Monitor {
+    debugger: Debugger<Target>,
+    /**
+     * Command that created each point, for listing them
+     * */
+    descriptions: HashMap<usize, String>,
+    symbols: SymbolTable,
+    /**
+     * Memory saved by snap
+     * */
+    snapshot: Option<Vec<u8>>,
+    repeat: String
+}
+
+impl Monitor {
+    fn new() -> Self {
+        let mut cpu = Cpu8080::new();
+        cpu.call_stack = Some(CallStack::new());
+        let mut debugger = Debugger::new(Target::new(cpu));
+        debugger.enable_history(History::default());
+        Monitor {
+            debugger,
+            descriptions: HashMap::new(),
+            symbols: SymbolTable::new(),
+            snapshot: None,
+            repeat: String::new()
+        }
+    }
+
+    fn cpu(&self) -> &Cpu8080 {
+        self.debugger.cpu()
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu8080 {
+        self.debugger.cpu_mut()
+    }
+
+    fn parse(&self, text: &str) -> Result<Expr, String> {
+        Expr::parse_with(text, &|name| self.symbols.lookup(name))
+    }
+
+    fn eval(&self, text: &str) -> Result<u32, String> {
+        Ok(self.parse(text)?.eval(self.cpu()))
+    }
+
+    fn addr(&self, text: Option<&&str>) -> Result<u16, String> {
+        let text = text.ok_or("missing address")?;
+        Ok(self.eval(text)? as u16)
+    }
+
+    /**
+     * Runs one command line, returning true to quit
+     * */
+    fn command(&mut self, line: &str) -> Result<bool, String> {
+        let line = line.trim();
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some(&name) = args.first() else { return Ok(false) };
+        self.repeat = if name == "s" || name == "n" { line.to_string() } else { String::new() };
+
+        match name {
+            "q" | "quit" => return Ok(true),
+            "h" | "help" | "?" => println!("{}", HELP),
+            "load" => self.load(args.get(1).ok_or("missing file")?, args.get(2))?,
+            "save" => self.save(args.get(1).ok_or("missing file")?)?,
+            "r" => self.registers(&args[1..])?,
+            "m" => {
+                let addr = self.addr(args.get(1))?;
+                let len = match args.get(2) { Some(len) => self.eval(len)?, None => 0x80 };
+                let memory = &self.cpu().memory;
+                let _ = inspect::write_hexdump_with(&mut io::stdout(), addr, len.min(0x10000), |addr| memory[addr as usize]);
+            },
+            "find" => {
+                let text = line[name.len()..].trim();
+                let pattern = Pattern::parse(text).ok_or_else(|| format!("bad pattern '{}'", text))?;
+                let memory = &self.cpu().memory;
+                let found = pattern.find_with(0..=0xFFFF, |addr| memory[addr as usize]);
+                for addr in found.iter().take(FIND_LIMIT) {
+                    let label = self.symbols.describe(*addr).map(|name| format!("  {}", name)).unwrap_or_default();
+                    println!("{:04X}{}", addr, label);
+                }
+                if found.len() > FIND_LIMIT {
+                    println!("... {} more", found.len() - FIND_LIMIT);
+                }
+            },
+            "snap" => self.snapshot = Some(self.cpu().memory.clone()),
+            "diff" => {
+                let old = match args.get(1) {
+                    Some(path) => {
+                        let state = SaveState::load_file(path).map_err(|e| format!("{}: {}", path, e))?;
+                        let mut cpu = Cpu8080::new();
+                        cpu.load_state(&state).map_err(|e| format!("{}: {}", path, e))?;
+                        cpu.memory
+                    },
+                    None => self.snapshot.clone().ok_or("no snapshot, take one with 'snap'")?
+                };
+                let memory = &self.cpu().memory;
+                let _ = inspect::write_diff_with(&mut io::stdout(), &old, |addr| memory[addr as usize]);
+            },
+            "e" => {
+                let addr = self.addr(args.get(1))?;
+                for (i, byte) in args[2..].iter().enumerate() {
+                    // bare hex like FF would otherwise be read as a name
+                    let value = match parse_number(byte) { Some(value) => value, None => self.eval(byte)? } as u8;
+                    self.cpu_mut().memory[addr.wrapping_add(i as u16) as usize] = value;
+                }
+            },
+            "d" => {
+                let addr = match args.get(1) { Some(_) => self.addr(args.get(1))?, None => self.cpu().pc };
+                let count = match args.get(2) { Some(count) => self.eval(count)?, None => 0x10 };
+                self.disassemble(addr, count);
+            },
+            "b" if args.len() == 1 => self.list_points(),
+            "b" => {
+                let (addr, condition) = match line.split_once(" if ") {
+                    Some((_, condition)) => (self.addr(args.get(1))?, Some(self.parse(condition)?)),
+                    None => (self.addr(args.get(1))?, None)
+                };
+                let id = match condition {
+                    Some(condition) => self.debugger.add_conditional_breakpoint(addr, condition),
+                    None => self.debugger.add_breakpoint(addr)
+                };
+                self.descriptions.insert(id, line.to_string());
+                println!("breakpoint {} at {:04X}", id, addr);
+            },
+            "w" => {
+                let (kind, rest) = match args[1..].split_last() {
+                    Some((&"r", rest)) => (WatchKind::Read, rest),
+                    Some((&"w", rest)) => (WatchKind::Write, rest),
+                    Some((&"rw", rest)) => (WatchKind::Access, rest),
+                    _ => (WatchKind::Write, &args[1..])
+                };
+                let addr = self.addr(rest.first())?;
+                let len = match rest.get(1) { Some(len) => self.eval(len)?.max(1), None => 1 };
+                let end = addr.saturating_add((len - 1) as u16);
+                let id = self.debugger.add_watchpoint(addr..=end, kind);
+                self.descriptions.insert(id, line.to_string());
+                println!("watchpoint {} on {:04X}-{:04X}", id, addr, end);
+            },
+            "bp" => {
+                let port = self.eval(args.get(1).ok_or("missing port")?)? as u8;
+                let direction = match args.get(2) {
+                    Some(&"in") => PortDirection::In,
+                    Some(&"out") => PortDirection::Out,
+                    None => PortDirection::Both,
+                    Some(other) => return Err(format!("expected in or out, not '{}'", other))
+                };
+                let id = self.debugger.add_port_breakpoint(port, direction);
+                self.descriptions.insert(id, line.to_string());
+                println!("port breakpoint {} on {:02X}", id, port);
+            },
+            "del" => match args.get(1) {
+                Some(id) => {
+                    let id = self.point_id(id)?;
+                    self.debugger.remove(id);
+                },
+                None => self.debugger.clear()
+            },
+            "on" | "off" => {
+                let id = self.point_id(args.get(1).ok_or("missing id")?)?;
+                self.debugger.point_mut(id).ok_or("no such point")?.enabled = name == "on";
+            },
+            "ignore" => {
+                let id = self.point_id(args.get(1).ok_or("missing id")?)?;
+                let count = self.eval(args.get(2).ok_or("missing count")?)?;
+                self.debugger.point_mut(id).ok_or("no such point")?.ignore_count = count as u64;
+            },
+            "s" => {
+                let count = match args.get(1) { Some(count) => self.eval(count)?.max(1), None => 1 };
+                let mut reason = StopReason::Step;
+                for _ in 0..count {
+                    reason = self.debugger.step();
+                    if reason != StopReason::Step {
+                        break;
+                    }
+                }
+                self.stopped(reason);
+            },
+            "n" => {
+                let reason = self.debugger.step_over();
+                self.stopped(reason);
+            },
+            "finish" => {
+                let reason = self.debugger.step_out();
+                self.stopped(reason);
+            },
+            "c" => {
+                let reason = match args.get(1) {
+                    Some(max) => self.debugger.run_for(self.eval(max)? as u64),
+                    None => self.debugger.run()
+                };
+                self.stopped(reason);
+            },
+            "bs" => {
+                let count = match args.get(1) { Some(count) => self.eval(count)?.max(1), None => 1 };
+                self.history()?;
+                let undone = self.debugger.seek_back(count as u64);
+                let reason = if undone < count as u64 { StopReason::HistoryStart } else { StopReason::Step };
+                self.stopped(reason);
+            },
+            "rc" => {
+                self.history()?;
+                let reason = self.debugger.reverse_continue();
+                self.stopped(reason);
+            },
+            "history" => match args.get(1) {
+                Some(&"on") => {
+                    let budget = match args.get(2) { Some(kib) => self.eval(kib)? as usize * 1024, None => history::DEFAULT_BUDGET };
+                    match self.debugger.history_mut() {
+                        Some(history) => history.set_budget(budget),
+                        None => self.debugger.enable_history(History::new(budget))
+                    }
+                },
+                Some(&"off") => self.debugger.disable_history(),
+                None => {
+                    let history = self.history()?;
+                    println!("{} instructions back, {} of {} KiB used", history.len(), history.memory_used() / 1024, history.budget() / 1024);
+                },
+                _ => return Err("history on [KiB] | off".to_string())
+            },
+            "u" => {
+                let addr = self.addr(args.get(1))?;
+                let reason = self.debugger.run_to(addr);
+                self.stopped(reason);
+            },
+            "bt" => {
+                let cpu = self.cpu();
+                let call_stack = cpu.call_stack.as_ref().ok_or("call stack tracking is off")?;
+                let _ = call_stack.write_backtrace(&mut io::stdout(), cpu.pc, |addr| self.symbols.describe(addr));
+                for mismatch in call_stack.mismatches.iter().rev().take(3) {
+                    println!("mismatched return at {:04X} to {:04X}", mismatch.pc, mismatch.return_addr);
+                }
+            },
+            "trace" => match args.get(1) {
+                Some(&"on") => {
+                    let sink: Box<dyn Write> = match args.get(2) {
+                        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|e| format!("{}: {}", path, e))?)),
+                        None => Box::new(io::stdout())
+                    };
+                    self.debugger.machine.tracer = Some(Tracer::new(sink, TraceFormat::Native).with_symbols(self.symbols.clone()));
+                },
+                Some(&"off") => self.debugger.machine.tracer = None,
+                _ => return Err("trace on [file] | off".to_string())
+            },
+            "profile" => match args.get(1) {
+                Some(&"on") => {
+                    self.debugger.machine.profiler.get_or_insert_with(Profiler::new);
+                },
+                Some(&"off") => self.debugger.machine.profiler = None,
+                Some(&"clear") => self.profiler()?.clear(),
+                Some(&"folded") => {
+                    let path = args.get(2).ok_or("missing file")?;
+                    let mut file = BufWriter::new(File::create(path).map_err(|e| format!("{}: {}", path, e))?);
+                    let profiler = self.debugger.machine.profiler.as_ref().ok_or(PROFILER_OFF)?;
+                    profiler.write_folded(&mut file, &self.symbols).and_then(|_| file.flush()).map_err(|e| format!("{}: {}", path, e))?;
+                },
+                count => {
+                    let count = match count { Some(count) => self.eval(count)? as usize, None => 20 };
+                    let profiler = self.debugger.machine.profiler.as_ref().ok_or(PROFILER_OFF)?;
+                    let _ = profiler.write_report(&mut io::stdout(), self.cpu(), &self.symbols, count);
+                }
+            },
+            "cover" => match args.get(1) {
+                Some(&"on") => {
+                    self.debugger.machine.coverage.get_or_insert_with(Coverage::new);
+                },
+                Some(&"off") => self.debugger.machine.coverage = None,
+                Some(&"clear") => self.coverage()?.clear(),
+                Some(&"save") => {
+                    let path = args.get(2).ok_or("missing file")?;
+                    self.coverage()?.save_file(path).map_err(|e| format!("{}: {}", path, e))?;
+                },
+                Some(&"list") => {
+                    let path = args.get(2).ok_or("missing file")?;
+                    let coverage = self.debugger.machine.coverage.as_ref().ok_or(COVERAGE_OFF)?;
+                    let range = match (args.get(3), args.get(4)) {
+                        (Some(_), Some(_)) => self.addr(args.get(3))?..=self.addr(args.get(4))?,
+                        _ => {
+                            let ranges = coverage.ranges();
+                            match (ranges.first(), ranges.last()) {
+                                (Some((first, _)), Some((last, _))) => *first.start()..=*last.end(),
+                                _ => return Err("nothing covered yet".to_string())
+                            }
+                        }
+                    };
+                    let mut file = BufWriter::new(File::create(path).map_err(|e| format!("{}: {}", path, e))?);
+                    coverage.write_listing(&mut file, self.cpu(), &self.symbols, range).and_then(|_| file.flush())
+                        .map_err(|e| format!("{}: {}", path, e))?;
+                },
+                None => {
+                    let coverage = self.debugger.machine.coverage.as_ref().ok_or(COVERAGE_OFF)?;
+                    let _ = coverage.write_ranges(&mut io::stdout());
+                    println!("{} bytes executed as opcodes, {} as operands, {} read, {} written",
+                        coverage.count(coverage::OPCODE), coverage.count(coverage::OPERAND),
+                        coverage.count(coverage::READ), coverage.count(coverage::WRITE));
+                },
+                _ => return Err("cover [on|off|clear|save <file>|list <file> [a b]]".to_string())
+            },
+            "stats" => match args.get(1) {
+                Some(&"on") => {
+                    self.cpu_mut().stats.get_or_insert_with(InstructionStats::new);
+                },
+                Some(&"off") => self.cpu_mut().stats = None,
+                Some(&"clear") => self.cpu_mut().stats.as_mut().ok_or(STATS_OFF)?.clear(),
+                None => {
+                    let cpu = self.cpu();
+                    let _ = cpu.stats.as_ref().ok_or(STATS_OFF)?.write_report(&mut io::stdout(), &cpu.opcode_table);
+                },
+                _ => return Err("stats [on|off|clear]".to_string())
+            },
+            "sym" if args.len() == 1 => {
+                for (addr, name) in self.symbols.iter() {
+                    println!("{:04X}  {}", addr, name);
+                }
+            },
+            "sym" => {
+                let value = self.addr(args.get(2))?;
+                self.symbols.insert(args[1], value);
+            },
+            "symfile" => {
+                let path = args.get(1).ok_or("missing file")?;
+                let count = self.symbols.load_file(path).map_err(|e| e.to_string())?;
+                println!("{} symbols", count);
+            },
+            "gdb" => {
+                let addr = args.get(1).copied().unwrap_or("127.0.0.1:1234");
+                println!("waiting for gdb on {}", addr);
+                let debugger = std::mem::replace(&mut self.debugger, Debugger::new(Target::new(Cpu8080::new())));
+                let mut stub = GdbStub::with_debugger(debugger);
+                let result = stub.serve(addr);
+                self.debugger = stub.into_inner();
+                result.map_err(|e| format!("gdb: {}", e))?;
+                println!("gdb detached\n{}", self.status());
+            },
+            "bdos" => self.debugger.machine.bdos = match args.get(1) {
+                Some(&"on") => true,
+                Some(&"off") => false,
+                _ => return Err("bdos on|off".to_string())
+            },
+            _ => return Err(format!("unknown command '{}', try help", name))
+        }
+        Ok(false)
+    }
+
+    fn history(&self) -> Result<&History, String> {
+        self.debugger.history().ok_or_else(|| "history is off, turn it on with 'history on'".to_string())
+    }
+
+    fn profiler(&mut self) -> Result<&mut Profiler, String> {
+        self.debugger.machine.profiler.as_mut().ok_or_else(|| PROFILER_OFF.to_string())
+    }
+
+    fn coverage(&mut self) -> Result<&mut Coverage, String> {
+        self.debugger.machine.coverage.as_mut().ok_or_else(|| COVERAGE_OFF.to_string())
+    }
+
+    fn point_id(&self, text: &str) -> Result<usize, String> {
+        text.parse().map_err(|_| format!("bad id '{}'", text))
+    }
+
+    fn load(&mut self, path: &str, addr: Option<&&str>) -> Result<(), String> {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        if bytes.starts_with(savestate::MAGIC) {
+            return self.restore(&bytes);
+        }
+
+        let lower = path.to_ascii_lowercase();
+        let load_addr = match addr { Some(addr) => self.eval(addr)? as u16, None => 0 };
+        if let Some(history) = self.debugger.history_mut() {
+            history.clear();
+        }
+        let target = &mut self.debugger.machine;
+        if let Some(call_stack) = &mut target.cpu.call_stack {
+            call_stack.clear();
+        }
+        let entry = if lower.ends_with(".hex") || lower.ends_with(".ihx") {
+            let text = String::from_utf8_lossy(&bytes);
+            let image = HexImage::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+            image.load_into(&mut target.cpu);
+            target.bdos = false;
+            image.entry().unwrap_or(0)
+        } else if lower.ends_with(".com") {
+            target.cpu.load(TPA, &bytes);
+            target.cpu.sp = 0xF000;
+            // a return from the program warm boots, as if the CCP had called it
+            target.cpu.push_stack(0x0000);
+            let sp = target.cpu.sp;
+            if let Some(call_stack) = &mut target.cpu.call_stack {
+                call_stack.push(Frame { kind: FrameKind::Call, call_site: 0x0000, target: TPA, return_addr: 0x0000, sp });
+            }
+            target.bdos = true;
+            TPA
+        } else {
+            target.cpu.load(load_addr, &bytes);
+            target.bdos = false;
+            load_addr
+        };
+
+        let cpu = self.cpu_mut();
+        cpu.pc = entry;
+        cpu.halted = false;
+        println!("loaded {}, pc {:04X}", path, entry);
+        Ok(())
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        SaveState::of(self.cpu()).save_file(path).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn restore(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let state = SaveState::from_bytes(bytes).map_err(|e| e.to_string())?;
+        self.cpu_mut().load_state(&state).map_err(|e| e.to_string())?;
+        if let Some(history) = self.debugger.history_mut() {
+            history.clear();
+        }
+        println!("state restored, pc {:04X}", self.cpu().pc);
+        Ok(())
+    }
+
+    fn registers(&mut self, args: &[&str]) -> Result<(), String> {
+        if args.is_empty() {
+            println!("{}", self.status());
+            return Ok(());
+        }
+        let (name, value) = match args {
+            [name, value] => (name.to_ascii_lowercase(), self.eval(value)?),
+            _ => return Err("r [reg value]".to_string())
+        };
+        let cpu = self.cpu_mut();
+        if name == "f" {
+            cpu.condition_codes = rustyi8080::ConditionBitset::from_psw(value as u8);
+        } else if let Some(reg) = Reg8::parse(&name) {
+            cpu.set(reg, value as u8);
+        } else if let Some(reg) = Reg16::parse(&name) {
+            cpu.set(reg, value as u16);
+        } else {
+            return Err(format!("unknown register '{}'", name));
+        }
+        Ok(())
+    }
+
+    /**
+     * Registers and the instruction at pc
+     * */
+    fn status(&self) -> String {
+        let cpu = self.cpu();
+        let (disassembly, _) = cpu.opcode_table.disassemble_symbolic(&cpu.memory, cpu.pc, &self.symbols);
+        let label = self.symbols.name_at(cpu.pc).map(|name| format!("{}: ", name)).unwrap_or_default();
+        format!("{:04X}  {}{:<16} A:{:02X} BC:{:02X}{:02X} DE:{:02X}{:02X} HL:{:02X}{:02X} SP:{:04X} F:{} CYC:{}{}",
+            cpu.pc, label, disassembly, cpu.a, cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l, cpu.sp,
+            flag_string(cpu), cpu.cycles, if cpu.halted { " HALTED" } else { "" })
+    }
+
+    fn stopped(&self, reason: StopReason) {
+        match reason {
+            StopReason::Step | StopReason::Temporary { .. } => {},
+            StopReason::Breakpoint { id, addr } => println!("breakpoint {} at {:04X}", id, addr),
+            StopReason::Watchpoint { id, access } => println!("watchpoint {}: {:?} {:02X} at {:04X}", id, access.kind, access.value, access.addr),
+            StopReason::Port { id, port, value, direction } => println!("port breakpoint {}: {:?} {:02X} on port {:02X}", id, direction, value, port),
+            StopReason::Halted => println!("halted"),
+            StopReason::Limit => println!("step limit reached"),
+            StopReason::HistoryStart => println!("reached the start of the recorded history")
+        }
+        println!("{}", self.status());
+    }
+
+    fn list_points(&self) {
+        for point in self.debugger.points() {
+            let description = self.descriptions.get(&point.id).map(|s| s.as_str()).unwrap_or("");
+            println!("{:>3}  {:<32} hits {}{}{}", point.id, description, point.hits,
+                if point.ignore_count > 0 { format!(" ignore {}", point.ignore_count) } else { String::new() },
+                if point.enabled { "" } else { " (off)" });
+        }
+    }
+
+    fn disassemble(&self, mut addr: u16, count: u32) {
+        let cpu = self.cpu();
+        for _ in 0..count {
+            if let Some(name) = self.symbols.name_at(addr) {
+                println!("{}:", name);
+            }
+            let (text, size) = cpu.opcode_table.disassemble_symbolic(&cpu.memory, addr, &self.symbols);
+            let bytes: Vec<String> = (0..size as u16).map(|i| format!("{:02X}", cpu.memory[addr.wrapping_add(i) as usize])).collect();
+            println!("{}{:04X}  {:<8}  {}", if addr == cpu.pc { ">" } else { " " }, addr, bytes.join(" "), text);
+            addr = addr.wrapping_add(size as u16);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use rustyi8080::opcode::OpcodeTable;
+use rustyi8080::trace::{TraceDiff, describe, describe_instruction, diff_traces};
+use std::fs::File;
+use std::io::BufReader;
+use std::process;
+
+const USAGE: &str = "usage: i8080-tracediff [-C lines] [--cycles] <ours.log> <theirs.log>";
+
+/**
+ * Compares two instruction traces (see `rustyi8080::trace`, either format) line by line and reports the first
+ * instruction where PC, a register or a flag differs, with the instructions leading up to it.
+ * Lines that aren't trace records are ignored. Cycle counts are only compared with --cycles.
+ * Exits with 0 when the traces agree, 1 when they diverge and 2 on bad arguments.
+ * */
+fn main() {
+    let mut context = 5;
+    let mut compare_cycles = false;
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-C" | "--context" => {
+                context = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage());
+            },
+            "--cycles" => compare_cycles = true,
+            "-h" | "--help" => usage(),
+            _ => paths.push(arg)
+        }
+    }
+    if paths.len() != 2 {
+        usage();
+    }
+
+    let ours = open(&paths[0]);
+    let theirs = open(&paths[1]);
+    let table = OpcodeTable::new();
+
+    // at least one record back, for the instruction that produced the difference
+    let result = diff_traces(ours, theirs, context.max(1), compare_cycles).unwrap_or_else(|e| {
+        eprintln!("reading traces: {}", e);
+        process::exit(2);
+    });
+    match result {
+        TraceDiff::Agree(count) => println!("traces agree for all {} instructions", count),
+        TraceDiff::Ends { agreed, ours_ended } => {
+            let (shorter, longer) = if ours_ended { ("ours", "theirs") } else { ("theirs", "ours") };
+            println!("traces agree for {} instructions, then {} ends while {} continues", agreed, shorter, longer);
+            process::exit(1);
+        },
+        TraceDiff::Diverge(divergence) => {
+            let i = divergence.index;
+            println!("first divergence at instruction {}", i);
+            // each line holds the state before its instruction, so the previous instruction produced the difference
+            if let Some(previous) = divergence.context.last() {
+                println!("  after {:04X}  {}", previous.pc, describe_instruction(&table, previous));
+            }
+            for difference in divergence.differences.iter() {
+                println!("  {}", difference);
+            }
+            println!();
+            let shown = &divergence.context[divergence.context.len().saturating_sub(context)..];
+            let first = i - shown.len() as u64;
+            for (j, record) in shown.iter().enumerate() {
+                println!("  {:>8}  {}", first + j as u64, describe(&table, record));
+            }
+            println!("< {:>8}  {}", i, describe(&table, &divergence.ours));
+            println!("> {:>8}  {}", i, describe(&table, &divergence.theirs));
+            process::exit(1);
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn open(path: &str) -> BufReader<File> {
+    match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::io::{self, Write};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FrameKind {
+    Call,
+    Restart,
+    Interrupt
+}
+
+/**
+ * One subroutine entry
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    /**
+     * Address of the CALL/RST, or the interrupted instruction for an interrupt
+     * */
+    pub call_site: u16,
+    pub target: u16,
+    pub return_addr: u16,
+    /**
+     * Where the return address was pushed
+     * */
+    pub sp: u16
+}
+
+/**
+ * A return that didn't go back to the innermost frame
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /**
+     * Address of the RET/Rcc
+     * */
+    pub pc: u16,
+    /**
+     * Where it actually returned to
+     * */
+    pub return_addr: u16,
+    /**
+     * The innermost frame at the time, None if the shadow stack was empty
+     * */
+    pub expected: Option<Frame>
+}
+
+/**
+ * Shadow call stack kept by the CPU alongside the real one (see `Cpu8080::call_stack`). Frames are pushed on CALL,
+ * taken Ccc, RST and interrupts and popped on RET and taken Rcc. Code that moves SP on its own (SPHL, LXI SP, a
+ * POP of a return address) leaves stale frames behind, so a return drops every frame whose slot SP has moved past.
+ * */
+#[derive(Clone, Debug, Default)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+    pub mismatches: Vec<Mismatch>
+}
+
+impl CallStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Frames from outermost to innermost
+     * */
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.mismatches.clear();
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    /**
+     * Drops the innermost frames down to depth
+     * */
+    pub fn truncate(&mut self, depth: usize) {
+        self.frames.truncate(depth);
+    }
+
+    /**
+     * Records the return executed at pc, which left the CPU at return_addr with sp
+     * */
+    pub fn on_return(&mut self, pc: u16, return_addr: u16, sp: u16) {
+        let expected = self.frames.last().copied();
+        let matched = expected.is_some_and(|f| f.sp == sp.wrapping_sub(2) && f.return_addr == return_addr);
+        if !matched {
+            self.mismatches.push(Mismatch { pc, return_addr, expected });
+        }
+        self.frames.retain(|f| f.sp >= sp);
+    }
+
+    /**
+     * Writes one line per frame, innermost first, starting with the current pc. symbol names an address, e.g.
+     * "PRINT+$3", and addresses it can't name are shown in hex only
+     * */
+    pub fn write_backtrace<W: Write>(&self, sink: &mut W, pc: u16, symbol: impl Fn(u16) -> Option<String>) -> io::Result<()> {
+        let name = |addr: u16| symbol(addr).map(|s| format!("  {}", s)).unwrap_or_default();
+        writeln!(sink, "#0  {:04X}{}", pc, name(pc))?;
+        for (i, frame) in self.frames.iter().rev().enumerate() {
+            let how = match frame.kind {
+                FrameKind::Call => {
+                    let target = symbol(frame.target).unwrap_or_else(|| format!("{:04X}", frame.target));
+                    format!("called {} from {:04X}", target, frame.call_site)
+                },
+                FrameKind::Restart => format!("RST {} from {:04X}", frame.target >> 3, frame.call_site),
+                FrameKind::Interrupt => format!("interrupt RST {}", frame.target >> 3)
+            };
+            writeln!(sink, "#{:<2} {:04X}{}  ({})", i + 1, frame.return_addr, name(frame.return_addr), how)?;
+        }
+        Ok(())
+    }
+}
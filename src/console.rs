@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+use std::io::{BufReader, Read, Stdout, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/**
+ * A byte oriented terminal, the shape shared by the CP/M BIOS console and serial boards
+ * */
+pub trait Console {
+    /**
+     * True when a byte is waiting to be read
+     * */
+    fn status(&mut self) -> bool;
+
+    /**
+     * Blocks until a byte is available, None once input is exhausted
+     * */
+    fn read(&mut self) -> Option<u8>;
+
+    fn write(&mut self, byte: u8);
+
+    /**
+     * True once input has run out for good, nothing is waiting and nothing more will arrive
+     * */
+    fn at_eof(&mut self) -> bool {
+        false
+    }
+}
+
+/**
+ * Console on a pair of host byte streams, e.g. a pty or socket. The reader is drained on a background
+ * thread so status() never blocks
+ * */
+pub struct StreamConsole<W: Write> {
+    input: Receiver<u8>,
+    output: W,
+    pending: Option<u8>,
+    eof: bool
+}
+
+/**
+ * Console on the host's stdin/stdout, line feeds are handed to the guest as carriage returns
+ * */
+pub type StdioConsole = StreamConsole<Stdout>;
+
+impl StdioConsole {
+    pub fn new() -> Self {
+        Self::spawn(std::io::stdin(), std::io::stdout(), true)
+    }
+}
+
+impl Default for StdioConsole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> StreamConsole<W> {
+    pub fn from_streams<R: Read + Send + 'static>(reader: R, writer: W) -> Self {
+        Self::spawn(reader, writer, false)
+    }
+
+    fn spawn<R: Read + Send + 'static>(reader: R, writer: W, lf_to_cr: bool) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for byte in BufReader::new(reader).bytes() {
+                let byte = match byte {
+                    Ok(b'\n') if lf_to_cr => b'\r',
+                    Ok(b) => b,
+                    Err(_) => break
+                };
+                if tx.send(byte).is_err() {
+                    break;
+                }
+            }
+        });
+
+        StreamConsole { input: rx, output: writer, pending: None, eof: false }
+    }
+}
+
+impl<W: Write> Console for StreamConsole<W> {
+    fn status(&mut self) -> bool {
+        if self.pending.is_none() && !self.eof {
+            match self.input.try_recv() {
+                Ok(b) => self.pending = Some(b),
+                Err(TryRecvError::Disconnected) => self.eof = true,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        self.pending.is_some()
+    }
+
+    fn read(&mut self) -> Option<u8> {
+        if let Some(b) = self.pending.take() {
+            return Some(b);
+        }
+        if self.eof {
+            return None;
+        }
+        match self.input.recv() {
+            Ok(b) => Some(b),
+            Err(_) => { self.eof = true; None }
+        }
+    }
+
+    fn write(&mut self, byte: u8) {
+        let _ = self.output.write_all(&[byte]);
+        let _ = self.output.flush();
+    }
+
+    fn at_eof(&mut self) -> bool {
+        !self.status() && self.eof
+    }
+}
+
+/**
+ * In-memory console, input is queued up front and everything written is collected in output
+ * */
+#[derive(Clone, Debug, Default)]
+pub struct BufferConsole {
+    pub input: VecDeque<u8>,
+    pub output: Vec<u8>
+}
+
+impl BufferConsole {
+    pub fn new(input: &[u8]) -> Self {
+        BufferConsole { input: input.iter().copied().collect(), output: Vec::new() }
+    }
+
+    pub fn output_string(&self) -> String {
+        String::from_utf8_lossy(&self.output).into_owned()
+    }
+}
+
+impl Console for BufferConsole {
+    fn status(&mut self) -> bool {
+        !self.input.is_empty()
+    }
+
+    fn read(&mut self) -> Option<u8> {
+        self.input.pop_front()
+    }
+
+    fn write(&mut self, byte: u8) {
+        self.output.push(byte);
+    }
+
+    fn at_eof(&mut self) -> bool {
+        self.input.is_empty()
+    }
+}
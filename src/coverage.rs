@@ -0,0 +1,214 @@
+use crate::{Cpu8080, AccessKind};
+use crate::io::IoBus;
+use crate::symbols::SymbolTable;
+use std::fs;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/**
+ * Bits of a coverage map entry
+ * */
+pub const OPCODE: u8 = 0x01;
+pub const OPERAND: u8 = 0x02;
+pub const READ: u8 = 0x04;
+pub const WRITE: u8 = 0x08;
+
+// bytes per DB line in a listing
+const DATA_PER_LINE: usize = 8;
+// untouched runs of $00 or $FF at least this long are listed as one line
+const MIN_FILL: usize = 16;
+
+/**
+ * How every address of the 64K has been used: fetched as an opcode, fetched as an operand, read as data or
+ * written. Data accesses are the ones `Cpu8080::memory_log` sees, so memory touched by host traps doesn't count.
+ *
+ * The map saves as 65536 bytes, one per address holding the bits above, so maps from several runs can be merged
+ * or read by other tools.
+ * */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Coverage {
+    map: Vec<u8>
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage { map: vec![0; Cpu8080::MEMORY_SIZE] }
+    }
+
+    /**
+     * Marks the opcode and operands of the instruction at pc and turns on the CPU's memory log. Call it right
+     * before executing each instruction, then end after.
+     *
+     * Under a debugger with watchpoints, both clear the same log before the instruction and read it after, so
+     * called from `Machine::step` they see the same accesses. Removing the last watchpoint turns the log off, the
+     * next begin turns it back on
+     * */
+    pub fn begin(&mut self, cpu: &mut Cpu8080) {
+        if cpu.halted {
+            return;
+        }
+        let opcode = cpu.memory[cpu.pc as usize];
+        let size = cpu.opcode_table.get(opcode).map_or(1, |instruction| instruction.size);
+        self.map[cpu.pc as usize] |= OPCODE;
+        for i in 1..size as u16 {
+            self.map[cpu.pc.wrapping_add(i) as usize] |= OPERAND;
+        }
+        cpu.memory_log.get_or_insert_with(Vec::new).clear();
+    }
+
+    /**
+     * Marks the data the instruction read and wrote
+     * */
+    pub fn end(&mut self, cpu: &Cpu8080) {
+        for access in cpu.memory_log.iter().flatten() {
+            self.map[access.addr as usize] |= match access.kind {
+                AccessKind::Read => READ,
+                AccessKind::Write => WRITE
+            };
+        }
+    }
+
+    /**
+     * Records then executes one instruction
+     * */
+    pub fn step(&mut self, cpu: &mut Cpu8080, io: &mut dyn IoBus) -> u32 {
+        self.begin(cpu);
+        let cycles = cpu.step(io);
+        self.end(cpu);
+        cycles
+    }
+
+    pub fn get(&self, addr: u16) -> u8 {
+        self.map[addr as usize]
+    }
+
+    /**
+     * Number of addresses with any of the bits in mask
+     * */
+    pub fn count(&self, mask: u8) -> usize {
+        self.map.iter().filter(|&&bits| bits & mask != 0).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.iter_mut().for_each(|bits| *bits = 0);
+    }
+
+    /**
+     * Adds the coverage of another run
+     * */
+    pub fn merge(&mut self, other: &Coverage) {
+        for (bits, other) in self.map.iter_mut().zip(other.map.iter()) {
+            *bits |= other;
+        }
+    }
+
+    /**
+     * Runs of consecutive addresses with the same bits, untouched memory left out
+     * */
+    pub fn ranges(&self) -> Vec<(RangeInclusive<u16>, u8)> {
+        let mut ranges: Vec<(RangeInclusive<u16>, u8)> = Vec::new();
+        for (addr, &bits) in self.map.iter().enumerate() {
+            let addr = addr as u16;
+            match ranges.last_mut() {
+                Some((range, last)) if *last == bits && *range.end() == addr.wrapping_sub(1) => *range = *range.start()..=addr,
+                _ if bits == 0 => {},
+                _ => ranges.push((addr..=addr, bits))
+            }
+        }
+        ranges
+    }
+
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() != Cpu8080::MEMORY_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("coverage map is {} bytes, expected {}", bytes.len(), Cpu8080::MEMORY_SIZE)));
+        }
+        Ok(Coverage { map: bytes.to_vec() })
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, &self.map)
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /**
+     * Writes the ranges as `0100-01FF  X...` lines, see bit_string
+     * */
+    pub fn write_ranges<W: Write>(&self, sink: &mut W) -> io::Result<()> {
+        for (range, bits) in self.ranges() {
+            writeln!(sink, "{:04X}-{:04X}  {}", range.start(), range.end(), bit_string(bits))?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Writes a disassembly of range annotated with the coverage of every line. Executed instructions and
+     * memory nobody touched disassemble as code, the latter marked `!` as never executed; memory only used
+     * as data is listed as DB bytes, and long untouched runs of $00 or $FF as a single line.
+     *
+     *  0100  C3 AB 01  X...    JMP START
+     *  0103  3E 00     !       MVI A,$00
+     *  0200  01 02 03  ..R.    DB $01,$02,$03
+     * */
+    pub fn write_listing<W: Write>(&self, sink: &mut W, cpu: &Cpu8080, symbols: &SymbolTable, range: RangeInclusive<u16>) -> io::Result<()> {
+        let (start, end) = (*range.start() as usize, *range.end() as usize);
+        let mut addr = start;
+        while addr <= end {
+            if let Some(name) = symbols.name_at(addr as u16) {
+                writeln!(sink, "{}:", name)?;
+            }
+            let bits = self.map[addr];
+            let fill = cpu.memory[addr];
+            if bits == 0 && (fill == 0x00 || fill == 0xFF) {
+                let run = (addr..=end)
+                    .take_while(|&a| self.map[a] == 0 && cpu.memory[a] == fill && (a == addr || symbols.name_at(a as u16).is_none()))
+                    .count();
+                if run >= MIN_FILL {
+                    writeln!(sink, "{:04X}-{:04X}  untouched ${:02X}", addr, addr + run - 1, fill)?;
+                    addr += run;
+                    continue;
+                }
+            }
+            let is_data = bits & (OPCODE | OPERAND) == 0 && bits & (READ | WRITE) != 0;
+            let (size, text) = if is_data {
+                let run = (addr..=end)
+                    .take(DATA_PER_LINE)
+                    .take_while(|&a| self.map[a] == bits && (a == addr || symbols.name_at(a as u16).is_none()))
+                    .count();
+                let bytes: Vec<String> = cpu.memory[addr..addr + run].iter().map(|b| format!("${:02X}", b)).collect();
+                (run, format!("DB {}", bytes.join(",")))
+            } else {
+                let (text, size) = cpu.opcode_table.disassemble_symbolic(&cpu.memory, addr as u16, symbols);
+                (size as usize, text)
+            };
+
+            let bytes: Vec<String> = (0..size.min(3)).map(|i| format!("{:02X}", cpu.memory[(addr + i) % Cpu8080::MEMORY_SIZE])).collect();
+            let coverage = if bits == 0 { "!".to_string() } else { bit_string(bits) };
+            writeln!(sink, "{:04X}  {:<8}  {:<6}  {}", addr, bytes.join(" "), coverage, text)?;
+            addr += size;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * The bits as `XORW`, with a dot for every clear bit: eXecuted as an opcode, Operand, Read, Written
+ * */
+pub fn bit_string(bits: u8) -> String {
+    [(OPCODE, 'X'), (OPERAND, 'O'), (READ, 'R'), (WRITE, 'W')].iter()
+        .map(|&(bit, c)| if bits & bit != 0 { c } else { '.' })
+        .collect()
+}
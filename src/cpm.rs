@@ -0,0 +1,444 @@
+pub mod disk;
+pub mod hostfs;
+
+use crate::Cpu8080;
+use crate::console::Console;
+use crate::debugger::Machine;
+use crate::io::NullBus;
+use crate::savestate::{SaveState, Snapshot, StateWriter, invalid};
+use disk::{DiskImage, SECTOR_SIZE, SECTORS_PER_TRACK};
+use hostfs::HostFs;
+use std::io;
+use std::path::PathBuf;
+
+pub const DRIVES: usize = 16;
+
+pub const CCP_SIZE: u16 = 0x800;
+pub const BDOS_SIZE: u16 = 0xE00;
+
+/**
+ * CCP base of a standard 64K CP/M 2.2 system, the BDOS and BIOS follow directly after it
+ * */
+pub const DEFAULT_CCP_BASE: u16 = 0xE400;
+
+pub const DEFAULT_DMA: u16 = 0x0080;
+
+// CCP + BDOS as stored on the system tracks, starting at track 0 sector 2 after the cold start loader
+const SYSTEM_SECTORS: u16 = (CCP_SIZE + BDOS_SIZE) / SECTOR_SIZE as u16;
+
+// Layout of the emulated BIOS image, as offsets from the BIOS base
+const JUMP_TABLE_ENTRIES: u16 = 17;
+const XLT_OFFSET: u16 = 0x40;
+const DPB_OFFSET: u16 = 0x60;
+const DIRBUF_OFFSET: u16 = 0x80;
+const DPH_OFFSET: u16 = 0x100;
+const CSV_OFFSET: u16 = 0x200;
+const ALV_OFFSET: u16 = 0x300;
+const BIOS_SIZE: u16 = ALV_OFFSET + DRIVES as u16 * 32;
+
+/**
+ * Highest CCP base the CCP, BDOS and BIOS still fit below the top of memory with
+ * */
+pub const MAX_CCP_BASE: u16 = (0x10000 - (CCP_SIZE + BDOS_SIZE + BIOS_SIZE) as u32) as u16;
+
+// 1-based sector skew table of the standard IBM 8" SSSD format
+const SKEW_TABLE: [u8; SECTORS_PER_TRACK] = [
+    1, 7, 13, 19, 25, 5, 11, 17, 23, 3, 9, 15, 21,
+    2, 8, 14, 20, 26, 6, 12, 18, 24, 4, 10, 16, 22
+];
+
+// Disk parameter block of the standard IBM 8" SSSD format
+// SPT, BSH, BLM, EXM, DSM, DRM, AL0, AL1, CKS, OFF
+const SSSD_DPB: [u8; 15] = [
+    26, 0,
+    3,
+    7,
+    0,
+    242, 0,
+    63, 0,
+    0xC0,
+    0x00,
+    16, 0,
+    2, 0
+];
+
+/**
+ * Entries of the BIOS jump table, in table order
+ * */
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BiosCall {
+    Boot = 0,
+    WBoot,
+    Const,
+    ConIn,
+    ConOut,
+    List,
+    Punch,
+    Reader,
+    Home,
+    SelDsk,
+    SetTrk,
+    SetSec,
+    SetDma,
+    Read,
+    Write,
+    ListSt,
+    SecTran
+}
+
+impl BiosCall {
+    pub fn from_index(index: u16) -> Option<Self> {
+        use BiosCall::*;
+        let call = match index {
+            0 => Boot, 1 => WBoot, 2 => Const, 3 => ConIn,
+            4 => ConOut, 5 => List, 6 => Punch, 7 => Reader,
+            8 => Home, 9 => SelDsk, 10 => SetTrk, 11 => SetSec,
+            12 => SetDma, 13 => Read, 14 => Write, 15 => ListSt,
+            16 => SecTran,
+            _ => return None
+        };
+        Some(call)
+    }
+}
+
+/**
+ * A CP/M 2.2 system: the real CCP and BDOS loaded from the system tracks of drive A, running on top of a BIOS
+ * implemented here in Rust. The BIOS jump table lives in guest memory but each entry is intercepted before it executes.
+ * */
+pub struct CpmMachine<C: Console> {
+    pub cpu: Cpu8080,
+    pub console: C,
+    pub drives: Vec<Option<DiskImage>>,
+    pub host_fs: HostFs,
+
+    ccp_base: u16,
+
+    disk: u8,
+    track: u16,
+    sector: u16,
+    dma: u16
+}
+
+impl<C: Console> CpmMachine<C> {
+    pub fn new(console: C) -> Self {
+        Self::with_ccp_base(console, DEFAULT_CCP_BASE)
+    }
+
+    /**
+     * System built for a different memory size, ccp_base must match the CCP/BDOS stored on the boot disk.
+     * Panics if ccp_base is above MAX_CCP_BASE
+     * */
+    pub fn with_ccp_base(console: C, ccp_base: u16) -> Self {
+        assert!(ccp_base <= MAX_CCP_BASE, "CCP base {:04X} leaves no room for the BDOS and BIOS, the highest is {:04X}", ccp_base, MAX_CCP_BASE);
+        let mut machine = CpmMachine {
+            cpu: Cpu8080::new(),
+            console,
+            drives: (0..DRIVES).map(|_| None).collect(),
+            host_fs: HostFs::new(),
+            ccp_base,
+            disk: 0,
+            track: 0,
+            sector: 1,
+            dma: DEFAULT_DMA
+        };
+        machine.install_bios();
+        machine
+    }
+
+    pub fn ccp_base(&self) -> u16 {
+        self.ccp_base
+    }
+
+    pub fn bdos_base(&self) -> u16 {
+        self.ccp_base + CCP_SIZE
+    }
+
+    /**
+     * Address called by programs through CALL 5
+     * */
+    pub fn bdos_entry(&self) -> u16 {
+        self.bdos_base() + 6
+    }
+
+    pub fn bios_base(&self) -> u16 {
+        self.bdos_base() + BDOS_SIZE
+    }
+
+    pub fn mount(&mut self, drive: usize, image: DiskImage) {
+        self.drives[drive] = Some(image);
+    }
+
+    pub fn unmount(&mut self, drive: usize) -> Option<DiskImage> {
+        self.drives[drive].take()
+    }
+
+    /**
+     * Maps a drive onto a host directory, see `HostFs`. Drive A still has to hold a boot image
+     * */
+    pub fn mount_dir<P: Into<PathBuf>>(&mut self, drive: usize, dir: P) {
+        self.host_fs.map(drive, dir.into());
+    }
+
+    /**
+     * Writes the jump table, skew table, DPB and a DPH per drive into the BIOS area.
+     * Jump table entries jump to themselves, they never execute since step() intercepts them.
+     * */
+    fn install_bios(&mut self) {
+        let bios = self.bios_base();
+
+        for i in 0..JUMP_TABLE_ENTRIES {
+            let entry = bios + i * 3;
+            self.cpu.memory[entry as usize] = 0xC3;
+            self.cpu.write_u16(entry + 1, entry);
+        }
+
+        self.cpu.load(bios + XLT_OFFSET, &SKEW_TABLE);
+        self.cpu.load(bios + DPB_OFFSET, &SSSD_DPB);
+
+        for drive in 0..DRIVES as u16 {
+            let dph = bios + DPH_OFFSET + drive * 16;
+            self.cpu.write_u16(dph, bios + XLT_OFFSET);
+            self.cpu.load(dph + 2, &[0; 6]);
+            self.cpu.write_u16(dph + 8, bios + DIRBUF_OFFSET);
+            self.cpu.write_u16(dph + 10, bios + DPB_OFFSET);
+            self.cpu.write_u16(dph + 12, bios + CSV_OFFSET + drive * 16);
+            self.cpu.write_u16(dph + 14, bios + ALV_OFFSET + drive * 32);
+        }
+    }
+
+    /**
+     * Cold start: loads the CCP and BDOS from drive A and jumps into the CCP on drive A
+     * */
+    pub fn boot(&mut self) -> io::Result<()> {
+        self.load_system()?;
+        self.cpu.memory[0x0003] = 0;
+        self.cpu.memory[0x0004] = 0;
+        self.go_cpm();
+        Ok(())
+    }
+
+    fn load_system(&mut self) -> io::Result<()> {
+        let image = self.drives[0].as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no disk in drive A to boot from"))?;
+
+        for i in 0..SYSTEM_SECTORS {
+            let linear = i as usize + 1;
+            let track = (linear / SECTORS_PER_TRACK) as u16;
+            let sector = (linear % SECTORS_PER_TRACK) as u16 + 1;
+            let data = image.read_sector(track, sector)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "system tracks are truncated"))?;
+            let addr = self.ccp_base + i * SECTOR_SIZE as u16;
+            self.cpu.memory[addr as usize..addr as usize + SECTOR_SIZE].copy_from_slice(data);
+        }
+        Ok(())
+    }
+
+    /**
+     * Sets up page zero and enters the CCP, shared by cold and warm boot
+     * */
+    fn go_cpm(&mut self) {
+        let bios = self.bios_base();
+        let bdos = self.bdos_entry();
+
+        self.cpu.memory[0x0000] = 0xC3;
+        self.cpu.write_u16(0x0001, bios + 3);
+        self.cpu.memory[0x0005] = 0xC3;
+        self.cpu.write_u16(0x0006, bdos);
+
+        self.dma = DEFAULT_DMA;
+        self.cpu.sp = DEFAULT_DMA;
+        self.cpu.c = self.cpu.memory[0x0004];
+        self.cpu.halted = false;
+        self.cpu.jmp(self.ccp_base);
+    }
+
+    /**
+     * BIOS call about to be made if pc is on a jump table entry
+     * */
+    pub fn pending_bios_call(&self) -> Option<BiosCall> {
+        let offset = self.cpu.pc.wrapping_sub(self.bios_base());
+        if offset < JUMP_TABLE_ENTRIES * 3 && offset.is_multiple_of(3) {
+            BiosCall::from_index(offset / 3)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Executes one instruction, or one whole BIOS call when pc is on the jump table. Returns the cycles taken
+     * */
+    pub fn step(&mut self) -> u32 {
+        if let Some(call) = self.pending_bios_call() {
+            self.bios(call);
+            self.cpu.cycles += 10;
+            return 10;
+        }
+        if self.cpu.pc == self.bdos_entry() && self.host_fs.bdos(&mut self.cpu, self.dma) {
+            self.cpu.trap_return();
+            self.cpu.cycles += 10;
+            return 10;
+        }
+        self.cpu.step(&mut NullBus)
+    }
+
+    /**
+     * Runs until the CPU halts, which also happens once console input runs out
+     * */
+    pub fn run(&mut self) {
+        while !self.cpu.halted {
+            self.step();
+        }
+    }
+
+    fn bios(&mut self, call: BiosCall) {
+        use BiosCall::*;
+        match call {
+            Boot => {
+                if self.boot().is_err() {
+                    self.cpu.halted = true;
+                }
+                return;
+            },
+            WBoot => {
+                if self.load_system().is_err() {
+                    self.cpu.halted = true;
+                    return;
+                }
+                self.go_cpm();
+                return;
+            },
+            Const => {
+                self.cpu.a = if self.console.status() { 0xFF } else { 0x00 };
+            },
+            ConIn => {
+                match self.console.read() {
+                    Some(b) => self.cpu.a = b & 0x7F,
+                    None => {
+                        self.cpu.halted = true;
+                        return;
+                    }
+                }
+            },
+            ConOut => self.console.write(self.cpu.c),
+            List | Punch => {},
+            Reader => self.cpu.a = 0x1A,
+            Home => self.track = 0,
+            SelDsk => {
+                let disk = self.cpu.c as usize;
+                let dph = if disk < DRIVES && self.drives[disk].is_some() {
+                    self.disk = disk as u8;
+                    self.bios_base() + DPH_OFFSET + disk as u16 * 16
+                } else {
+                    0
+                };
+                self.cpu.set_hl(dph);
+            },
+            SetTrk => self.track = self.cpu.bc(),
+            SetSec => self.sector = self.cpu.bc(),
+            SetDma => self.dma = self.cpu.bc(),
+            Read => {
+                self.cpu.a = if self.read_sector() { 0 } else { 1 };
+            },
+            Write => {
+                self.cpu.a = if self.write_sector() { 0 } else { 1 };
+            },
+            ListSt => self.cpu.a = 0xFF,
+            SecTran => {
+                let sector = self.cpu.bc();
+                let table = self.cpu.de();
+                let translated = if table == 0 {
+                    sector + 1
+                } else {
+                    self.cpu.memory[table.wrapping_add(sector) as usize] as u16
+                };
+                self.cpu.set_hl(translated);
+            }
+        }
+        self.cpu.trap_return();
+    }
+
+    fn read_sector(&mut self) -> bool {
+        let data = match &self.drives[self.disk as usize] {
+            Some(image) => image.read_sector(self.track, self.sector),
+            None => None
+        };
+        match data {
+            Some(data) => {
+                let data = data.to_vec();
+                self.cpu.load(self.dma, &data);
+                true
+            },
+            None => false
+        }
+    }
+
+    fn write_sector(&mut self) -> bool {
+        let data: Vec<u8> = (0..SECTOR_SIZE as u16)
+            .map(|i| self.cpu.memory[self.dma.wrapping_add(i) as usize])
+            .collect();
+        match &mut self.drives[self.disk as usize] {
+            Some(image) => image.write_sector(self.track, self.sector, &data),
+            None => false
+        }
+    }
+}
+
+impl<C: Console> Machine for CpmMachine<C> {
+    fn cpu(&self) -> &Cpu8080 {
+        &self.cpu
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu8080 {
+        &mut self.cpu
+    }
+
+    fn step(&mut self) -> u32 {
+        CpmMachine::step(self)
+    }
+}
+
+const CPM: [u8; 4] = *b"CP/M";
+
+/**
+ * CPU and memory, the BIOS's disk selection and DMA address, and the contents of every drive. Host directories
+ * mapped through host_fs aren't part of it
+ * */
+impl<C: Console> Snapshot for CpmMachine<C> {
+    fn save_state(&self, state: &mut SaveState) {
+        self.cpu.save_state(state);
+        let mut cpm = StateWriter::new();
+        cpm.u16(self.ccp_base).u8(self.disk).u16(self.track).u16(self.sector).u16(self.dma)
+            .u8(self.drives.len() as u8);
+        for drive in self.drives.iter() {
+            disk::save_drive(&mut cpm, drive.as_ref());
+        }
+        state.put(CPM, cpm.into_inner());
+    }
+
+    fn load_state(&mut self, state: &SaveState) -> io::Result<()> {
+        let mut cpm = state.reader(CPM)?;
+        let ccp_base = cpm.u16()?;
+        if ccp_base != self.ccp_base {
+            return Err(invalid(format!("save state is of a system with the CCP at {:04X}, not {:04X}", ccp_base, self.ccp_base)));
+        }
+        let (disk, track, sector, dma) = (cpm.u8()?, cpm.u16()?, cpm.u16()?, cpm.u16()?);
+        let drives = cpm.u8()? as usize;
+        if drives != self.drives.len() {
+            return Err(invalid(format!("save state has {} drives, expected {}", drives, self.drives.len())));
+        }
+        let geometry = |drive: &Option<DiskImage>| drive.as_ref().map_or(disk::IBM_SSSD, |image| image.geometry());
+        let images = self.drives.iter().map(|drive| Ok((disk::read_drive(&mut cpm, geometry(drive))?, geometry(drive))))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        self.cpu.load_state(state)?;
+        self.disk = disk;
+        self.track = track;
+        self.sector = sector;
+        self.dma = dma;
+        for (drive, (image, geometry)) in self.drives.iter_mut().zip(images) {
+            disk::restore_drive(drive, image, geometry)?;
+        }
+        Ok(())
+    }
+}
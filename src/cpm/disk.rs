@@ -0,0 +1,207 @@
+use crate::savestate::{StateReader, StateWriter};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const TRACKS: usize = 77;
+pub const SECTORS_PER_TRACK: usize = 26;
+pub const SECTOR_SIZE: usize = 128;
+pub const IMAGE_SIZE: usize = TRACKS * SECTORS_PER_TRACK * SECTOR_SIZE;
+
+/**
+ * Byte value of a freshly formatted sector, which CP/M also reads as an empty directory entry
+ * */
+pub const FORMAT_FILL: u8 = 0xE5;
+
+/**
+ * Layout of a raw image: tracks of equally sized sectors stored back to back in physical order
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Geometry {
+    pub tracks: usize,
+    pub sectors_per_track: usize,
+    pub sector_size: usize,
+    /**
+     * Number of the first sector on a track, 1 for IBM formats and 0 for hard sectored Altair disks
+     * */
+    pub first_sector: u16
+}
+
+impl Geometry {
+    pub const fn image_size(&self) -> usize {
+        self.tracks * self.sectors_per_track * self.sector_size
+    }
+}
+
+/**
+ * IBM 8" single sided, single density: 77 tracks of 26 x 128 byte sectors, numbered from 1
+ * */
+pub const IBM_SSSD: Geometry = Geometry {
+    tracks: TRACKS,
+    sectors_per_track: SECTORS_PER_TRACK,
+    sector_size: SECTOR_SIZE,
+    first_sector: 1
+};
+
+/**
+ * A raw .dsk image, by default in the IBM 8" SSSD format the CP/M BIOS expects.
+ * Writes go through to the backing file when the image was opened from one.
+ * */
+pub struct DiskImage {
+    geometry: Geometry,
+    data: Vec<u8>,
+    file: Option<File>,
+    read_only: bool
+}
+
+/**
+ * Saves the image in a drive, or that the drive is empty
+ * */
+pub fn save_drive(state: &mut StateWriter, image: Option<&DiskImage>) {
+    state.bool(image.is_some());
+    if let Some(image) = image {
+        state.blob(image.as_bytes());
+    }
+}
+
+/**
+ * Reads what save_drive wrote, None for an empty drive
+ * */
+pub fn read_drive<'a>(state: &mut StateReader<'a>, geometry: Geometry) -> io::Result<Option<&'a [u8]>> {
+    if !state.bool()? {
+        return Ok(None);
+    }
+    let data = state.blob()?;
+    if data.len() != geometry.image_size() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "disk image in save state has the wrong size"));
+    }
+    Ok(Some(data))
+}
+
+/**
+ * Puts a drive back as read_drive found it, mounting an in-memory image if nothing is mounted
+ * */
+pub fn restore_drive(drive: &mut Option<DiskImage>, data: Option<&[u8]>, geometry: Geometry) -> io::Result<()> {
+    match (drive.as_mut(), data) {
+        (_, None) => *drive = None,
+        (Some(image), Some(data)) => image.restore(data)?,
+        (None, Some(data)) => *drive = Some(DiskImage::from_bytes_with(data.to_vec(), geometry))
+    }
+    Ok(())
+}
+
+impl DiskImage {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_with(path, IBM_SSSD)
+    }
+
+    /**
+     * Opens an image file, read only if it can't be opened for writing. Short images are padded as if freshly formatted
+     * */
+    pub fn open_with<P: AsRef<Path>>(path: P, geometry: Geometry) -> io::Result<Self> {
+        let path = path.as_ref();
+        let (mut file, read_only) = match OpenOptions::new().read(true).write(true).open(path) {
+            Ok(f) => (f, false),
+            Err(_) => (File::open(path)?, true)
+        };
+
+        let size = geometry.image_size();
+        let mut data = Vec::with_capacity(size);
+        file.read_to_end(&mut data)?;
+        if data.len() > size {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("{} is {} bytes, larger than the disk format ({} bytes)", path.display(), data.len(), size)));
+        }
+        data.resize(size, FORMAT_FILL);
+
+        Ok(DiskImage { geometry, data, file: Some(file), read_only })
+    }
+
+    /**
+     * A formatted, empty disk that only lives in memory
+     * */
+    pub fn blank() -> Self {
+        Self::blank_with(IBM_SSSD)
+    }
+
+    pub fn blank_with(geometry: Geometry) -> Self {
+        DiskImage { geometry, data: vec![FORMAT_FILL; geometry.image_size()], file: None, read_only: false }
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self::from_bytes_with(data, IBM_SSSD)
+    }
+
+    pub fn from_bytes_with(mut data: Vec<u8>, geometry: Geometry) -> Self {
+        data.resize(geometry.image_size(), FORMAT_FILL);
+        DiskImage { geometry, data, file: None, read_only: false }
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn offset(&self, track: u16, sector: u16) -> Option<usize> {
+        let g = &self.geometry;
+        let sector = sector.checked_sub(g.first_sector)? as usize;
+        let track = track as usize;
+        if track >= g.tracks || sector >= g.sectors_per_track {
+            return None;
+        }
+        Some((track * g.sectors_per_track + sector) * g.sector_size)
+    }
+
+    /**
+     * Replaces the whole image, e.g. from a save state, writing it through to the backing file
+     * */
+    pub fn restore(&mut self, data: &[u8]) -> io::Result<()> {
+        if data.len() != self.data.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("disk image is {} bytes, expected {}", data.len(), self.data.len())));
+        }
+        if self.data == data {
+            return Ok(());
+        }
+        self.data.copy_from_slice(data);
+        if let (Some(file), false) = (&mut self.file, self.read_only) {
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&self.data)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_sector(&self, track: u16, sector: u16) -> Option<&[u8]> {
+        let offset = self.offset(track, sector)?;
+        Some(&self.data[offset..offset + self.geometry.sector_size])
+    }
+
+    /**
+     * Writes one sector, returns false for a bad track/sector, a read only image or a failed write to the backing file
+     * */
+    pub fn write_sector(&mut self, track: u16, sector: u16, bytes: &[u8]) -> bool {
+        let offset = match self.offset(track, sector) {
+            Some(offset) if !self.read_only => offset,
+            _ => return false
+        };
+
+        let sector_size = self.geometry.sector_size;
+        let len = bytes.len().min(sector_size);
+        self.data[offset..offset + len].copy_from_slice(&bytes[..len]);
+
+        if let Some(file) = &mut self.file {
+            let sector = &self.data[offset..offset + sector_size];
+            if file.seek(SeekFrom::Start(offset as u64)).and_then(|_| file.write_all(sector)).is_err() {
+                return false;
+            }
+        }
+        true
+    }
+}
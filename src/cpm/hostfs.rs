@@ -0,0 +1,420 @@
+use crate::Cpu8080;
+use super::{DRIVES, disk::SECTOR_SIZE};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const RECORD_SIZE: u64 = SECTOR_SIZE as u64;
+const RECORDS_PER_EXTENT: u64 = 128;
+const DIR_ENTRY_SIZE: u16 = 32;
+
+// FCB layout
+const FCB_DRIVE: u16 = 0;
+const FCB_NAME: u16 = 1;
+const FCB_EXTENT: u16 = 12;
+const FCB_S2: u16 = 14;
+const FCB_RECORD_COUNT: u16 = 15;
+const FCB_CURRENT_RECORD: u16 = 32;
+const FCB_RANDOM_RECORD: u16 = 33;
+const FCB_RENAME: u16 = 16;
+
+const SUCCESS: u8 = 0x00;
+const NOT_FOUND: u8 = 0xFF;
+const END_OF_FILE: u8 = 0x01;
+const DISK_FULL: u8 = 0x02;
+const EOF_FILL: u8 = 0x1A;
+
+// BDOS functions handled for host mapped drives
+const RESET_DISK_SYSTEM: u8 = 13;
+const SELECT_DISK: u8 = 14;
+const OPEN_FILE: u8 = 15;
+const CLOSE_FILE: u8 = 16;
+const SEARCH_FIRST: u8 = 17;
+const SEARCH_NEXT: u8 = 18;
+const DELETE_FILE: u8 = 19;
+const READ_SEQUENTIAL: u8 = 20;
+const WRITE_SEQUENTIAL: u8 = 21;
+const MAKE_FILE: u8 = 22;
+const RENAME_FILE: u8 = 23;
+const CURRENT_DISK: u8 = 25;
+const READ_RANDOM: u8 = 33;
+const WRITE_RANDOM: u8 = 34;
+const COMPUTE_FILE_SIZE: u8 = 35;
+const SET_RANDOM_RECORD: u8 = 36;
+
+/**
+ * Maps CP/M drives onto host directories at the BDOS level. FCB based file calls for a mapped drive
+ * are answered from host files here, everything else falls through to the real BDOS.
+ * Host names are mangled to 8.3: upper cased, cut at the last '.', truncated, and characters CP/M can't
+ * hold replaced by '_'. Files created from CP/M are written lower case.
+ * */
+pub struct HostFs {
+    dirs: Vec<Option<PathBuf>>,
+    current: Option<u8>,
+    searching: bool,
+    search_results: Vec<[u8; DIR_ENTRY_SIZE as usize]>
+}
+
+impl Default for HostFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HostFs {
+    pub fn new() -> Self {
+        HostFs { dirs: vec![None; DRIVES], current: None, searching: false, search_results: Vec::new() }
+    }
+
+    pub fn map(&mut self, drive: usize, dir: PathBuf) {
+        self.dirs[drive] = Some(dir);
+    }
+
+    pub fn unmap(&mut self, drive: usize) -> Option<PathBuf> {
+        if self.current == Some(drive as u8) {
+            self.current = None;
+        }
+        self.dirs[drive].take()
+    }
+
+    pub fn is_mapped(&self, drive: usize) -> bool {
+        self.dirs.get(drive).is_some_and(|d| d.is_some())
+    }
+
+    /**
+     * Handles the BDOS call in C if it targets a mapped drive. Returns false to let the real BDOS run it.
+     * On true the result is in A/L (B/H cleared) and the caller still has to return to the program.
+     * */
+    pub fn bdos(&mut self, cpu: &mut Cpu8080, dma: u16) -> bool {
+        let function = cpu.c;
+        let fcb = cpu.de();
+
+        let result = match function {
+            RESET_DISK_SYSTEM => {
+                self.current = None;
+                return false;
+            },
+            SELECT_DISK => {
+                let drive = cpu.e & 0x0F;
+                if !self.is_mapped(drive as usize) {
+                    self.current = None;
+                    return false;
+                }
+                self.current = Some(drive);
+                cpu.memory[0x0004] = (cpu.memory[0x0004] & 0xF0) | drive;
+                SUCCESS
+            },
+            CURRENT_DISK => match self.current {
+                Some(drive) => drive,
+                None => return false
+            },
+            OPEN_FILE | CLOSE_FILE | SEARCH_FIRST | DELETE_FILE | READ_SEQUENTIAL | WRITE_SEQUENTIAL |
+            MAKE_FILE | RENAME_FILE | READ_RANDOM | WRITE_RANDOM | COMPUTE_FILE_SIZE | SET_RANDOM_RECORD => {
+                let dir = self.fcb_dir(cpu, fcb);
+                if function == SEARCH_FIRST {
+                    self.searching = dir.is_some();
+                }
+                let dir = match dir {
+                    Some(dir) => dir,
+                    None => return false
+                };
+                self.file_call(cpu, function, &dir, fcb, dma)
+            },
+            SEARCH_NEXT => {
+                if !self.searching {
+                    return false;
+                }
+                self.next_search_result(cpu, dma)
+            },
+            _ => return false
+        };
+
+        cpu.a = result;
+        cpu.l = result;
+        cpu.b = 0;
+        cpu.h = 0;
+        true
+    }
+
+    /**
+     * Host directory of the drive named by the FCB, 0 meaning the current drive
+     * */
+    fn fcb_dir(&self, cpu: &Cpu8080, fcb: u16) -> Option<PathBuf> {
+        let drive = match cpu.memory[fcb.wrapping_add(FCB_DRIVE) as usize] {
+            0 | b'?' => self.current.unwrap_or(cpu.memory[0x0004] & 0x0F),
+            d => (d - 1) & 0x0F
+        };
+        self.dirs[drive as usize].clone()
+    }
+
+    fn file_call(&mut self, cpu: &mut Cpu8080, function: u8, dir: &Path, fcb: u16, dma: u16) -> u8 {
+        let pattern = read_name(cpu, fcb.wrapping_add(FCB_NAME));
+
+        match function {
+            OPEN_FILE => match find(dir, &pattern).into_iter().next() {
+                Some((name, path)) => {
+                    cpu.load(fcb.wrapping_add(FCB_NAME), &name);
+                    set_record_count(cpu, fcb, &path);
+                    SUCCESS
+                },
+                None => NOT_FOUND
+            },
+            CLOSE_FILE => {
+                if find(dir, &pattern).is_empty() { NOT_FOUND } else { SUCCESS }
+            },
+            SEARCH_FIRST => {
+                let all_extents = cpu.memory[fcb.wrapping_add(FCB_EXTENT) as usize] == b'?';
+                let extent = cpu.memory[fcb.wrapping_add(FCB_EXTENT) as usize];
+                self.search_results = find(dir, &pattern).into_iter()
+                    .flat_map(|(name, path)| directory_entries(name, &path))
+                    .filter(|entry| all_extents || entry[FCB_EXTENT as usize] == extent)
+                    .collect();
+                self.search_results.reverse();
+                self.next_search_result(cpu, dma)
+            },
+            DELETE_FILE => {
+                let found = find(dir, &pattern);
+                let deleted = found.iter().filter(|(_, path)| fs::remove_file(path).is_ok()).count();
+                if deleted == 0 { NOT_FOUND } else { SUCCESS }
+            },
+            MAKE_FILE => {
+                if pattern.contains(&b'?') {
+                    return NOT_FOUND;
+                }
+                // never truncate a file that is already there, under this name or one mangling to it
+                if !find(dir, &pattern).is_empty() {
+                    return NOT_FOUND;
+                }
+                let path = dir.join(host_name(&pattern));
+                match OpenOptions::new().write(true).create_new(true).open(&path) {
+                    Ok(_) => {
+                        cpu.memory[fcb.wrapping_add(FCB_RECORD_COUNT) as usize] = 0;
+                        SUCCESS
+                    },
+                    Err(_) => NOT_FOUND
+                }
+            },
+            RENAME_FILE => {
+                let new_name = read_name(cpu, fcb.wrapping_add(FCB_RENAME + FCB_NAME));
+                match find(dir, &pattern).into_iter().next() {
+                    Some((_, path)) if !new_name.contains(&b'?') => {
+                        if fs::rename(&path, dir.join(host_name(&new_name))).is_ok() { SUCCESS } else { NOT_FOUND }
+                    },
+                    _ => NOT_FOUND
+                }
+            },
+            READ_SEQUENTIAL | WRITE_SEQUENTIAL | READ_RANDOM | WRITE_RANDOM => {
+                let path = match find(dir, &pattern).into_iter().next() {
+                    Some((_, path)) => path,
+                    None => return NOT_FOUND
+                };
+                let sequential = function == READ_SEQUENTIAL || function == WRITE_SEQUENTIAL;
+                let record = if sequential { sequential_record(cpu, fcb) } else { random_record(cpu, fcb) };
+                if !sequential {
+                    set_sequential_record(cpu, fcb, record);
+                }
+
+                let result = if function == READ_SEQUENTIAL || function == READ_RANDOM {
+                    read_record(cpu, &path, record, dma)
+                } else {
+                    write_record(cpu, &path, record, dma)
+                };
+                if result == SUCCESS && sequential {
+                    set_sequential_record(cpu, fcb, record + 1);
+                }
+                set_record_count(cpu, fcb, &path);
+                result
+            },
+            COMPUTE_FILE_SIZE => match find(dir, &pattern).into_iter().next() {
+                Some((_, path)) => {
+                    set_random_record(cpu, fcb, record_len(&path));
+                    SUCCESS
+                },
+                None => NOT_FOUND
+            },
+            SET_RANDOM_RECORD => {
+                let record = sequential_record(cpu, fcb);
+                set_random_record(cpu, fcb, record);
+                SUCCESS
+            },
+            _ => NOT_FOUND
+        }
+    }
+
+    fn next_search_result(&mut self, cpu: &mut Cpu8080, dma: u16) -> u8 {
+        match self.search_results.pop() {
+            Some(entry) => {
+                cpu.load(dma, &entry);
+                SUCCESS
+            },
+            None => NOT_FOUND
+        }
+    }
+}
+
+/**
+ * 11 byte name + type from an FCB with the attribute bits stripped
+ * */
+fn read_name(cpu: &Cpu8080, addr: u16) -> [u8; 11] {
+    let mut name = [b' '; 11];
+    for (i, c) in name.iter_mut().enumerate() {
+        *c = (cpu.memory[addr.wrapping_add(i as u16) as usize] & 0x7F).to_ascii_uppercase();
+    }
+    name
+}
+
+fn matches(pattern: &[u8; 11], name: &[u8; 11]) -> bool {
+    pattern.iter().zip(name.iter()).all(|(p, n)| *p == b'?' || p == n)
+}
+
+/**
+ * Mangles a host file name into a CP/M 8.3 name, None if nothing of it survives
+ * */
+pub fn cpm_name(host: &str) -> Option<[u8; 11]> {
+    let (base, ext) = match host.rfind('.') {
+        Some(0) | None => (host, ""),
+        Some(i) => (&host[..i], &host[i + 1..])
+    };
+
+    let mangle = |c: char| -> u8 {
+        if c.is_ascii_alphanumeric() || "!#$%&'()-@^_`{}~".contains(c) { c.to_ascii_uppercase() as u8 } else { b'_' }
+    };
+
+    let mut name = [b' '; 11];
+    for (i, c) in base.chars().take(8).enumerate() {
+        name[i] = mangle(c);
+    }
+    for (i, c) in ext.chars().take(3).enumerate() {
+        name[8 + i] = mangle(c);
+    }
+
+    if name[0] == b' ' { None } else { Some(name) }
+}
+
+/**
+ * Host file name for a CP/M 8.3 name: lower case, trailing padding dropped
+ * */
+pub fn host_name(name: &[u8; 11]) -> String {
+    let base = String::from_utf8_lossy(&name[..8]).trim_end().to_ascii_lowercase();
+    let ext = String::from_utf8_lossy(&name[8..]).trim_end().to_ascii_lowercase();
+    if ext.is_empty() { base } else { format!("{}.{}", base, ext) }
+}
+
+/**
+ * Regular files in dir whose mangled name matches the pattern, sorted by name. The first host file wins a name clash
+ * */
+fn find(dir: &Path, pattern: &[u8; 11]) -> Vec<([u8; 11], PathBuf)> {
+    let mut entries: Vec<(String, PathBuf)> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .map(|e| (e.file_name().to_string_lossy().into_owned(), e.path()))
+            .collect(),
+        Err(_) => return Vec::new()
+    };
+    entries.sort();
+
+    let mut found: Vec<([u8; 11], PathBuf)> = Vec::new();
+    for (host, path) in entries {
+        if let Some(name) = cpm_name(&host) {
+            if matches(pattern, &name) && !found.iter().any(|(n, _)| *n == name) {
+                found.push((name, path));
+            }
+        }
+    }
+    found
+}
+
+fn record_len(path: &Path) -> u64 {
+    let len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    len.div_ceil(RECORD_SIZE)
+}
+
+/**
+ * One 32 byte directory entry per 16K extent, with a non zero allocation map so tools see it as used
+ * */
+fn directory_entries(name: [u8; 11], path: &Path) -> Vec<[u8; DIR_ENTRY_SIZE as usize]> {
+    let records = record_len(path);
+    let extents = records.div_ceil(RECORDS_PER_EXTENT).max(1);
+    (0..extents).map(|extent| {
+        let mut entry = [0u8; DIR_ENTRY_SIZE as usize];
+        entry[1..12].copy_from_slice(&name);
+        entry[FCB_EXTENT as usize] = (extent & 0x1F) as u8;
+        entry[FCB_S2 as usize] = (extent >> 5) as u8;
+        entry[FCB_RECORD_COUNT as usize] = (records - extent * RECORDS_PER_EXTENT).min(RECORDS_PER_EXTENT) as u8;
+        for block in entry[16..].iter_mut() {
+            *block = 1;
+        }
+        entry
+    }).collect()
+}
+
+fn sequential_record(cpu: &Cpu8080, fcb: u16) -> u64 {
+    let extent = cpu.memory[fcb.wrapping_add(FCB_EXTENT) as usize] as u64 & 0x1F;
+    let s2 = cpu.memory[fcb.wrapping_add(FCB_S2) as usize] as u64 & 0x3F;
+    let record = cpu.memory[fcb.wrapping_add(FCB_CURRENT_RECORD) as usize] as u64 & 0x7F;
+    (((s2 << 5) | extent) * RECORDS_PER_EXTENT) + record
+}
+
+fn set_sequential_record(cpu: &mut Cpu8080, fcb: u16, record: u64) {
+    let extent = record / RECORDS_PER_EXTENT;
+    cpu.memory[fcb.wrapping_add(FCB_EXTENT) as usize] = (extent & 0x1F) as u8;
+    cpu.memory[fcb.wrapping_add(FCB_S2) as usize] = ((extent >> 5) & 0x3F) as u8;
+    cpu.memory[fcb.wrapping_add(FCB_CURRENT_RECORD) as usize] = (record % RECORDS_PER_EXTENT) as u8;
+}
+
+fn random_record(cpu: &Cpu8080, fcb: u16) -> u64 {
+    let r = fcb.wrapping_add(FCB_RANDOM_RECORD);
+    let byte = |i: u16| cpu.memory[r.wrapping_add(i) as usize] as u64;
+    byte(0) | byte(1) << 8 | (byte(2) & 0x03) << 16
+}
+
+fn set_random_record(cpu: &mut Cpu8080, fcb: u16, record: u64) {
+    let r = fcb.wrapping_add(FCB_RANDOM_RECORD);
+    cpu.load(r, &[record as u8, (record >> 8) as u8, (record >> 16) as u8]);
+}
+
+/**
+ * RC of the FCB's current extent, how many of its records exist in the host file
+ * */
+fn set_record_count(cpu: &mut Cpu8080, fcb: u16, path: &Path) {
+    let extent = sequential_record(cpu, fcb) / RECORDS_PER_EXTENT;
+    let records = record_len(path).saturating_sub(extent * RECORDS_PER_EXTENT).min(RECORDS_PER_EXTENT);
+    cpu.memory[fcb.wrapping_add(FCB_RECORD_COUNT) as usize] = records as u8;
+}
+
+fn read_record(cpu: &mut Cpu8080, path: &Path, record: u64, dma: u16) -> u8 {
+    let mut buffer = [EOF_FILL; SECTOR_SIZE];
+    let read = File::open(path)
+        .and_then(|mut f| {
+            f.seek(SeekFrom::Start(record * RECORD_SIZE))?;
+            let mut total = 0;
+            while total < SECTOR_SIZE {
+                match f.read(&mut buffer[total..])? {
+                    0 => break,
+                    n => total += n
+                }
+            }
+            Ok(total)
+        });
+
+    match read {
+        Ok(0) | Err(_) => END_OF_FILE,
+        Ok(n) => {
+            for b in buffer[n..].iter_mut() {
+                *b = EOF_FILL;
+            }
+            cpu.load(dma, &buffer);
+            SUCCESS
+        }
+    }
+}
+
+fn write_record(cpu: &Cpu8080, path: &Path, record: u64, dma: u16) -> u8 {
+    let data: Vec<u8> = (0..SECTOR_SIZE as u16).map(|i| cpu.memory[dma.wrapping_add(i) as usize]).collect();
+    let written = OpenOptions::new().write(true).open(path)
+        .and_then(|mut f| {
+            f.seek(SeekFrom::Start(record * RECORD_SIZE))?;
+            f.write_all(&data)
+        });
+    if written.is_ok() { SUCCESS } else { DISK_FULL }
+}
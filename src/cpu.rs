@@ -1,11 +1,30 @@
 pub mod opcode;
-use opcode::{Instruction, OpcodeTable};
+pub mod io;
+pub mod console;
+pub mod cpm;
+pub mod machines;
+pub mod reference;
+pub mod trace;
+pub mod debugger;
+pub mod callstack;
+pub mod ihex;
+pub mod gdbstub;
+pub mod symbols;
+pub mod savestate;
+pub mod movie;
+pub mod profile;
+pub mod coverage;
+pub mod stats;
+pub mod inspect;
+use opcode::{OpcodeTable, is_call, is_return};
+use callstack::{CallStack, Frame, FrameKind};
+use io::IoBus;
+use stats::InstructionStats;
 use std::num::Wrapping;
-use std::collections::HashMap;
 
 
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ConditionFlag {
     Zero = 1 << 0, 
     Sign = 1 << 1, 
@@ -14,6 +33,7 @@ pub enum ConditionFlag {
     AuxiliaryCarry = 1 << 4
 }
 
+#[derive(Copy, Clone, Debug, Default)]
 pub struct ConditionBitset(u8);
 
 type HighU8 = u8;
@@ -21,7 +41,7 @@ type LowU8 = u8;
 
 impl ConditionBitset {
     pub fn set(&mut self, flag: ConditionFlag) {
-        self.0 &= flag as u8;
+        self.0 |= flag as u8;
     }
 
     pub fn unset(&mut self, flag: ConditionFlag) {
@@ -32,9 +52,191 @@ impl ConditionBitset {
         (self.0 & flag as u8) == flag as u8
     }
 
+    pub fn assign(&mut self, flag: ConditionFlag, value: bool) {
+        if value { self.set(flag) } else { self.unset(flag) }
+    }
+
     fn as_bit(&self, flag: ConditionFlag) -> u8 {
         if self.is_set(flag) { 1 } else { 0 }
     }
+
+    /**
+     * Packs the flags into the byte pushed by PUSH PSW: S Z 0 AC 0 P 1 CY
+     * */
+    pub fn to_psw(&self) -> u8 {
+        (self.as_bit(ConditionFlag::Sign) << 7)
+            | (self.as_bit(ConditionFlag::Zero) << 6)
+            | (self.as_bit(ConditionFlag::AuxiliaryCarry) << 4)
+            | (self.as_bit(ConditionFlag::Parity) << 2)
+            | 0x02
+            | self.as_bit(ConditionFlag::Carry)
+    }
+
+    /**
+     * Unpacks a flag byte in the PUSH PSW layout, as done by POP PSW
+     * */
+    pub fn from_psw(psw: u8) -> Self {
+        let mut flags = ConditionBitset(0);
+        flags.assign(ConditionFlag::Sign, psw & 0x80 != 0);
+        flags.assign(ConditionFlag::Zero, psw & 0x40 != 0);
+        flags.assign(ConditionFlag::AuxiliaryCarry, psw & 0x10 != 0);
+        flags.assign(ConditionFlag::Parity, psw & 0x04 != 0);
+        flags.assign(ConditionFlag::Carry, psw & 0x01 != 0);
+        flags
+    }
+}
+
+/**
+ * A port access requested by the last executed IN/OUT instruction, serviced by `Cpu8080::step`
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PortAccess {
+    Input(u8),
+    Output(u8, u8)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write
+}
+
+/**
+ * A data access made through `read_byte`/`write_byte`
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MemoryAccess {
+    pub addr: u16,
+    pub value: u8,
+    pub kind: AccessKind
+}
+
+/**
+ * An 8 bit register, numbered as in the opcode's register fields, M being the byte HL points to
+ * */
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reg8 {
+    B = 0, C = 1, D = 2, E = 3, H = 4, L = 5, M = 6, A = 7
+}
+
+impl Reg8 {
+    pub const ALL: [Reg8; 8] = [Reg8::B, Reg8::C, Reg8::D, Reg8::E, Reg8::H, Reg8::L, Reg8::M, Reg8::A];
+
+    /**
+     * Register of a 3 bit register field, e.g. bits 3-5 (destination) or 0-2 (source) of MOV
+     * */
+    pub fn from_code(code: u8) -> Self {
+        Self::ALL[(code & 0x07) as usize]
+    }
+
+    pub fn name(self) -> &'static str {
+        ["B", "C", "D", "E", "H", "L", "M", "A"][self as usize]
+    }
+
+    /**
+     * Register by name, any case
+     * */
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|reg| reg.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/**
+ * A 16 bit register: a register pair, SP, PC, or PSW, the A and flags pair PUSH PSW pushes
+ * */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reg16 {
+    BC, DE, HL, SP, PSW, PC
+}
+
+impl Reg16 {
+    pub const ALL: [Reg16; 6] = [Reg16::BC, Reg16::DE, Reg16::HL, Reg16::SP, Reg16::PSW, Reg16::PC];
+
+    pub fn name(self) -> &'static str {
+        ["BC", "DE", "HL", "SP", "PSW", "PC"][self as usize]
+    }
+
+    /**
+     * Register by name, any case
+     * */
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|reg| reg.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/**
+ * A register `Cpu8080::get` and `set` can address, `Reg8` holding a byte and `Reg16` a word
+ * */
+pub trait Register: Copy {
+    type Value;
+    fn get(self, cpu: &Cpu8080) -> Self::Value;
+    fn set(self, cpu: &mut Cpu8080, val: Self::Value);
+}
+
+/**
+ * Reading M doesn't go through read_byte, so it isn't logged. Writing it goes through write_byte
+ * */
+impl Register for Reg8 {
+    type Value = u8;
+
+    fn get(self, cpu: &Cpu8080) -> u8 {
+        match self {
+            Reg8::A => cpu.a,
+            Reg8::B => cpu.b,
+            Reg8::C => cpu.c,
+            Reg8::D => cpu.d,
+            Reg8::E => cpu.e,
+            Reg8::H => cpu.h,
+            Reg8::L => cpu.l,
+            Reg8::M => cpu.memory[cpu.hl() as usize]
+        }
+    }
+
+    fn set(self, cpu: &mut Cpu8080, val: u8) {
+        match self {
+            Reg8::A => cpu.a = val,
+            Reg8::B => cpu.b = val,
+            Reg8::C => cpu.c = val,
+            Reg8::D => cpu.d = val,
+            Reg8::E => cpu.e = val,
+            Reg8::H => cpu.h = val,
+            Reg8::L => cpu.l = val,
+            Reg8::M => cpu.write_byte(cpu.hl(), val)
+        }
+    }
+}
+
+/**
+ * Setting PSW sets the flags as POP PSW does
+ * */
+impl Register for Reg16 {
+    type Value = u16;
+
+    fn get(self, cpu: &Cpu8080) -> u16 {
+        match self {
+            Reg16::BC => cpu.bc(),
+            Reg16::DE => cpu.de(),
+            Reg16::HL => cpu.hl(),
+            Reg16::SP => cpu.sp,
+            Reg16::PSW => combine_bytes(cpu.a, cpu.condition_codes.to_psw()),
+            Reg16::PC => cpu.pc
+        }
+    }
+
+    fn set(self, cpu: &mut Cpu8080, val: u16) {
+        match self {
+            Reg16::BC => cpu.set_bc(val),
+            Reg16::DE => cpu.set_de(val),
+            Reg16::HL => cpu.set_hl(val),
+            Reg16::SP => cpu.sp = val,
+            Reg16::PSW => {
+                cpu.a = (val >> 8) as u8;
+                cpu.condition_codes = ConditionBitset::from_psw(val as u8);
+            },
+            Reg16::PC => cpu.pc = val
+        }
+    }
 }
 
 pub struct Cpu8080 {
@@ -46,46 +248,208 @@ pub struct Cpu8080 {
     pub h: u8, pub l: u8,
 
     pub halted: bool,
+    pub interrupts_enabled: bool,
+    pub cycles: u64,
+    pub port_access: Option<PortAccess>,
 
     pub memory: Vec<u8>,
+    /**
+     * Every data access of the instructions executed since it was last cleared, None (the default) turns logging off
+     * */
+    pub memory_log: Option<Vec<MemoryAccess>>,
+    /**
+     * Shadow call stack, None (the default) turns tracking off
+     * */
+    pub call_stack: Option<CallStack>,
+    /**
+     * Address and old value of every byte overwritten through write_byte, write_u16 or load since it was last
+     * cleared, None (the default) turns it off. Used to undo instructions
+     * */
+    pub undo_log: Option<Vec<(u16, u8)>>,
+    /**
+     * Opcode counts, None (the default) turns counting off
+     * */
+    pub stats: Option<InstructionStats>,
     pub condition_codes: ConditionBitset,
 
     pub opcode_table: OpcodeTable
 }
 
+impl Default for Cpu8080 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Cpu8080 {
 
-    // pub fn new() -> Self {
-    //     Cpu8080 {
-    //         pc
-    //     }
-    // }
+    pub const MEMORY_SIZE: usize = 0x10000;
+
+    pub fn new() -> Self {
+        Cpu8080 {
+            pc: 0, sp: 0,
+            a: 0,
+            b: 0, c: 0,
+            d: 0, e: 0,
+            h: 0, l: 0,
+            halted: false,
+            interrupts_enabled: false,
+            cycles: 0,
+            port_access: None,
+            memory: vec![0; Self::MEMORY_SIZE],
+            memory_log: None,
+            call_stack: None,
+            undo_log: None,
+            stats: None,
+            condition_codes: ConditionBitset::default(),
+            opcode_table: OpcodeTable::new()
+        }
+    }
+
+    /**
+     * Copies bytes into memory starting at addr, wrapping around the top of the address space
+     * */
+    pub fn load(&mut self, addr: u16, bytes: &[u8]) {
+        for (i, b) in bytes.iter().enumerate() {
+            let addr = addr.wrapping_add(i as u16);
+            self.log_undo(addr);
+            self.memory[addr as usize] = *b;
+        }
+    }
 
     pub fn execute(&mut self, opcode: u8) {
-        self.pc += 1;
+        let (pc, sp) = (self.pc, self.sp);
+        self.pc = self.pc.wrapping_add(1);
 
-        if let Some(instruction) = self.opcode_table.get(opcode) {
+        if let Some(&instruction) = self.opcode_table.get(opcode) {
             let op = instruction.func_ptr;
-            let pc = self.pc as usize;
+            let b2 = self.memory[self.pc as usize];
+            let b3 = self.memory[self.pc.wrapping_add(1) as usize];
+            self.cycles += instruction.cycles as u64;
+            // pc has to point past the operands before the instruction runs so jumps and calls see the return address
+            self.pc = self.pc.wrapping_add(instruction.size as u16 - 1);
             match instruction.size {
                 1 => { op(self, 0, 0) },
-                2 => { op(self, self.memory[pc], 0); self.pc += 1; },
-                3 => { op(self, self.memory[pc], self.memory[pc + 1]); self.pc += 2; },
+                2 => { op(self, b2, 0) },
+                3 => { op(self, b2, b3) },
                 _ => {}
             }
         } else {
-            // TODO :: Do something lol
             panic!("OPCODE ERROR :: Opcode {:x} not found", opcode);
         }
 
+        if self.call_stack.is_some() {
+            self.track_call(opcode, pc, sp);
+        }
+        if let Some(stats) = &mut self.stats {
+            // a taken Jcc leaves pc somewhere other than after it, a taken Ccc or Rcc moves SP
+            let taken = match opcode & 0xC7 {
+                0xC2 => self.pc != pc.wrapping_add(3),
+                0xC0 | 0xC4 => self.sp != sp,
+                _ => false
+            };
+            stats.record(opcode, taken);
+        }
+    }
+
+    /**
+     * Updates the shadow call stack after the instruction at pc ran. Conditional calls and returns only count when
+     * taken, which shows in SP having moved by 2
+     * */
+    fn track_call(&mut self, opcode: u8, pc: u16, sp: u16) {
+        let Some(call_stack) = &mut self.call_stack else { return };
+        if is_call(opcode) && self.sp == sp.wrapping_sub(2) {
+            let kind = if opcode & 0xC7 == 0xC7 { FrameKind::Restart } else { FrameKind::Call };
+            let return_addr = combine_bytes(self.memory[self.sp.wrapping_add(1) as usize], self.memory[self.sp as usize]);
+            call_stack.push(Frame { kind, call_site: pc, target: self.pc, return_addr, sp: self.sp });
+        } else if is_return(opcode) && self.sp == sp.wrapping_add(2) {
+            call_stack.on_return(pc, self.pc, self.sp);
+        }
     }
 
     /**
-     * Increments stack pointer by 2 then pops value off of stack, which is returned in its parts: (high, low)
+     * Fetches and executes the instruction at pc, servicing any IN/OUT through io. Returns the number of cycles taken
+     * */
+    pub fn step(&mut self, io: &mut dyn IoBus) -> u32 {
+        let start = self.cycles;
+        if self.halted {
+            self.cycles += 4;
+            return 4;
+        }
+
+        let opcode = self.memory[self.pc as usize];
+        self.execute(opcode);
+
+        match self.port_access.take() {
+            Some(PortAccess::Input(port)) => self.a = io.input(port),
+            Some(PortAccess::Output(port, value)) => io.output(port, value),
+            None => {}
+        }
+
+        (self.cycles - start) as u32
+    }
+
+    /**
+     * Raises an interrupt with the given RST vector (0 - 7). Ignored, returning false, while interrupts are disabled
+     * */
+    pub fn interrupt(&mut self, vector: u8) -> bool {
+        if !self.interrupts_enabled {
+            return false;
+        }
+        self.interrupts_enabled = false;
+        self.halted = false;
+        self.cycles += 11;
+        let return_addr = self.pc;
+        self.call(((vector & 0x7) as u16) << 3);
+        if let Some(call_stack) = &mut self.call_stack {
+            call_stack.push(Frame { kind: FrameKind::Interrupt, call_site: return_addr, target: self.pc, return_addr, sp: self.sp });
+        }
+        true
+    }
+
+    pub fn bc(&self) -> u16 {
+        combine_bytes(self.b, self.c)
+    }
+
+    pub fn de(&self) -> u16 {
+        combine_bytes(self.d, self.e)
+    }
+
+    pub fn hl(&self) -> u16 {
+        combine_bytes(self.h, self.l)
+    }
+
+    pub fn set_bc(&mut self, val: u16) {
+        set_byte_pair(&mut self.b, &mut self.c, val);
+    }
+
+    pub fn set_de(&mut self, val: u16) {
+        set_byte_pair(&mut self.d, &mut self.e, val);
+    }
+
+    pub fn set_hl(&mut self, val: u16) {
+        set_byte_pair(&mut self.h, &mut self.l, val);
+    }
+
+    /**
+     * Value of a register, a byte for a `Reg8` and a word for a `Reg16`
+     * */
+    pub fn get<R: Register>(&self, reg: R) -> R::Value {
+        reg.get(self)
+    }
+
+    pub fn set<R: Register>(&mut self, reg: R, val: R::Value) {
+        reg.set(self, val)
+    }
+
+    /**
+     * Pops value off of stack then increments stack pointer by 2, value is returned in its parts: (high, low)
      * */
     pub fn pop_stack_parts(&mut self) -> (HighU8, LowU8) {
-        self.sp += 2;
-        self.read_u16_parts(self.sp)
+        let low = self.read_byte(self.sp);
+        let high = self.read_byte(self.sp.wrapping_add(1));
+        self.sp = self.sp.wrapping_add(2);
+        (high, low)
     }
 
     pub fn pop_stack(&mut self) -> u16 {
@@ -94,17 +458,46 @@ impl Cpu8080 {
     }
 
     pub fn push_stack(&mut self, val: u16) {
-        self.write_u16(self.sp - 2, val);
-        self.sp -= 2;
+        self.sp = self.sp.wrapping_sub(1);
+        self.write_byte(self.sp, (val >> 8) as u8);
+        self.sp = self.sp.wrapping_sub(1);
+        self.write_byte(self.sp, val as u8);
+    }
+
+    /**
+     * Data read made by an instruction, recorded in memory_log when logging is on. Opcode and operand fetches don't go through here
+     * */
+    pub fn read_byte(&mut self, addr: u16) -> u8 {
+        let value = self.memory[addr as usize];
+        if let Some(log) = &mut self.memory_log {
+            log.push(MemoryAccess { addr, value, kind: AccessKind::Read });
+        }
+        value
+    }
+
+    /**
+     * Data write made by an instruction, recorded in memory_log when logging is on
+     * */
+    pub fn write_byte(&mut self, addr: u16, value: u8) {
+        if let Some(log) = &mut self.memory_log {
+            log.push(MemoryAccess { addr, value, kind: AccessKind::Write });
+        }
+        self.log_undo(addr);
+        self.memory[addr as usize] = value;
+    }
+
+    fn log_undo(&mut self, addr: u16) {
+        if let Some(log) = &mut self.undo_log {
+            log.push((addr, self.memory[addr as usize]));
+        }
     }
 
     /**
      * Reads u16 from memory address and returns it in its parts: (high, low)
      * */
     pub fn read_u16_parts(&self, addr: u16) -> (HighU8, LowU8) {
-        let addr = addr as usize;
-        let low = self.memory[addr];
-        let high = self.memory[addr + 1];
+        let low = self.memory[addr as usize];
+        let high = self.memory[addr.wrapping_add(1) as usize];
         (high, low)
     }
 
@@ -114,13 +507,12 @@ impl Cpu8080 {
     }
 
     pub fn write_u16(&mut self, addr: u16, val: u16) {
-        let addr = addr as usize;
-
-        let mut high = self.memory[addr + 1];
-        let mut low = self.memory[addr];
+        let (mut high, mut low) = (0, 0);
         set_byte_pair(&mut high, &mut low, val);
-        self.memory[addr + 1] = high;
-        self.memory[addr] = low;
+        self.log_undo(addr.wrapping_add(1));
+        self.log_undo(addr);
+        self.memory[addr.wrapping_add(1) as usize] = high;
+        self.memory[addr as usize] = low;
     }
 
     pub fn jmp(&mut self, addr: u16) {
@@ -133,6 +525,18 @@ impl Cpu8080 {
         // self.sp += 2;
     }
 
+    /**
+     * Returns from a subroutine implemented on the host (a BIOS or BDOS trap) instead of by a RET in guest code,
+     * keeping the shadow call stack in step
+     * */
+    pub fn trap_return(&mut self) {
+        let pc = self.pc;
+        self.ret();
+        if let Some(call_stack) = &mut self.call_stack {
+            call_stack.on_return(pc, self.pc, self.sp);
+        }
+    }
+
     pub fn call(&mut self, addr: u16) {
         self.push_stack(self.pc);
         self.pc = addr;
@@ -149,10 +553,14 @@ impl Cpu8080 {
         self.check_sign(result);
         self.check_parity(result as u32);
         self.check_carry(result);
-        // aux carry
+        self.check_aux_carry((a & 0xF) + (val & 0xF) + cy);
         self.a = result as u8;
     }
 
+    /**
+     * The 8080 subtracts by adding the two's complement, so CY is the inverted carry out
+     * and AC is the carry out of bit 3 of A + !val + !CY
+     * */
     pub fn sbb(&mut self, val: u8) {
         use Wrapping as W;
         let (a, val, cy)  = (self.a as u16, val as u16, self.condition_codes.as_bit(ConditionFlag::Carry) as u16);
@@ -164,7 +572,7 @@ impl Cpu8080 {
         self.check_sign(result);
         self.check_parity(result as u32);
         self.check_carry(result);
-        // aux carry
+        self.check_aux_carry((a & 0xF) + (!val & 0xF) + (cy ^ 1));
         self.a = result as u8;
     }
 
@@ -176,7 +584,7 @@ impl Cpu8080 {
         self.check_sign(result);
         self.check_parity(result as u32);
         self.check_carry(result);
-        // aux carry
+        self.check_aux_carry((self.a as u16 & 0xF) + (val as u16 & 0xF));
 
         self.a = result as u8;
     }
@@ -189,7 +597,7 @@ impl Cpu8080 {
         self.check_sign(result);
         self.check_parity(result as u32);
         self.check_carry(result);
-        // aux carry
+        self.check_aux_carry((self.a as u16 & 0xF) + (!val as u16 & 0xF) + 1);
 
         self.a = result as u8;
     }
@@ -201,7 +609,7 @@ impl Cpu8080 {
         self.check_zero(result);
         self.check_sign(result);
         self.check_parity(result as u32);
-        // auxillary carry
+        self.check_aux_carry((val as u16 & 0xF) + 1);
 
         result as u8
     }
@@ -213,7 +621,7 @@ impl Cpu8080 {
         self.check_zero(result);
         self.check_sign(result);
         self.check_parity(result as u32);
-        // auxillary carry
+        self.check_aux_carry((val as u16 & 0xF) + 0xF);
 
         result as u8
     }
@@ -225,7 +633,8 @@ impl Cpu8080 {
         self.check_zero(result);
         self.check_sign(result);
         self.check_parity(result as u32);
-        // aux carry
+        // ANA sets AC to the OR of bit 3 of both operands
+        self.condition_codes.assign(ConditionFlag::AuxiliaryCarry, (self.a | val) & 0x08 != 0);
 
         self.a = result as u8;
     }
@@ -237,7 +646,7 @@ impl Cpu8080 {
         self.check_zero(result);
         self.check_sign(result);
         self.check_parity(result as u32);
-        // aux carry
+        self.condition_codes.unset(ConditionFlag::AuxiliaryCarry);
 
         self.a = result as u8;
     }
@@ -249,7 +658,7 @@ impl Cpu8080 {
         self.check_zero(result);
         self.check_sign(result);
         self.check_parity(result as u32);
-        // aux carry
+        self.condition_codes.unset(ConditionFlag::AuxiliaryCarry);
 
         self.a = result as u8;
     }
@@ -262,6 +671,46 @@ impl Cpu8080 {
         self.check_carry(result);
         self.check_sign(result);
         self.check_parity(result as u32);
+        self.check_aux_carry((self.a as u16 & 0xF) + (!val as u16 & 0xF) + 1);
+    }
+
+    /**
+     * Decimal Adjust Accumulator
+     *
+     *  1. If the value of the least significant 4 bits of the accumulator is greater than 9 OR if the AC flag is set, 6 is added to the accumulator
+     *  2. If the value of the most significant 4 bits of the accumulator is now greater than 9 OR if the CY flag is set, 6 is added to the most 4 significant bits of the accumulator
+     * */
+    pub fn daa(&mut self) {
+        let lsb = self.a & 0xF;
+        let msb = self.a >> 4;
+        let mut carry = self.condition_codes.is_set(ConditionFlag::Carry);
+
+        let mut acc = 0;
+        if lsb > 9 || self.condition_codes.is_set(ConditionFlag::AuxiliaryCarry) {
+            acc += 0x06;
+        }
+        if carry || msb > 9 || (msb >= 9 && lsb > 9) {
+            acc += 0x60;
+            carry = true;
+        }
+
+        let result = self.a as u16 + acc;
+        self.check_zero(result);
+        self.check_sign(result);
+        self.check_parity(result as u32);
+        self.check_aux_carry(lsb as u16 + (acc & 0xF));
+        self.condition_codes.assign(ConditionFlag::Carry, carry);
+
+        self.a = result as u8;
+    }
+
+    /**
+     * Adds val to HL, only CY is effected
+     * */
+    pub fn dad(&mut self, val: u16) {
+        let result = self.hl() as u32 + val as u32;
+        self.condition_codes.assign(ConditionFlag::Carry, result > 0xFFFF);
+        self.set_hl(result as u16);
     }
 
     fn check_zero(&mut self, result: u16) -> bool {
@@ -285,7 +734,8 @@ impl Cpu8080 {
     }
 
     fn check_parity(&mut self, result: u32) -> bool {
-        if parity(result) {
+        // P is set when the low byte of the result has an even number of set bits
+        if !parity(result & 0xFF) {
             self.condition_codes.set(ConditionFlag::Parity);
             true
         } else {
@@ -294,6 +744,16 @@ impl Cpu8080 {
         }
     }
 
+    fn check_aux_carry(&mut self, low_nibble_sum: u16) -> bool {
+        if low_nibble_sum > 0xF {
+            self.condition_codes.set(ConditionFlag::AuxiliaryCarry);
+            true
+        } else {
+            self.condition_codes.unset(ConditionFlag::AuxiliaryCarry);
+            false
+        }
+    }
+
     fn check_carry(&mut self, result: u16) -> bool {
         if result > 0xFF {
             self.condition_codes.set(ConditionFlag::Carry);
@@ -321,20 +781,11 @@ pub(crate) const fn parity(n: u32) -> bool {
     let mut y = n ^ (n >> 1);
     y = y ^ (y >> 2);
     y = y ^ (y >> 4);
-    y = y ^ (y >> 6);
     y = y ^ (y >> 8);
     y = y ^ (y >> 16);
     (y & 1) != 0
 }
 
-pub(crate) const fn sign_flag(n: u16) -> bool {
-    n & 0x80 != 0
-}
-
-pub(crate) const fn carry_flag(n: u16) -> bool {
-    n > 0xFF
-}
-
 pub fn read_file(path: &str) -> std::io::Result<Vec<u8>> {
     use std::fs::File;
     use std::io::prelude::*;
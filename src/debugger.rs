@@ -0,0 +1,418 @@
+pub mod expr;
+pub mod history;
+
+use crate::{Cpu8080, AccessKind, MemoryAccess};
+use crate::io::NullBus;
+use crate::opcode::{is_call, is_return};
+use expr::Expr;
+use history::History;
+use std::ops::RangeInclusive;
+
+/**
+ * Anything built around a `Cpu8080` that executes one instruction at a time, so it can be debugged
+ * */
+pub trait Machine {
+    fn cpu(&self) -> &Cpu8080;
+    fn cpu_mut(&mut self) -> &mut Cpu8080;
+    /**
+     * Executes one instruction, returning the cycles taken
+     * */
+    fn step(&mut self) -> u32;
+}
+
+/**
+ * A bare CPU with nothing on its I/O ports
+ * */
+impl Machine for Cpu8080 {
+    fn cpu(&self) -> &Cpu8080 {
+        self
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu8080 {
+        self
+    }
+
+    fn step(&mut self) -> u32 {
+        Cpu8080::step(self, &mut NullBus)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access
+}
+
+impl WatchKind {
+    fn matches(self, kind: AccessKind) -> bool {
+        match self {
+            WatchKind::Read => kind == AccessKind::Read,
+            WatchKind::Write => kind == AccessKind::Write,
+            WatchKind::Access => true
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PortDirection {
+    In,
+    Out,
+    Both
+}
+
+#[derive(Clone, Debug)]
+pub enum PointKind {
+    /**
+     * Stops before the instruction at addr runs, when the condition (if any) is non zero
+     * */
+    Breakpoint { addr: u16, condition: Option<Expr> },
+    /**
+     * Stops after an instruction accesses memory in the range
+     * */
+    Watchpoint { range: RangeInclusive<u16>, kind: WatchKind },
+    /**
+     * Stops after an IN or OUT on the port
+     * */
+    Port { port: u8, direction: PortDirection }
+}
+
+/**
+ * A breakpoint, watchpoint or port breakpoint. Every time it triggers its hit count goes up, and it only stops
+ * execution once the hit count is past ignore_count
+ * */
+#[derive(Clone, Debug)]
+pub struct Point {
+    pub id: usize,
+    pub kind: PointKind,
+    pub enabled: bool,
+    pub hits: u64,
+    pub ignore_count: u64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopReason {
+    /**
+     * step, step_over or step_out finished
+     * */
+    Step,
+    Breakpoint { id: usize, addr: u16 },
+    /**
+     * run_to reached its address
+     * */
+    Temporary { addr: u16 },
+    Watchpoint { id: usize, access: MemoryAccess },
+    Port { id: usize, port: u8, value: u8, direction: PortDirection },
+    Halted,
+    /**
+     * run_for used up its instruction budget
+     * */
+    Limit,
+    /**
+     * step_back or reverse_continue reached the oldest recorded instruction
+     * */
+    HistoryStart
+}
+
+/**
+ * Debugger around a machine. Breakpoints are checked before each instruction, watchpoints and port breakpoints
+ * after it. Watchpoints see every data access that goes through `Cpu8080::read_byte`/`write_byte`, which covers
+ * M operands, the stack, STAX/LDAX and the direct addressing instructions.
+ * */
+pub struct Debugger<M: Machine> {
+    pub machine: M,
+    points: Vec<Point>,
+    next_id: usize,
+    history: Option<History>
+}
+
+impl<M: Machine> Debugger<M> {
+    pub fn new(machine: M) -> Self {
+        Debugger { machine, points: Vec::new(), next_id: 1, history: None }
+    }
+
+    pub fn cpu(&self) -> &Cpu8080 {
+        self.machine.cpu()
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut Cpu8080 {
+        self.machine.cpu_mut()
+    }
+
+    /**
+     * Starts recording execution history so step_back and reverse_continue can go back, see History
+     * */
+    pub fn enable_history(&mut self, history: History) {
+        self.history = Some(history);
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    pub fn history_mut(&mut self) -> Option<&mut History> {
+        self.history.as_mut()
+    }
+
+    fn add(&mut self, kind: PointKind) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.points.push(Point { id, kind, enabled: true, hits: 0, ignore_count: 0 });
+        self.update_memory_log();
+        id
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16) -> usize {
+        self.add(PointKind::Breakpoint { addr, condition: None })
+    }
+
+    pub fn add_conditional_breakpoint(&mut self, addr: u16, condition: Expr) -> usize {
+        self.add(PointKind::Breakpoint { addr, condition: Some(condition) })
+    }
+
+    pub fn add_watchpoint(&mut self, range: RangeInclusive<u16>, kind: WatchKind) -> usize {
+        self.add(PointKind::Watchpoint { range, kind })
+    }
+
+    pub fn add_port_breakpoint(&mut self, port: u8, direction: PortDirection) -> usize {
+        self.add(PointKind::Port { port, direction })
+    }
+
+    pub fn remove(&mut self, id: usize) -> bool {
+        let len = self.points.len();
+        self.points.retain(|p| p.id != id);
+        self.update_memory_log();
+        self.points.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.update_memory_log();
+    }
+
+    pub fn point(&self, id: usize) -> Option<&Point> {
+        self.points.iter().find(|p| p.id == id)
+    }
+
+    pub fn point_mut(&mut self, id: usize) -> Option<&mut Point> {
+        self.points.iter_mut().find(|p| p.id == id)
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /**
+     * The memory access log is only kept while there are watchpoints, it costs an allocation per access otherwise
+     * */
+    fn update_memory_log(&mut self) {
+        let watching = self.points.iter().any(|p| matches!(p.kind, PointKind::Watchpoint { .. }));
+        let cpu = self.machine.cpu_mut();
+        if watching && cpu.memory_log.is_none() {
+            cpu.memory_log = Some(Vec::new());
+        } else if !watching {
+            cpu.memory_log = None;
+        }
+    }
+
+    /**
+     * Counts a hit on a point and says whether it should stop execution
+     * */
+    fn hit(point: &mut Point) -> bool {
+        point.hits += 1;
+        point.hits > point.ignore_count
+    }
+
+    /**
+     * Checks the breakpoints at pc, counting their hits, as run does before every instruction but the first. For
+     * frontends that run in slices with run_for and must not skip the breakpoint on the first instruction of a slice
+     * */
+    pub fn check_breakpoints(&mut self) -> Option<StopReason> {
+        let cpu = self.machine.cpu();
+        let pc = cpu.pc;
+        let mut stop = None;
+        for point in self.points.iter_mut().filter(|p| p.enabled) {
+            if let PointKind::Breakpoint { addr, condition } = &point.kind {
+                let triggered = *addr == pc && condition.as_ref().is_none_or(|c| c.eval(cpu) != 0);
+                if triggered && Self::hit(point) && stop.is_none() {
+                    stop = Some(StopReason::Breakpoint { id: point.id, addr: pc });
+                }
+            }
+        }
+        stop
+    }
+
+    /**
+     * Executes one instruction and checks watchpoints and port breakpoints against what it did
+     * */
+    fn execute(&mut self) -> Option<StopReason> {
+        let cpu = self.machine.cpu_mut();
+        if let Some(log) = &mut cpu.memory_log {
+            log.clear();
+        }
+        let opcode = cpu.memory[cpu.pc as usize];
+        let port = cpu.memory[cpu.pc.wrapping_add(1) as usize];
+        let was_halted = cpu.halted;
+        if let Some(history) = &mut self.history {
+            history.begin(cpu);
+        }
+
+        self.machine.step();
+
+        if let Some(history) = &mut self.history {
+            history.end(self.machine.cpu_mut());
+        }
+        let cpu = self.machine.cpu();
+        let port_access = match opcode {
+            0xDB if !was_halted => Some((PortDirection::In, cpu.a)),
+            0xD3 if !was_halted => Some((PortDirection::Out, cpu.a)),
+            _ => None
+        };
+        let accesses: &[MemoryAccess] = cpu.memory_log.as_deref().unwrap_or(&[]);
+
+        let mut stop = None;
+        for point in self.points.iter_mut().filter(|p| p.enabled) {
+            let triggered = match &point.kind {
+                PointKind::Watchpoint { range, kind } => accesses.iter()
+                    .find(|a| range.contains(&a.addr) && kind.matches(a.kind))
+                    .map(|&access| StopReason::Watchpoint { id: point.id, access }),
+                PointKind::Port { port: p, direction } => match port_access {
+                    Some((dir, value)) if *p == port && (*direction == PortDirection::Both || *direction == dir) =>
+                        Some(StopReason::Port { id: point.id, port, value, direction: dir }),
+                    _ => None
+                },
+                PointKind::Breakpoint { .. } => None
+            };
+            if let Some(reason) = triggered {
+                if Self::hit(point) && stop.is_none() {
+                    stop = Some(reason);
+                }
+            }
+        }
+        stop
+    }
+
+    /**
+     * Executes exactly one instruction, even if a breakpoint sits on it
+     * */
+    pub fn step(&mut self) -> StopReason {
+        self.execute().unwrap_or(StopReason::Step)
+    }
+
+    /**
+     * Undoes the last instruction. Needs history to be enabled, without it or at the start of the recorded
+     * history it returns `StopReason::HistoryStart`
+     * */
+    pub fn step_back(&mut self) -> StopReason {
+        let cpu = self.machine.cpu_mut();
+        match self.history.as_mut().map(|history| history.step_back(cpu)) {
+            Some(true) => StopReason::Step,
+            _ => StopReason::HistoryStart
+        }
+    }
+
+    /**
+     * Undoes count instructions, or as many as the history holds, returning how many. Breakpoints are ignored
+     * */
+    pub fn seek_back(&mut self, count: u64) -> u64 {
+        let cpu = self.machine.cpu_mut();
+        self.history.as_mut().map_or(0, |history| history.seek_back(cpu, count))
+    }
+
+    /**
+     * Runs backwards until a breakpoint, checked with the state before each undone instruction as when running
+     * forwards, or until the start of the recorded history. Watchpoints and port breakpoints don't stop it
+     * */
+    pub fn reverse_continue(&mut self) -> StopReason {
+        loop {
+            if let StopReason::HistoryStart = self.step_back() {
+                return StopReason::HistoryStart;
+            }
+            if let Some(reason) = self.check_breakpoints() {
+                return reason;
+            }
+        }
+    }
+
+    /**
+     * Like step, but a CALL, Ccc or RST counts as one step: runs until pc is back after the call with SP where
+     * it was. A breakpoint or watchpoint hit inside the subroutine stops it early
+     * */
+    pub fn step_over(&mut self) -> StopReason {
+        let cpu = self.machine.cpu();
+        if !is_call(cpu.memory[cpu.pc as usize]) || cpu.halted {
+            return self.step();
+        }
+        let (_, size) = cpu.opcode_table.disassemble(&cpu.memory, cpu.pc);
+        let return_addr = cpu.pc.wrapping_add(size as u16);
+        let sp = cpu.sp;
+        self.run_until(u64::MAX, |cpu, _| cpu.pc == return_addr && cpu.sp == sp)
+    }
+
+    /**
+     * Runs until the current subroutine returns, i.e. until a return pops SP above where it was when stepping out
+     * started. Returns from nested calls don't count. SP is compared as a signed distance so a stack at the top
+     * of memory, whose return leaves SP at 0000, still counts
+     * */
+    pub fn step_out(&mut self) -> StopReason {
+        let sp = self.machine.cpu().sp;
+        self.run_until(u64::MAX, |cpu, opcode| is_return(opcode) && cpu.sp.wrapping_sub(sp) as i16 > 0)
+    }
+
+    /**
+     * Runs until pc reaches addr, through a temporary breakpoint that is removed again however the run ends
+     * */
+    pub fn run_to(&mut self, addr: u16) -> StopReason {
+        let id = self.add_breakpoint(addr);
+        let reason = self.run();
+        self.remove(id);
+        match reason {
+            StopReason::Breakpoint { id: hit, addr } if hit == id => StopReason::Temporary { addr },
+            reason => reason
+        }
+    }
+
+    /**
+     * Runs until something stops execution. The instruction at pc always runs first, so continuing from a
+     * breakpoint doesn't stop on it again
+     * */
+    pub fn run(&mut self) -> StopReason {
+        self.run_until(u64::MAX, |_, _| false)
+    }
+
+    /**
+     * Like run, but gives up with `StopReason::Limit` after max_steps instructions
+     * */
+    pub fn run_for(&mut self, max_steps: u64) -> StopReason {
+        self.run_until(max_steps, |_, _| false)
+    }
+
+    /**
+     * Runs until a point stops execution or done, given the CPU and the opcode just executed, returns true
+     * */
+    fn run_until(&mut self, max_steps: u64, mut done: impl FnMut(&Cpu8080, u8) -> bool) -> StopReason {
+        for i in 0..max_steps {
+            if i > 0 {
+                if let Some(reason) = self.check_breakpoints() {
+                    return reason;
+                }
+            }
+            let cpu = self.machine.cpu();
+            if cpu.halted {
+                return StopReason::Halted;
+            }
+            let opcode = cpu.memory[cpu.pc as usize];
+            if let Some(reason) = self.execute() {
+                return reason;
+            }
+            if done(self.machine.cpu(), opcode) {
+                return StopReason::Step;
+            }
+        }
+        StopReason::Limit
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{Cpu8080, ConditionFlag};
+use std::fmt;
+
+/**
+ * Something an expression can read from the CPU
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Operand {
+    A, B, C, D, E, H, L,
+    BC, DE, HL, SP, PC,
+    /**
+     * A flag, read as 0 or 1
+     * */
+    Flag(ConditionFlag)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BinaryOp {
+    Or, And,
+    Eq, Ne, Lt, Le, Gt, Ge,
+    Add, Sub, BitAnd, BitOr
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(u32),
+    Operand(Operand),
+    /**
+     * Byte in memory at the address
+     * */
+    Memory(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>)
+}
+
+/**
+ * Expression on registers, flags and memory, used for breakpoint conditions and addresses.
+ * Numbers are hex like everywhere else in the debugger: `0100`, `$FF`, `0xFF` or `0FFh`, with `#` for decimal (`#10`).
+ * Registers are A-L, BC, DE, HL, SP and PC, flags Z, S, P, CY and AC, and `[expr]` reads a byte of memory.
+ * Operators from loosest to tightest: `||`, `&&`, comparisons, `&` `|`, `+` `-`, then `!`.
+ * */
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        Self::parse_with(text, &|_| None)
+    }
+
+    /**
+     * Parses with symbol names allowed, resolved to their value by symbol. Register and flag names win over symbols
+     * */
+    pub fn parse_with(text: &str, symbol: &dyn Fn(&str) -> Option<u16>) -> Result<Expr, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0, symbol };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected '{}'", token))
+        }
+    }
+
+    pub fn eval(&self, cpu: &Cpu8080) -> u32 {
+        use BinaryOp::*;
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Operand(operand) => read_operand(cpu, *operand),
+            Expr::Memory(addr) => cpu.memory[(addr.eval(cpu) & 0xFFFF) as usize] as u32,
+            Expr::Not(e) => (e.eval(cpu) == 0) as u32,
+            Expr::Binary(op, a, b) => {
+                let a = a.eval(cpu);
+                // || and && short circuit so [..] reads only happen when needed
+                match op {
+                    Or => return (a != 0 || b.eval(cpu) != 0) as u32,
+                    And => return (a != 0 && b.eval(cpu) != 0) as u32,
+                    _ => {}
+                }
+                let b = b.eval(cpu);
+                match op {
+                    Eq => (a == b) as u32,
+                    Ne => (a != b) as u32,
+                    Lt => (a < b) as u32,
+                    Le => (a <= b) as u32,
+                    Gt => (a > b) as u32,
+                    Ge => (a >= b) as u32,
+                    Add => a.wrapping_add(b),
+                    Sub => a.wrapping_sub(b),
+                    BitAnd => a & b,
+                    BitOr => a | b,
+                    Or | And => unreachable!()
+                }
+            }
+        }
+    }
+
+    /**
+     * Value as an address
+     * */
+    pub fn eval_addr(&self, cpu: &Cpu8080) -> u16 {
+        self.eval(cpu) as u16
+    }
+}
+
+fn read_operand(cpu: &Cpu8080, operand: Operand) -> u32 {
+    use Operand::*;
+    match operand {
+        A => cpu.a as u32,
+        B => cpu.b as u32,
+        C => cpu.c as u32,
+        D => cpu.d as u32,
+        E => cpu.e as u32,
+        H => cpu.h as u32,
+        L => cpu.l as u32,
+        BC => cpu.bc() as u32,
+        DE => cpu.de() as u32,
+        HL => cpu.hl() as u32,
+        SP => cpu.sp as u32,
+        PC => cpu.pc as u32,
+        Flag(flag) => cpu.condition_codes.is_set(flag) as u32
+    }
+}
+
+fn operand(name: &str) -> Option<Operand> {
+    use Operand::*;
+    let operand = match name.to_ascii_uppercase().as_str() {
+        "A" => A, "B" => B, "C" => C, "D" => D, "E" => E, "H" => H, "L" => L,
+        "BC" => BC, "DE" => DE, "HL" => HL, "SP" => SP, "PC" => PC,
+        "Z" => Flag(ConditionFlag::Zero),
+        "S" => Flag(ConditionFlag::Sign),
+        "P" => Flag(ConditionFlag::Parity),
+        "CY" => Flag(ConditionFlag::Carry),
+        "AC" => Flag(ConditionFlag::AuxiliaryCarry),
+        _ => return None
+    };
+    Some(operand)
+}
+
+/**
+ * Parses a number in the debugger's notation, hex unless prefixed with #
+ * */
+pub fn parse_number(text: &str) -> Option<u32> {
+    if let Some(decimal) = text.strip_prefix('#') {
+        return decimal.parse().ok();
+    }
+    let hex = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X"))
+        .or_else(|| text.strip_prefix('$'))
+        .or_else(|| text.strip_suffix('h').or_else(|| text.strip_suffix('H')))
+        .unwrap_or(text);
+    u32::from_str_radix(hex, 16).ok()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(u32),
+    Name(String),
+    Symbol(&'static str)
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{:X}", n),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(s) => write!(f, "{}", s)
+        }
+    }
+}
+
+const SYMBOLS: [&str; 15] = ["||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "&", "|", "!", "[", "]"];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(*s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || "_$#.?@".contains(c)))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("unexpected '{}'", &rest[..rest.chars().next().unwrap().len_utf8()]));
+            }
+            let word = &rest[..end];
+            let starts_number = word.starts_with(|c: char| c.is_ascii_digit() || c == '$' || c == '#');
+            if starts_number {
+                tokens.push(Token::Number(parse_number(word).ok_or_else(|| format!("bad number '{}'", word))?));
+            } else {
+                tokens.push(Token::Name(word.to_string()));
+            }
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    symbol: &'a dyn Fn(&str) -> Option<u16>
+}
+
+impl Parser<'_> {
+    fn accept(&mut self, symbol: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn binary(&mut self, ops: &[(&str, BinaryOp)], next: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        let mut left = next(self)?;
+        'outer: loop {
+            for &(symbol, op) in ops {
+                if self.accept(symbol) {
+                    let right = next(self)?;
+                    left = Expr::Binary(op, Box::new(left), Box::new(right));
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        use BinaryOp::*;
+        self.binary(&[("==", Eq), ("!=", Ne), ("<=", Le), (">=", Ge), ("<", Lt), (">", Gt)], Self::bits)
+    }
+
+    fn bits(&mut self) -> Result<Expr, String> {
+        self.binary(&[("&", BinaryOp::BitAnd), ("|", BinaryOp::BitOr)], Self::sum)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.accept("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.accept("[") {
+            let addr = self.or()?;
+            if !self.accept("]") {
+                return Err("missing ']'".to_string());
+            }
+            return Ok(Expr::Memory(Box::new(addr)));
+        }
+
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| "expression ends early".to_string())?;
+        self.pos += 1;
+        match token {
+            Token::Number(n) => Ok(Expr::Number(n)),
+            Token::Name(name) => operand(&name).map(Expr::Operand)
+                .or_else(|| (self.symbol)(&name).map(|addr| Expr::Number(addr as u32)))
+                .ok_or_else(|| format!("unknown name '{}'", name)),
+            Token::Symbol(s) => Err(format!("unexpected '{}'", s))
+        }
+    }
+}
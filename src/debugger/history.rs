@@ -0,0 +1,331 @@
+use crate::{Cpu8080, ConditionBitset};
+use crate::callstack::{CallStack, Frame};
+use crate::opcode::is_return;
+use std::collections::VecDeque;
+use std::mem::size_of;
+
+/**
+ * Default memory budget, 16 MiB
+ * */
+pub const DEFAULT_BUDGET: usize = 16 << 20;
+/**
+ * Default number of instructions between snapshots
+ * */
+pub const DEFAULT_SNAPSHOT_INTERVAL: u64 = 100_000;
+
+/**
+ * The register file, halt and interrupt state and cycle count
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Registers {
+    pc: u16,
+    sp: u16,
+    a: u8,
+    b: u8, c: u8,
+    d: u8, e: u8,
+    h: u8, l: u8,
+    psw: u8,
+    halted: bool,
+    interrupts_enabled: bool,
+    cycles: u64
+}
+
+impl Registers {
+    fn of(cpu: &Cpu8080) -> Self {
+        Registers {
+            pc: cpu.pc, sp: cpu.sp,
+            a: cpu.a,
+            b: cpu.b, c: cpu.c,
+            d: cpu.d, e: cpu.e,
+            h: cpu.h, l: cpu.l,
+            psw: cpu.condition_codes.to_psw(),
+            halted: cpu.halted,
+            interrupts_enabled: cpu.interrupts_enabled,
+            cycles: cpu.cycles
+        }
+    }
+
+    fn restore(&self, cpu: &mut Cpu8080) {
+        cpu.pc = self.pc;
+        cpu.sp = self.sp;
+        cpu.a = self.a;
+        cpu.b = self.b;
+        cpu.c = self.c;
+        cpu.d = self.d;
+        cpu.e = self.e;
+        cpu.h = self.h;
+        cpu.l = self.l;
+        cpu.condition_codes = ConditionBitset::from_psw(self.psw);
+        cpu.halted = self.halted;
+        cpu.interrupts_enabled = self.interrupts_enabled;
+        cpu.cycles = self.cycles;
+        cpu.port_access = None;
+    }
+}
+
+/**
+ * How to put the shadow call stack back. Returns can drop any number of frames so the whole stack is kept for them,
+ * anything else only ever pushes
+ * */
+#[derive(Clone, Debug)]
+enum CallStackUndo {
+    Untracked,
+    Truncate { depth: usize, mismatches: usize },
+    Replace(CallStack)
+}
+
+/**
+ * What it takes to undo one instruction: the registers before it and the bytes it overwrote, in write order
+ * */
+#[derive(Clone, Debug)]
+struct Record {
+    registers: Registers,
+    memory: Vec<(u16, u8)>,
+    call_stack: CallStackUndo
+}
+
+impl Record {
+    fn size(&self) -> usize {
+        let call_stack = match &self.call_stack {
+            CallStackUndo::Replace(stack) => stack.depth() * size_of::<Frame>(),
+            _ => 0
+        };
+        size_of::<Record>() + self.memory.capacity() * size_of::<(u16, u8)>() + call_stack
+    }
+
+    fn undo(self, cpu: &mut Cpu8080) {
+        for (addr, value) in self.memory.into_iter().rev() {
+            cpu.memory[addr as usize] = value;
+        }
+        self.registers.restore(cpu);
+        match (self.call_stack, &mut cpu.call_stack) {
+            (CallStackUndo::Replace(stack), Some(call_stack)) => *call_stack = stack,
+            (CallStackUndo::Truncate { depth, mismatches }, Some(call_stack)) => {
+                call_stack.truncate(depth);
+                call_stack.mismatches.truncate(mismatches);
+            },
+            _ => {}
+        }
+    }
+}
+
+/**
+ * The whole CPU state before the instruction numbered step
+ * */
+#[derive(Clone, Debug)]
+struct Snapshot {
+    step: u64,
+    registers: Registers,
+    memory: Vec<u8>,
+    call_stack: Option<CallStack>
+}
+
+impl Snapshot {
+    fn size(&self) -> usize {
+        size_of::<Snapshot>() + self.memory.len()
+    }
+}
+
+/**
+ * Execution history for stepping backwards. Every instruction leaves an undo record with the registers before it
+ * and the memory bytes it overwrote, and every snapshot_interval instructions the whole CPU state is kept as well,
+ * so seeking far back restores a snapshot and undoes the few records after it instead of all of them. When the
+ * records and snapshots outgrow the budget the oldest are dropped.
+ *
+ * Only the CPU is rewound: devices keep their state, and running forward again executes the instructions again,
+ * I/O included. Memory written straight into `Cpu8080::memory` by a host trap isn't undone, though a snapshot
+ * taken after it restores it.
+ * */
+#[derive(Clone, Debug)]
+pub struct History {
+    records: VecDeque<Record>,
+    snapshots: VecDeque<Snapshot>,
+    /**
+     * Number of the instruction the oldest record undoes
+     * */
+    first: u64,
+    used: usize,
+    budget: usize,
+    snapshot_interval: u64,
+    pending: Option<Record>
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUDGET)
+    }
+}
+
+impl History {
+    /**
+     * History holding at most about budget bytes
+     * */
+    pub fn new(budget: usize) -> Self {
+        History {
+            records: VecDeque::new(),
+            snapshots: VecDeque::new(),
+            first: 0,
+            used: 0,
+            budget,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            pending: None
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.evict();
+    }
+
+    pub fn snapshot_interval(&self) -> u64 {
+        self.snapshot_interval
+    }
+
+    pub fn set_snapshot_interval(&mut self, interval: u64) {
+        self.snapshot_interval = interval.max(1);
+    }
+
+    /**
+     * Bytes taken by the records and snapshots
+     * */
+    pub fn memory_used(&self) -> usize {
+        self.used
+    }
+
+    /**
+     * Number of instructions recorded since the history was started or cleared
+     * */
+    pub fn position(&self) -> u64 {
+        self.first + self.records.len() as u64
+    }
+
+    /**
+     * How many instructions can be stepped back
+     * */
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /**
+     * Forgets everything, for when the machine state is replaced, e.g. by loading a program or a save state
+     * */
+    pub fn clear(&mut self) {
+        self.first = self.position();
+        self.records.clear();
+        self.snapshots.clear();
+        self.used = 0;
+        self.pending = None;
+    }
+
+    /**
+     * Called before an instruction runs: takes a snapshot when one is due and starts logging the bytes it overwrites
+     * */
+    pub(crate) fn begin(&mut self, cpu: &mut Cpu8080) {
+        let step = self.position();
+        let due = step.is_multiple_of(self.snapshot_interval) && self.snapshots.back().is_none_or(|s| s.step != step);
+        if due {
+            let snapshot = Snapshot {
+                step,
+                registers: Registers::of(cpu),
+                memory: cpu.memory.clone(),
+                call_stack: cpu.call_stack.clone()
+            };
+            self.used += snapshot.size();
+            self.snapshots.push_back(snapshot);
+        }
+
+        let call_stack = match &cpu.call_stack {
+            None => CallStackUndo::Untracked,
+            Some(stack) if is_return(cpu.memory[cpu.pc as usize]) => CallStackUndo::Replace(stack.clone()),
+            Some(stack) => CallStackUndo::Truncate { depth: stack.depth(), mismatches: stack.mismatches.len() }
+        };
+        self.pending = Some(Record { registers: Registers::of(cpu), memory: Vec::new(), call_stack });
+        cpu.undo_log = Some(Vec::new());
+    }
+
+    /**
+     * Called after the instruction ran: turns what it overwrote into its undo record
+     * */
+    pub(crate) fn end(&mut self, cpu: &mut Cpu8080) {
+        let memory = cpu.undo_log.take().unwrap_or_default();
+        if let Some(mut record) = self.pending.take() {
+            record.memory = memory;
+            record.memory.shrink_to_fit();
+            self.used += record.size();
+            self.records.push_back(record);
+            self.evict();
+        }
+    }
+
+    /**
+     * Undoes the last recorded instruction, false when there is nothing left to undo
+     * */
+    pub fn step_back(&mut self, cpu: &mut Cpu8080) -> bool {
+        let Some(record) = self.records.pop_back() else { return false };
+        self.used -= record.size();
+        record.undo(cpu);
+        self.drop_snapshots_after(self.position());
+        true
+    }
+
+    /**
+     * Goes back count instructions, or as far as the history reaches, through the nearest snapshot when there is
+     * one in between. Returns how many instructions were undone
+     * */
+    pub fn seek_back(&mut self, cpu: &mut Cpu8080, count: u64) -> u64 {
+        let start = self.position();
+        let target = start.saturating_sub(count).max(self.first);
+        if let Some(snapshot) = self.snapshots.iter().find(|s| s.step >= target && s.step < start).cloned() {
+            while self.position() > snapshot.step {
+                let record = self.records.pop_back().unwrap();
+                self.used -= record.size();
+            }
+            snapshot.registers.restore(cpu);
+            cpu.memory.copy_from_slice(&snapshot.memory);
+            if cpu.call_stack.is_some() {
+                cpu.call_stack = snapshot.call_stack;
+            }
+            self.drop_snapshots_after(snapshot.step);
+        }
+        while self.position() > target && self.step_back(cpu) {}
+        start - self.position()
+    }
+
+    /**
+     * Snapshots past step describe a future that will be executed again, and may turn out differently
+     * */
+    fn drop_snapshots_after(&mut self, step: u64) {
+        while self.snapshots.back().is_some_and(|s| s.step > step) {
+            let snapshot = self.snapshots.pop_back().unwrap();
+            self.used -= snapshot.size();
+        }
+    }
+
+    /**
+     * Drops the oldest records, then the snapshots that no longer have records after them, until within budget
+     * */
+    fn evict(&mut self) {
+        while self.used > self.budget {
+            if let Some(record) = self.records.pop_front() {
+                self.first += 1;
+                self.used -= record.size();
+            } else if let Some(snapshot) = self.snapshots.pop_front() {
+                self.used -= snapshot.size();
+            } else {
+                break;
+            }
+            while self.snapshots.front().is_some_and(|s| s.step < self.first) {
+                let snapshot = self.snapshots.pop_front().unwrap();
+                self.used -= snapshot.size();
+            }
+        }
+    }
+}
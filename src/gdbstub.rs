@@ -0,0 +1,486 @@
+use crate::{Cpu8080, ConditionBitset};
+use crate::debugger::{Debugger, Machine, PointKind, StopReason, WatchKind};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/**
+ * Register numbers used by g/G/p/P, matching TARGET_XML
+ * */
+pub const REGISTERS: [&str; 10] = ["a", "f", "b", "c", "d", "e", "h", "l", "sp", "pc"];
+
+pub const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.rusty-i8080.cpu">
+    <flags id="i8080_flags" size="1">
+      <field name="CY" start="0" end="0"/>
+      <field name="P" start="2" end="2"/>
+      <field name="AC" start="4" end="4"/>
+      <field name="Z" start="6" end="6"/>
+      <field name="S" start="7" end="7"/>
+    </flags>
+    <reg name="a" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="f" bitsize="8" type="i8080_flags"/>
+    <reg name="b" bitsize="8" type="uint8"/>
+    <reg name="c" bitsize="8" type="uint8"/>
+    <reg name="d" bitsize="8" type="uint8"/>
+    <reg name="e" bitsize="8" type="uint8"/>
+    <reg name="h" bitsize="8" type="uint8"/>
+    <reg name="l" bitsize="8" type="uint8"/>
+    <reg name="sp" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+// instructions run between checks for a ^C from the client while continuing
+const SLICE: u64 = 10_000;
+const INTERRUPT: u8 = 0x03;
+const SIGTRAP: &str = "S05";
+const SIGINT: &str = "S02";
+
+/**
+ * GDB Remote Serial Protocol server for any machine built on the crate. Serves one client at a time over TCP with
+ * register, memory, step/continue, breakpoint and watchpoint packets, on top of `Debugger`. 16 bit registers go
+ * over the wire little endian. With history enabled on the debugger, reverse-stepi and reverse-continue work too.
+ *
+ *  let mut stub = GdbStub::new(machine);
+ *  stub.serve("127.0.0.1:1234")?;
+ * */
+pub struct GdbStub<M: Machine> {
+    pub debugger: Debugger<M>,
+    /**
+     * Debugger point for each Z packet, keyed by type, address and length
+     * */
+    points: HashMap<(u8, u16, u16), usize>,
+    no_ack: bool
+}
+
+impl<M: Machine> GdbStub<M> {
+    pub fn new(machine: M) -> Self {
+        Self::with_debugger(Debugger::new(machine))
+    }
+
+    pub fn with_debugger(debugger: Debugger<M>) -> Self {
+        GdbStub { debugger, points: HashMap::new(), no_ack: false }
+    }
+
+    pub fn into_inner(self) -> Debugger<M> {
+        self.debugger
+    }
+
+    /**
+     * Waits for one client on addr and serves it until it detaches, kills or disconnects
+     * */
+    pub fn serve<A: ToSocketAddrs>(&mut self, addr: A) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        let (stream, _) = listener.accept()?;
+        self.session(stream)
+    }
+
+    /**
+     * Serves a connected client until it detaches, kills or disconnects. Breakpoints and watchpoints it set are removed
+     * */
+    pub fn session(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+        self.session_on(stream)
+    }
+
+    /**
+     * Like session, over any transport
+     * */
+    pub fn session_on<T: Transport>(&mut self, stream: T) -> io::Result<()> {
+        self.no_ack = false;
+        let result = self.packets(&mut Connection { stream, pending: VecDeque::new() });
+        for (_, id) in self.points.drain() {
+            self.debugger.remove(id);
+        }
+        result
+    }
+
+    fn packets<T: Transport>(&mut self, connection: &mut Connection<T>) -> io::Result<()> {
+        loop {
+            let packet = match connection.read_packet()? {
+                Some(Ok(packet)) => packet,
+                Some(Err(())) => {
+                    if !self.no_ack {
+                        connection.stream.write_all(b"-")?;
+                    }
+                    continue;
+                },
+                None => return Ok(())
+            };
+            if !self.no_ack {
+                connection.stream.write_all(b"+")?;
+            }
+            let (reply, done) = match packet.as_str() {
+                "D" | "D;1" | "vKill;1" => ("OK".to_string(), true),
+                // k has no reply
+                "k" => return Ok(()),
+                _ => (self.handle(&packet, connection)?, false)
+            };
+            connection.write_packet(&reply)?;
+            if packet == "QStartNoAckMode" {
+                self.no_ack = true;
+            }
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    fn handle<T: Transport>(&mut self, packet: &str, connection: &mut Connection<T>) -> io::Result<String> {
+        if packet.is_empty() {
+            return Ok(String::new());
+        }
+        let (command, args) = packet.split_at(1);
+        let reply = match command {
+            "?" => SIGTRAP.to_string(),
+            "g" => REGISTERS.iter().enumerate().map(|(n, _)| self.read_register(n)).collect(),
+            "G" => match decode_hex(args) {
+                // all or nothing, a short packet leaves the registers alone
+                Some(bytes) if bytes.len() >= (0..REGISTERS.len()).map(register_width).sum() => {
+                    let mut offset = 0;
+                    for n in 0..REGISTERS.len() {
+                        let width = register_width(n);
+                        self.write_register(n, &bytes[offset..offset + width]);
+                        offset += width;
+                    }
+                    "OK".to_string()
+                },
+                _ => "E01".to_string()
+            },
+            "p" => match usize::from_str_radix(args, 16) {
+                Ok(n) if n < REGISTERS.len() => self.read_register(n),
+                _ => "E01".to_string()
+            },
+            "P" => {
+                let parsed = args.split_once('=').and_then(|(n, value)| Some((usize::from_str_radix(n, 16).ok()?, decode_hex(value)?)));
+                match parsed {
+                    Some((n, value)) if n < REGISTERS.len() && value.len() == register_width(n) => {
+                        self.write_register(n, &value);
+                        "OK".to_string()
+                    },
+                    _ => "E01".to_string()
+                }
+            },
+            "m" => match parse_range(args) {
+                Some((addr, len)) => {
+                    let memory = &self.debugger.cpu().memory;
+                    (0..len).map(|i| format!("{:02x}", memory[addr.wrapping_add(i) as usize])).collect()
+                },
+                None => "E01".to_string()
+            },
+            "M" => {
+                let parsed = args.split_once(':').and_then(|(range, data)| Some((parse_range(range)?, decode_hex(data)?)));
+                match parsed {
+                    Some(((addr, len), data)) if data.len() == len as usize => {
+                        self.debugger.cpu_mut().load(addr, &data);
+                        "OK".to_string()
+                    },
+                    _ => "E01".to_string()
+                }
+            },
+            "s" | "c" => {
+                if let Ok(addr) = u16::from_str_radix(args, 16) {
+                    self.debugger.cpu_mut().pc = addr;
+                }
+                if command == "s" {
+                    let reason = self.debugger.step();
+                    self.stop_reply(reason)
+                } else {
+                    self.resume(connection)?
+                }
+            },
+            "b" if self.debugger.history().is_some() => match args {
+                "s" => {
+                    let reason = self.debugger.step_back();
+                    self.stop_reply(reason)
+                },
+                "c" => {
+                    let reason = self.debugger.reverse_continue();
+                    self.stop_reply(reason)
+                },
+                _ => String::new()
+            },
+            "Z" | "z" => self.breakpoint_packet(command == "Z", args),
+            "H" | "T" => "OK".to_string(),
+            "q" | "Q" | "v" => self.query(packet),
+            _ => String::new()
+        };
+        Ok(reply)
+    }
+
+    fn query(&self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            let reverse = if self.debugger.history().is_some() { ";ReverseStep+;ReverseContinue+" } else { "" };
+            return format!("PacketSize=4000;qXfer:features:read+;QStartNoAckMode+{}", reverse);
+        }
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            return match parse_range(range) {
+                Some((offset, len)) => {
+                    let xml = TARGET_XML.as_bytes();
+                    let start = (offset as usize).min(xml.len());
+                    let end = (start + len as usize).min(xml.len());
+                    let chunk = String::from_utf8_lossy(&xml[start..end]);
+                    format!("{}{}", if end == xml.len() { "l" } else { "m" }, chunk)
+                },
+                None => "E01".to_string()
+            };
+        }
+        match packet {
+            "QStartNoAckMode" => "OK".to_string(),
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            "qSymbol::" => "OK".to_string(),
+            _ => String::new()
+        }
+    }
+
+    /**
+     * Continues until the debugger stops or the client sends ^C
+     * */
+    fn resume<T: Transport>(&mut self, connection: &mut Connection<T>) -> io::Result<String> {
+        let mut reason = self.debugger.run_for(SLICE);
+        while reason == StopReason::Limit {
+            if connection.interrupted()? {
+                return Ok(SIGINT.to_string());
+            }
+            reason = match self.debugger.check_breakpoints() {
+                Some(reason) => reason,
+                None => self.debugger.run_for(SLICE)
+            };
+        }
+        Ok(self.stop_reply(reason))
+    }
+
+    fn stop_reply(&self, reason: StopReason) -> String {
+        match reason {
+            StopReason::Watchpoint { id, access } => {
+                let kind = self.debugger.point(id).map(|point| match point.kind {
+                    PointKind::Watchpoint { kind: WatchKind::Read, .. } => "rwatch",
+                    PointKind::Watchpoint { kind: WatchKind::Access, .. } => "awatch",
+                    _ => "watch"
+                }).unwrap_or("watch");
+                format!("T05{}:{:04x};", kind, access.addr)
+            },
+            StopReason::HistoryStart => "T05replaylog:begin;".to_string(),
+            _ => SIGTRAP.to_string()
+        }
+    }
+
+    /**
+     * Z/z packets: type 0 (software breakpoint), 2 (write), 3 (read) and 4 (access watchpoint)
+     * */
+    fn breakpoint_packet(&mut self, insert: bool, args: &str) -> String {
+        let Some((kind, addr, len)) = parse_breakpoint(args) else { return "E01".to_string() };
+        let watch = match kind {
+            0 => None,
+            2 => Some(WatchKind::Write),
+            3 => Some(WatchKind::Read),
+            4 => Some(WatchKind::Access),
+            _ => return String::new()
+        };
+
+        let key = (kind, addr, len);
+        if insert {
+            if !self.points.contains_key(&key) {
+                let id = match watch {
+                    Some(watch) => self.debugger.add_watchpoint(addr..=addr.saturating_add(len.max(1) - 1), watch),
+                    None => self.debugger.add_breakpoint(addr)
+                };
+                self.points.insert(key, id);
+            }
+        } else if let Some(id) = self.points.remove(&key) {
+            self.debugger.remove(id);
+        }
+        "OK".to_string()
+    }
+
+    fn read_register(&self, n: usize) -> String {
+        let cpu = self.debugger.cpu();
+        match n {
+            8 => encode_hex(&cpu.sp.to_le_bytes()),
+            9 => encode_hex(&cpu.pc.to_le_bytes()),
+            _ => format!("{:02x}", register_byte(cpu, n))
+        }
+    }
+
+    fn write_register(&mut self, n: usize, value: &[u8]) {
+        let cpu = self.debugger.cpu_mut();
+        match n {
+            0 => cpu.a = value[0],
+            1 => cpu.condition_codes = ConditionBitset::from_psw(value[0]),
+            2 => cpu.b = value[0],
+            3 => cpu.c = value[0],
+            4 => cpu.d = value[0],
+            5 => cpu.e = value[0],
+            6 => cpu.h = value[0],
+            7 => cpu.l = value[0],
+            8 => cpu.sp = u16::from_le_bytes([value[0], value[1]]),
+            _ => cpu.pc = u16::from_le_bytes([value[0], value[1]])
+        }
+    }
+}
+
+fn register_width(n: usize) -> usize {
+    if n >= 8 { 2 } else { 1 }
+}
+
+fn register_byte(cpu: &Cpu8080, n: usize) -> u8 {
+    match n {
+        0 => cpu.a,
+        1 => cpu.condition_codes.to_psw(),
+        2 => cpu.b,
+        3 => cpu.c,
+        4 => cpu.d,
+        5 => cpu.e,
+        6 => cpu.h,
+        _ => cpu.l
+    }
+}
+
+/**
+ * "type,addr,kind" of a Z packet, ignoring any conditions after the kind
+ * */
+fn parse_breakpoint(args: &str) -> Option<(u8, u16, u16)> {
+    let mut fields = args.splitn(3, ',');
+    let kind = fields.next()?.parse().ok()?;
+    let addr = u16::from_str_radix(fields.next()?, 16).ok()?;
+    let len = u16::from_str_radix(fields.next()?.split(';').next()?, 16).ok()?;
+    Some((kind, addr, len))
+}
+
+/**
+ * "addr,len" in hex
+ * */
+fn parse_range(text: &str) -> Option<(u16, u16)> {
+    let (addr, len) = text.split_once(',')?;
+    Some((u16::from_str_radix(addr, 16).ok()?, u16::from_str_radix(len, 16).ok()?))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/**
+ * Byte stream a client is served over, e.g. a TcpStream
+ * */
+pub trait Transport: Read + Write {
+    /**
+     * A byte the client has already sent, None when none is waiting. Must not block
+     * */
+    fn poll_byte(&mut self) -> io::Result<Option<u8>>;
+}
+
+impl Transport for TcpStream {
+    fn poll_byte(&mut self) -> io::Result<Option<u8>> {
+        self.set_nonblocking(true)?;
+        let mut byte = [0];
+        let result = self.read(&mut byte);
+        self.set_nonblocking(false)?;
+        match result {
+            Ok(1) => Ok(Some(byte[0])),
+            Ok(_) => Err(io::ErrorKind::UnexpectedEof.into()),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for &mut T {
+    fn poll_byte(&mut self) -> io::Result<Option<u8>> {
+        (**self).poll_byte()
+    }
+}
+
+struct Connection<T: Transport> {
+    stream: T,
+    /**
+     * Bytes read while checking for ^C, they belong to the next packet
+     * */
+    pending: VecDeque<u8>
+}
+
+impl<T: Transport> Connection<T> {
+    /**
+     * Next byte from the client, None when it hung up
+     * */
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        if let Some(byte) = self.pending.pop_front() {
+            return Ok(Some(byte));
+        }
+        let mut byte = [0];
+        match self.stream.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0]))
+        }
+    }
+
+    /**
+     * Whether the client sent ^C, without blocking. Anything else it sent is kept for read_packet
+     * */
+    fn interrupted(&mut self) -> io::Result<bool> {
+        while let Some(byte) = self.stream.poll_byte()? {
+            if byte == INTERRUPT {
+                return Ok(true);
+            }
+            self.pending.push_back(byte);
+        }
+        Ok(false)
+    }
+
+    /**
+     * Next $packet#xx from the client, skipping acks and stray ^Cs. Err when the checksum is wrong, None when the
+     * client hung up
+     * */
+    fn read_packet(&mut self) -> io::Result<Option<Result<String, ()>>> {
+        loop {
+            match self.byte()? {
+                None => return Ok(None),
+                Some(b'$') => break,
+                Some(_) => {}
+            }
+        }
+
+        let mut data = Vec::new();
+        let mut sum = 0u8;
+        let mut escaped = false;
+        loop {
+            let Some(byte) = self.byte()? else { return Ok(None) };
+            match byte {
+                b'#' => break,
+                // escaped byte, only used by binary packets
+                b'}' => escaped = true,
+                b if escaped => {
+                    data.push(b ^ 0x20);
+                    escaped = false;
+                },
+                b => data.push(b)
+            }
+            sum = sum.wrapping_add(byte);
+        }
+        let (Some(high), Some(low)) = (self.byte()?, self.byte()?) else { return Ok(None) };
+        let checksum = std::str::from_utf8(&[high, low]).ok().and_then(|c| u8::from_str_radix(c, 16).ok());
+        if checksum != Some(sum) {
+            return Ok(Some(Err(())));
+        }
+        Ok(Some(Ok(String::from_utf8_lossy(&data).into_owned())))
+    }
+
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        write!(self.stream, "${}#{:02x}", data, checksum)?;
+        self.stream.flush()
+    }
+}
@@ -0,0 +1,85 @@
+use crate::Cpu8080;
+use std::io;
+
+/**
+ * Contents of an Intel HEX file
+ * */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HexImage {
+    /**
+     * Data records merged into runs of consecutive bytes, in file order
+     * */
+    pub chunks: Vec<(u16, Vec<u8>)>,
+    /**
+     * Entry point from a start address record, if there is one
+     * */
+    pub start: Option<u16>
+}
+
+impl HexImage {
+    /**
+     * Parses the records of an Intel HEX file, checking every checksum. Anything past the end of file record is ignored,
+     * extended address records must stay within the 8080's 64K
+     * */
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut image = HexImage::default();
+        for (n, line) in text.lines().enumerate().map(|(n, line)| (n + 1, line.trim())) {
+            if line.is_empty() {
+                continue;
+            }
+            let error = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", n, msg));
+
+            let hex = line.strip_prefix(':').ok_or_else(|| error("record doesn't start with ':'"))?;
+            if hex.len() % 2 != 0 || hex.len() < 10 {
+                return Err(error("bad record length"));
+            }
+            // get rather than indexing, a non-ASCII character doesn't split into digit pairs
+            let bytes = (0..hex.len()).step_by(2)
+                .map(|i| hex.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| error("bad hex digit"))?;
+            if bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+                return Err(error("bad checksum"));
+            }
+            let len = bytes[0] as usize;
+            if bytes.len() != len + 5 {
+                return Err(error("byte count doesn't match record length"));
+            }
+            let addr = u16::from_be_bytes([bytes[1], bytes[2]]);
+            let data = &bytes[4..4 + len];
+
+            match bytes[3] {
+                0x00 => image.add(addr, data),
+                0x01 => break,
+                0x02 | 0x04 if data.iter().all(|b| *b == 0) => {},
+                0x02 | 0x04 => return Err(error("extended address beyond 64K")),
+                0x03 | 0x05 if len == 4 => image.start = Some(u16::from_be_bytes([data[2], data[3]])),
+                _ => return Err(error("unsupported record type"))
+            }
+        }
+        Ok(image)
+    }
+
+    fn add(&mut self, addr: u16, data: &[u8]) {
+        if let Some((start, bytes)) = self.chunks.last_mut() {
+            if start.wrapping_add(bytes.len() as u16) == addr {
+                bytes.extend_from_slice(data);
+                return;
+            }
+        }
+        self.chunks.push((addr, data.to_vec()));
+    }
+
+    /**
+     * Entry point: the start address record, otherwise the first data byte
+     * */
+    pub fn entry(&self) -> Option<u16> {
+        self.start.or_else(|| self.chunks.first().map(|(addr, _)| *addr))
+    }
+
+    pub fn load_into(&self, cpu: &mut Cpu8080) {
+        for (addr, bytes) in self.chunks.iter() {
+            cpu.load(*addr, bytes);
+        }
+    }
+}
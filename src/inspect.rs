@@ -0,0 +1,176 @@
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+
+// bytes per hexdump row
+const ROW: usize = 16;
+// bytes of each side shown per changed range in a diff
+const DIFF_BYTES: usize = 8;
+
+/**
+ * Writes range of memory as hexdump rows of 16 bytes with the printable ones in an ASCII column. The part of range
+ * past the end of memory is left out:
+ *
+ *  0100  C3 03 01 48 45 4C 4C 4F 00 00 00 00 00 00 00 00  ...HELLO........
+ * */
+pub fn write_hexdump<W: Write>(sink: &mut W, memory: &[u8], range: RangeInclusive<u16>) -> io::Result<()> {
+    let (start, end) = (*range.start() as usize, (*range.end() as usize).min(memory.len().wrapping_sub(1)));
+    if memory.is_empty() || start > end {
+        return Ok(());
+    }
+    write_hexdump_with(sink, start as u16, (end - start + 1) as u32, |addr| memory[addr as usize])
+}
+
+/**
+ * Like write_hexdump, for len bytes from addr, each read through read so ROM or a mapped device shows what the CPU
+ * would see. Wraps from FFFF round to 0000
+ * */
+pub fn write_hexdump_with<W: Write>(sink: &mut W, addr: u16, len: u32, read: impl Fn(u16) -> u8) -> io::Result<()> {
+    for offset in (0..len).step_by(ROW) {
+        let row = addr.wrapping_add(offset as u16);
+        let bytes: Vec<u8> = (0..(len - offset).min(ROW as u32) as u16).map(|i| read(row.wrapping_add(i))).collect();
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        let ascii: String = bytes.iter().map(|&b| if (0x20..0x7F).contains(&b) { b as char } else { '.' }).collect();
+        writeln!(sink, "{:04X}  {:<47}  {}", row, hex.join(" "), ascii)?;
+    }
+    Ok(())
+}
+
+/**
+ * Bytes to search for, None matching any byte
+ * */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern(pub Vec<Option<u8>>);
+
+impl Pattern {
+    pub fn bytes(bytes: &[u8]) -> Self {
+        Pattern(bytes.iter().map(|&b| Some(b)).collect())
+    }
+
+    pub fn text(text: &str) -> Self {
+        Self::bytes(text.as_bytes())
+    }
+
+    /**
+     * Either a quoted string, "HELLO", or hex bytes with ?? for any byte, e.g. `CD ?? 01` or `CD??01`
+     * */
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(quoted) = text.strip_prefix('"') {
+            return Some(Self::text(quoted.strip_suffix('"')?)).filter(|p| !p.0.is_empty());
+        }
+        let digits: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let valid = digits.chars().all(|c| c.is_ascii_hexdigit() || c == '?');
+        if !valid || digits.is_empty() || !digits.len().is_multiple_of(2) {
+            return None;
+        }
+        let bytes = (0..digits.len()).step_by(2).map(|i| match &digits[i..i + 2] {
+            "??" => Some(None),
+            hex => u8::from_str_radix(hex, 16).ok().map(Some)
+        });
+        bytes.collect::<Option<Vec<Option<u8>>>>().map(Pattern)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn matches_at(&self, memory: &[u8], addr: usize) -> bool {
+        memory.get(addr..addr + self.len())
+            .is_some_and(|bytes| bytes.iter().zip(self.0.iter()).all(|(b, p)| p.is_none_or(|p| p == *b)))
+    }
+
+    /**
+     * Like matches_at, reading each byte through read. A match wraps from FFFF round to 0000
+     * */
+    pub fn matches_at_with(&self, addr: u16, read: impl Fn(u16) -> u8) -> bool {
+        self.0.iter().enumerate().all(|(i, p)| p.is_none_or(|p| p == read(addr.wrapping_add(i as u16))))
+    }
+
+    /**
+     * Addresses in range where the pattern starts. A match has to lie within memory but may run past the end of
+     * range
+     * */
+    pub fn find(&self, memory: &[u8], range: RangeInclusive<u16>) -> Vec<u16> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        (*range.start() as usize..=*range.end() as usize)
+            .filter(|&addr| self.matches_at(memory, addr))
+            .map(|addr| addr as u16)
+            .collect()
+    }
+
+    /**
+     * Like find, reading memory through read
+     * */
+    pub fn find_with(&self, range: RangeInclusive<u16>, read: impl Fn(u16) -> u8) -> Vec<u16> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        range.filter(|&addr| self.matches_at_with(addr, &read)).collect()
+    }
+}
+
+/**
+ * Ranges of addresses whose bytes differ between two memory images, e.g. a snapshot taken with
+ * `cpu.memory.clone()` and the memory now. Where one image is longer the extra bytes count as changed.
+ * Only the first 64K of an image is compared
+ * */
+pub fn diff(old: &[u8], new: &[u8]) -> Vec<RangeInclusive<u16>> {
+    changed_ranges(old.len().max(new.len()), |addr| old.get(addr) != new.get(addr))
+}
+
+/**
+ * Ranges where a snapshot differs from memory as read through read now
+ * */
+pub fn diff_with(old: &[u8], read: impl Fn(u16) -> u8) -> Vec<RangeInclusive<u16>> {
+    changed_ranges(old.len(), |addr| old[addr] != read(addr as u16))
+}
+
+fn changed_ranges(len: usize, changed: impl Fn(usize) -> bool) -> Vec<RangeInclusive<u16>> {
+    let mut ranges: Vec<RangeInclusive<u16>> = Vec::new();
+    for addr in (0..len.min(0x10000)).filter(|&addr| changed(addr)) {
+        let addr = addr as u16;
+        match ranges.last_mut() {
+            Some(range) if *range.end() == addr.wrapping_sub(1) => *range = *range.start()..=addr,
+            _ => ranges.push(addr..=addr)
+        }
+    }
+    ranges
+}
+
+/**
+ * Writes one line per changed range with its old and new bytes, the first 8 of each when it is longer. A byte
+ * past the end of the shorter image shows as --:
+ *
+ *  2000-2001  (2)  00 00 -> 3E 01
+ * */
+pub fn write_diff<W: Write>(sink: &mut W, old: &[u8], new: &[u8]) -> io::Result<()> {
+    write_ranges(sink, diff(old, new), |addr| old.get(addr as usize).copied(), |addr| new.get(addr as usize).copied())
+}
+
+/**
+ * Like write_diff, for a snapshot against memory as read through read now
+ * */
+pub fn write_diff_with<W: Write>(sink: &mut W, old: &[u8], read: impl Fn(u16) -> u8) -> io::Result<()> {
+    write_ranges(sink, diff_with(old, &read), |addr| Some(old[addr as usize]), |addr| Some(read(addr)))
+}
+
+fn write_ranges<W: Write>(sink: &mut W, ranges: Vec<RangeInclusive<u16>>, old: impl Fn(u16) -> Option<u8>,
+                          new: impl Fn(u16) -> Option<u8>) -> io::Result<()> {
+    let hex = |range: &RangeInclusive<u16>, read: &dyn Fn(u16) -> Option<u8>| {
+        let shown: Vec<String> = range.clone().take(DIFF_BYTES)
+            .map(|addr| read(addr).map_or("--".to_string(), |b| format!("{:02X}", b)))
+            .collect();
+        format!("{}{}", shown.join(" "), if range.len() > DIFF_BYTES { " ..." } else { "" })
+    };
+    for range in ranges {
+        let (start, end) = (*range.start(), *range.end());
+        writeln!(sink, "{:04X}-{:04X}  ({})  {} -> {}", start, end, end as usize - start as usize + 1, hex(&range, &old), hex(&range, &new))?;
+    }
+    Ok(())
+}
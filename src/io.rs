@@ -0,0 +1,39 @@
+use std::io;
+
+/**
+ * Devices on the 8080's 256 I/O ports, driven by the IN and OUT instructions
+ * */
+pub trait IoBus {
+    fn input(&mut self, port: u8) -> u8;
+    fn output(&mut self, port: u8, value: u8);
+
+    /**
+     * Called after every instruction with the cycles it took, for devices that run on the CPU clock
+     * */
+    fn tick(&mut self, _cycles: u32) {}
+
+    /**
+     * Device state for save states (see `savestate`), empty for devices without any
+     * */
+    fn save_state(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn load_state(&mut self, _state: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * Bus with nothing attached, reads float high and writes are dropped
+ * */
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NullBus;
+
+impl IoBus for NullBus {
+    fn input(&mut self, _port: u8) -> u8 {
+        0xFF
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
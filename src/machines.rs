@@ -0,0 +1,4 @@
+/**
+ * Complete systems built around `Cpu8080`: memory, I/O devices and a run loop
+ * */
+pub mod altair;
@@ -0,0 +1,215 @@
+pub mod dcdd;
+pub mod panel;
+
+use crate::Cpu8080;
+use crate::debugger::Machine;
+use crate::console::Console;
+use crate::io::IoBus;
+use crate::savestate::{SaveState, Snapshot, StateWriter, invalid};
+use std::io;
+use std::ops::RangeInclusive;
+
+// 88-SIO, status bits are active low
+pub const SIO_STATUS: u8 = 0x00;
+pub const SIO_DATA: u8 = 0x01;
+pub const SIO_INPUT_NOT_READY: u8 = 0x01;
+pub const SIO_OUTPUT_NOT_READY: u8 = 0x80;
+
+// 88-2SIO, two 6850 ACIAs. Only port A is connected to the console
+pub const SIO2_A_STATUS: u8 = 0x10;
+pub const SIO2_A_DATA: u8 = 0x11;
+pub const SIO2_B_STATUS: u8 = 0x12;
+pub const SIO2_B_DATA: u8 = 0x13;
+pub const ACIA_RECEIVE_FULL: u8 = 0x01;
+pub const ACIA_TRANSMIT_EMPTY: u8 = 0x02;
+pub const ACIA_MASTER_RESET: u8 = 0x03;
+
+pub const SENSE_SWITCHES: u8 = 0xFF;
+
+/**
+ * A board plugged into the bus, answering the ports in its range
+ * */
+pub struct Card {
+    pub ports: RangeInclusive<u8>,
+    pub device: Box<dyn IoBus>
+}
+
+/**
+ * I/O side of the Altair: the terminal sits on both the 88-SIO and port A of the 88-2SIO so software
+ * written for either board finds it, and IN 0xFF reads the front panel sense switches (A15 - A8).
+ * Further boards are plugged in as cards, which take priority over the built in ports.
+ * */
+pub struct AltairBus<C: Console> {
+    pub console: C,
+    pub sense_switches: u8,
+    pub acia_control: [u8; 2],
+    pub cards: Vec<Card>,
+    /**
+     * Set when the guest polls or reads the terminal after its input has run out
+     * */
+    pub input_ended: bool
+}
+
+impl<C: Console> AltairBus<C> {
+    pub fn new(console: C) -> Self {
+        AltairBus { console, sense_switches: 0, acia_control: [ACIA_MASTER_RESET; 2], cards: Vec::new(), input_ended: false }
+    }
+
+    pub fn plug(&mut self, ports: RangeInclusive<u8>, device: Box<dyn IoBus>) {
+        self.cards.push(Card { ports, device });
+    }
+
+    fn card(&mut self, port: u8) -> Option<&mut Card> {
+        self.cards.iter_mut().find(|card| card.ports.contains(&port))
+    }
+
+    fn console_status(&mut self) -> bool {
+        if self.console.status() {
+            return true;
+        }
+        if self.console.at_eof() {
+            self.input_ended = true;
+        }
+        false
+    }
+
+    fn read_data(&mut self) -> u8 {
+        if self.console_status() {
+            self.console.read().unwrap_or(0)
+        } else {
+            0
+        }
+    }
+}
+
+impl<C: Console> IoBus for AltairBus<C> {
+    fn input(&mut self, port: u8) -> u8 {
+        if let Some(card) = self.card(port) {
+            return card.device.input(port);
+        }
+        match port {
+            SIO_STATUS => if self.console_status() { 0 } else { SIO_INPUT_NOT_READY },
+            SIO_DATA => self.read_data(),
+            SIO2_A_STATUS => {
+                if self.console_status() { ACIA_RECEIVE_FULL | ACIA_TRANSMIT_EMPTY } else { ACIA_TRANSMIT_EMPTY }
+            },
+            SIO2_A_DATA => self.read_data(),
+            SIO2_B_STATUS => ACIA_TRANSMIT_EMPTY,
+            SIO2_B_DATA => 0,
+            SENSE_SWITCHES => self.sense_switches,
+            _ => 0xFF
+        }
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        if let Some(card) = self.card(port) {
+            card.device.output(port, value);
+            return;
+        }
+        match port {
+            SIO_DATA | SIO2_A_DATA => self.console.write(value & 0x7F),
+            SIO2_A_STATUS => self.acia_control[0] = value,
+            SIO2_B_STATUS => self.acia_control[1] = value,
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for card in self.cards.iter_mut() {
+            card.device.tick(cycles);
+        }
+    }
+}
+
+/**
+ * MITS Altair 8800 with 64K of RAM and a serial terminal
+ * */
+pub struct Altair<C: Console> {
+    pub cpu: Cpu8080,
+    pub io: AltairBus<C>
+}
+
+impl<C: Console> Altair<C> {
+    pub fn new(console: C) -> Self {
+        Altair { cpu: Cpu8080::new(), io: AltairBus::new(console) }
+    }
+
+    /**
+     * Loads a binary image and points pc at its start, as if toggled in and started from the front panel
+     * */
+    pub fn load(&mut self, addr: u16, image: &[u8]) {
+        self.cpu.load(addr, image);
+        self.cpu.pc = addr;
+    }
+
+    pub fn step(&mut self) -> u32 {
+        let cycles = self.cpu.step(&mut self.io);
+        self.io.tick(cycles);
+        cycles
+    }
+
+    /**
+     * Runs until the CPU halts, which also happens once the program waits on console input that has run out
+     * */
+    pub fn run(&mut self) {
+        while !self.cpu.halted {
+            self.step();
+            if self.io.input_ended {
+                self.cpu.halted = true;
+            }
+        }
+    }
+}
+
+impl<C: Console> Machine for Altair<C> {
+    fn cpu(&self) -> &Cpu8080 {
+        &self.cpu
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu8080 {
+        &mut self.cpu
+    }
+
+    fn step(&mut self) -> u32 {
+        Altair::step(self)
+    }
+}
+
+const ALTAIR: [u8; 4] = *b"ALTR";
+
+/**
+ * CPU and memory, the sense switches and 2SIO control registers, and the state of every card in plug order.
+ * Restoring needs the same cards plugged in; the terminal itself isn't saved
+ * */
+impl<C: Console> Snapshot for Altair<C> {
+    fn save_state(&self, state: &mut SaveState) {
+        self.cpu.save_state(state);
+        let mut altair = StateWriter::new();
+        altair.u8(self.io.sense_switches)
+            .u8(self.io.acia_control[0]).u8(self.io.acia_control[1])
+            .u8(self.io.cards.len() as u8);
+        for card in self.io.cards.iter() {
+            altair.blob(&card.device.save_state());
+        }
+        state.put(ALTAIR, altair.into_inner());
+    }
+
+    fn load_state(&mut self, state: &SaveState) -> io::Result<()> {
+        let mut altair = state.reader(ALTAIR)?;
+        let sense_switches = altair.u8()?;
+        let acia_control = [altair.u8()?, altair.u8()?];
+        let cards = altair.u8()? as usize;
+        if cards != self.io.cards.len() {
+            return Err(invalid(format!("save state has {} cards plugged in, this Altair has {}", cards, self.io.cards.len())));
+        }
+        let card_states = (0..cards).map(|_| altair.blob()).collect::<io::Result<Vec<&[u8]>>>()?;
+
+        self.cpu.load_state(state)?;
+        self.io.sense_switches = sense_switches;
+        self.io.acia_control = acia_control;
+        for (card, card_state) in self.io.cards.iter_mut().zip(card_states) {
+            card.device.load_state(card_state)?;
+        }
+        Ok(())
+    }
+}
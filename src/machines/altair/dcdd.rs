@@ -0,0 +1,339 @@
+use crate::cpm::disk::{self, DiskImage, Geometry};
+use crate::savestate::{StateReader, StateWriter, invalid};
+use crate::io::IoBus;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+pub const PORTS: RangeInclusive<u8> = 0x08..=0x0A;
+pub const STATUS: u8 = 0x08;
+pub const SELECT: u8 = 0x08;
+pub const CONTROL: u8 = 0x09;
+pub const SECTOR_POSITION: u8 = 0x09;
+pub const DATA: u8 = 0x0A;
+
+pub const DRIVES: usize = 16;
+
+/**
+ * 8" hard sectored Altair disk: 77 tracks of 32 x 137 byte sectors, numbered from 0
+ * */
+pub const ALTAIR_8: Geometry = Geometry {
+    tracks: 77,
+    sectors_per_track: 32,
+    sector_size: 137,
+    first_sector: 0
+};
+
+/**
+ * 360 RPM at the 8080's 2 MHz is 333,333 cycles a revolution, split over 32 sectors
+ * */
+pub const CYCLES_PER_SECTOR: u32 = 333_333 / 32;
+
+/**
+ * How long the sector true pulse is held at the start of every sector
+ * */
+pub const SECTOR_TRUE_CYCLES: u32 = CYCLES_PER_SECTOR / 4;
+
+// Status bits, all active low
+pub const STATUS_ENWD: u8 = 0x01;
+pub const STATUS_MOVE_HEAD: u8 = 0x02;
+pub const STATUS_HEAD: u8 = 0x04;
+pub const STATUS_INTE: u8 = 0x20;
+pub const STATUS_TRACK_0: u8 = 0x40;
+pub const STATUS_NRDA: u8 = 0x80;
+
+// Drive select bits
+pub const SELECT_CLEAR: u8 = 0x80;
+pub const SELECT_DRIVE: u8 = 0x0F;
+
+// Control bits
+pub const CONTROL_STEP_IN: u8 = 0x01;
+pub const CONTROL_STEP_OUT: u8 = 0x02;
+pub const CONTROL_HEAD_LOAD: u8 = 0x04;
+pub const CONTROL_HEAD_UNLOAD: u8 = 0x08;
+pub const CONTROL_INTERRUPT_ENABLE: u8 = 0x10;
+pub const CONTROL_INTERRUPT_DISABLE: u8 = 0x20;
+pub const CONTROL_WRITE_ENABLE: u8 = 0x80;
+
+#[derive(Default)]
+struct Drive {
+    image: Option<DiskImage>,
+    track: u16,
+    head_loaded: bool
+}
+
+/**
+ * MITS 88-DCDD floppy controller. The sector position counter follows the CPU clock through `tick`,
+ * reads stream the sector under the head and a write sequence is committed once all 137 bytes are in
+ * or the sector passes out from under the head.
+ * */
+pub struct Dcdd {
+    drives: Vec<Drive>,
+    selected: Option<usize>,
+    interrupts_enabled: bool,
+
+    rotation: u32,
+    byte: usize,
+    writing: bool,
+    write_buffer: Vec<u8>
+}
+
+impl Default for Dcdd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dcdd {
+    pub fn new() -> Self {
+        Dcdd {
+            drives: (0..DRIVES).map(|_| Drive::default()).collect(),
+            selected: None,
+            interrupts_enabled: false,
+            rotation: 0,
+            byte: 0,
+            writing: false,
+            write_buffer: Vec::with_capacity(ALTAIR_8.sector_size)
+        }
+    }
+
+    pub fn mount(&mut self, drive: usize, image: DiskImage) {
+        self.drives[drive].image = Some(image);
+    }
+
+    pub fn mount_file<P: AsRef<Path>>(&mut self, drive: usize, path: P) -> io::Result<()> {
+        self.mount(drive, DiskImage::open_with(path, ALTAIR_8)?);
+        Ok(())
+    }
+
+    pub fn unmount(&mut self, drive: usize) -> Option<DiskImage> {
+        if self.selected == Some(drive) {
+            self.commit_write();
+            self.selected = None;
+        }
+        self.drives[drive].image.take()
+    }
+
+    pub fn image(&self, drive: usize) -> Option<&DiskImage> {
+        self.drives[drive].image.as_ref()
+    }
+
+    /**
+     * Sector currently passing under the head
+     * */
+    pub fn sector(&self) -> u8 {
+        (self.rotation / CYCLES_PER_SECTOR) as u8
+    }
+
+    pub fn track(&self) -> Option<u16> {
+        self.selected.map(|d| self.drives[d].track)
+    }
+
+    fn drive(&mut self) -> Option<&mut Drive> {
+        match self.selected {
+            Some(d) => Some(&mut self.drives[d]),
+            None => None
+        }
+    }
+
+    fn status(&self) -> u8 {
+        let drive = match self.selected {
+            Some(d) => &self.drives[d],
+            None => return 0xFF
+        };
+
+        let mut status = 0xFF;
+        if self.writing && self.write_buffer.len() < ALTAIR_8.sector_size {
+            status &= !STATUS_ENWD;
+        }
+        status &= !STATUS_MOVE_HEAD;
+        if drive.head_loaded {
+            status &= !STATUS_HEAD;
+            if !self.writing {
+                status &= !STATUS_NRDA;
+            }
+        }
+        if self.interrupts_enabled {
+            status &= !STATUS_INTE;
+        }
+        if drive.track == 0 {
+            status &= !STATUS_TRACK_0;
+        }
+        // bits 3 and 4 are not driven and read as 0
+        status & !0x18
+    }
+
+    fn sector_position(&self) -> u8 {
+        let head_loaded = self.selected.is_some_and(|d| self.drives[d].head_loaded);
+        if !head_loaded {
+            return 0xFF;
+        }
+        let sector_true = self.rotation % CYCLES_PER_SECTOR < SECTOR_TRUE_CYCLES;
+        let sector_true_bit = if sector_true { 0 } else { 1 };
+        0xC0 | (self.sector() << 1) | sector_true_bit
+    }
+
+    fn read_data(&mut self) -> u8 {
+        let (sector, byte) = (self.sector() as u16, self.byte);
+        let drive = match self.drive() {
+            Some(drive) if drive.head_loaded => drive,
+            _ => return 0xFF
+        };
+        let track = drive.track;
+        let value = drive.image.as_ref()
+            .and_then(|image| image.read_sector(track, sector))
+            .and_then(|data| data.get(byte).copied())
+            .unwrap_or(0xFF);
+        self.byte = (self.byte + 1).min(ALTAIR_8.sector_size - 1);
+        value
+    }
+
+    fn write_data(&mut self, value: u8) {
+        if !self.writing {
+            return;
+        }
+        self.write_buffer.push(value);
+        if self.write_buffer.len() >= ALTAIR_8.sector_size {
+            self.commit_write();
+        }
+    }
+
+    fn commit_write(&mut self) {
+        if !self.writing {
+            return;
+        }
+        self.writing = false;
+        let sector = self.sector() as u16;
+        let buffer = std::mem::take(&mut self.write_buffer);
+        if let Some(drive) = self.drive() {
+            let track = drive.track;
+            if let Some(image) = &mut drive.image {
+                image.write_sector(track, sector, &buffer);
+            }
+        }
+    }
+
+    /**
+     * Selects a drive, a drive without a disk stays deselected and reads 0xFF status like on real hardware
+     * */
+    fn select(&mut self, value: u8) {
+        self.commit_write();
+        let drive = (value & SELECT_DRIVE) as usize;
+        self.selected = if value & SELECT_CLEAR == 0 && self.drives[drive].image.is_some() {
+            Some(drive)
+        } else {
+            None
+        };
+    }
+
+    fn control(&mut self, value: u8) {
+        if value & CONTROL_INTERRUPT_ENABLE != 0 {
+            self.interrupts_enabled = true;
+        }
+        if value & CONTROL_INTERRUPT_DISABLE != 0 {
+            self.interrupts_enabled = false;
+        }
+
+        let last_track = ALTAIR_8.tracks as u16 - 1;
+        let start_write = value & CONTROL_WRITE_ENABLE != 0;
+        let drive = match self.drive() {
+            Some(drive) => drive,
+            None => return
+        };
+
+        if value & CONTROL_STEP_IN != 0 {
+            drive.track = (drive.track + 1).min(last_track);
+        }
+        if value & CONTROL_STEP_OUT != 0 {
+            drive.track = drive.track.saturating_sub(1);
+        }
+        if value & CONTROL_HEAD_LOAD != 0 {
+            drive.head_loaded = true;
+        }
+        if value & CONTROL_HEAD_UNLOAD != 0 {
+            drive.head_loaded = false;
+        }
+
+        if start_write {
+            self.writing = true;
+            self.write_buffer.clear();
+        }
+    }
+}
+
+impl IoBus for Dcdd {
+    fn input(&mut self, port: u8) -> u8 {
+        match port {
+            STATUS => self.status(),
+            SECTOR_POSITION => self.sector_position(),
+            DATA => self.read_data(),
+            _ => 0xFF
+        }
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        match port {
+            SELECT => self.select(value),
+            CONTROL => self.control(value),
+            DATA => self.write_data(value),
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        let next = (self.rotation + cycles) % (CYCLES_PER_SECTOR * ALTAIR_8.sectors_per_track as u32);
+        if next / CYCLES_PER_SECTOR != self.rotation / CYCLES_PER_SECTOR {
+            self.commit_write();
+            self.byte = 0;
+        }
+        self.rotation = next;
+    }
+
+    /**
+     * Controller state, the head position of every drive and the contents of every mounted disk
+     * */
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = StateWriter::new();
+        state.u8(self.selected.map_or(0xFF, |d| d as u8))
+            .bool(self.interrupts_enabled)
+            .u32(self.rotation)
+            .u32(self.byte as u32)
+            .bool(self.writing)
+            .blob(&self.write_buffer);
+        for drive in self.drives.iter() {
+            state.u16(drive.track).bool(drive.head_loaded);
+            disk::save_drive(&mut state, drive.image.as_ref());
+        }
+        state.into_inner()
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut state = StateReader::new(data);
+        let selected = state.u8()?;
+        let interrupts_enabled = state.bool()?;
+        let (rotation, byte) = (state.u32()?, state.u32()? as usize);
+        let writing = state.bool()?;
+        let write_buffer = state.blob()?.to_vec();
+        let mut drives = Vec::with_capacity(DRIVES);
+        for _ in 0..DRIVES {
+            let (track, head_loaded) = (state.u16()?, state.bool()?);
+            drives.push((track, head_loaded, disk::read_drive(&mut state, ALTAIR_8)?));
+        }
+        if selected != 0xFF && selected as usize >= DRIVES {
+            return Err(invalid(format!("88-DCDD state selects drive {}", selected)));
+        }
+
+        for (drive, (track, head_loaded, image)) in self.drives.iter_mut().zip(drives) {
+            drive.track = track;
+            drive.head_loaded = head_loaded;
+            disk::restore_drive(&mut drive.image, image, ALTAIR_8)?;
+        }
+        self.selected = if selected == 0xFF { None } else { Some(selected as usize) };
+        self.interrupts_enabled = interrupts_enabled;
+        self.rotation = rotation;
+        self.byte = byte;
+        self.writing = writing;
+        self.write_buffer = write_buffer;
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use super::Altair;
+use crate::console::Console;
+use std::fmt;
+
+// 8080 status word bits, latched at the start of every machine cycle
+pub const STATUS_INTA: u8 = 0x01;
+pub const STATUS_WO: u8 = 0x02;
+pub const STATUS_STACK: u8 = 0x04;
+pub const STATUS_HLTA: u8 = 0x08;
+pub const STATUS_OUT: u8 = 0x10;
+pub const STATUS_M1: u8 = 0x20;
+pub const STATUS_INP: u8 = 0x40;
+pub const STATUS_MEMR: u8 = 0x80;
+
+/**
+ * Kinds of 8080 machine cycle, each with its own status word
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MachineCycle {
+    InstructionFetch,
+    MemoryRead,
+    MemoryWrite,
+    StackRead,
+    StackWrite,
+    InputRead,
+    OutputWrite,
+    InterruptAcknowledge,
+    HaltAcknowledge
+}
+
+impl MachineCycle {
+    /**
+     * Status word as put on the data bus during SYNC. WO is active low, so it is set on every cycle that doesn't write
+     * */
+    pub fn status_word(self) -> u8 {
+        use MachineCycle::*;
+        match self {
+            InstructionFetch => STATUS_MEMR | STATUS_M1 | STATUS_WO,
+            MemoryRead => STATUS_MEMR | STATUS_WO,
+            MemoryWrite => 0,
+            StackRead => STATUS_MEMR | STATUS_STACK | STATUS_WO,
+            StackWrite => STATUS_STACK,
+            InputRead => STATUS_INP | STATUS_WO,
+            OutputWrite => STATUS_OUT,
+            InterruptAcknowledge => STATUS_M1 | STATUS_INTA | STATUS_WO,
+            HaltAcknowledge => STATUS_MEMR | STATUS_HLTA | STATUS_WO
+        }
+    }
+
+    /**
+     * Final machine cycle of an instruction, which is what the panel shows once it completes
+     * */
+    pub fn last_of(opcode: u8) -> Self {
+        use MachineCycle::*;
+        match opcode {
+            0x76 => HaltAcknowledge,
+            0xDB => InputRead,
+            0xD3 => OutputWrite,
+            // PUSH, CALL, Ccc, RST, XTHL
+            0xC5 | 0xD5 | 0xE5 | 0xF5 | 0xE3 => StackWrite,
+            op if op & 0xC7 == 0xC4 || op & 0xC7 == 0xC7 || op & 0xCF == 0xCD => StackWrite,
+            // POP, RET, Rcc
+            0xC1 | 0xD1 | 0xE1 | 0xF1 | 0xC9 | 0xD9 => StackRead,
+            op if op & 0xC7 == 0xC0 => StackRead,
+            // STAX, SHLD, STA, INR M, DCR M, MVI M, MOV M,r
+            0x02 | 0x12 | 0x22 | 0x32 | 0x34 | 0x35 | 0x36 => MemoryWrite,
+            op if op & 0xF8 == 0x70 => MemoryWrite,
+            // LDAX, LHLD, LDA, MOV r,M, ALU M
+            0x0A | 0x1A | 0x2A | 0x3A => MemoryRead,
+            op if (0x40..0xC0).contains(&op) && op & 0x07 == 0x06 => MemoryRead,
+            // instructions whose last cycle fetches an operand byte
+            0x01 | 0x11 | 0x21 | 0x31 | 0x06 | 0x0E | 0x16 | 0x1E | 0x26 | 0x2E | 0x3E => MemoryRead,
+            op if op & 0xC7 == 0xC6 || op & 0xC7 == 0xC2 || op == 0xC3 || op == 0xCB => MemoryRead,
+            _ => InstructionFetch
+        }
+    }
+}
+
+/**
+ * Everything lit on the panel
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Leds {
+    pub address: u16,
+    pub data: u8,
+    pub status: u8,
+    pub inte: bool,
+    pub wait: bool
+}
+
+impl Leds {
+    pub fn memr(&self) -> bool { self.status & STATUS_MEMR != 0 }
+    pub fn inp(&self) -> bool { self.status & STATUS_INP != 0 }
+    pub fn m1(&self) -> bool { self.status & STATUS_M1 != 0 }
+    pub fn out(&self) -> bool { self.status & STATUS_OUT != 0 }
+    pub fn hlta(&self) -> bool { self.status & STATUS_HLTA != 0 }
+    pub fn stack(&self) -> bool { self.status & STATUS_STACK != 0 }
+    pub fn wo(&self) -> bool { self.status & STATUS_WO != 0 }
+    pub fn int(&self) -> bool { self.status & STATUS_INTA != 0 }
+}
+
+/**
+ * Software model of the Altair 8800 front panel. The panel owns the machine the way the real one sits
+ * in front of the bus: the switches drive EXAMINE/DEPOSIT, and while stopped the CPU waits on the
+ * fetch of the instruction at pc. SINGLE STEP runs one whole instruction rather than one machine cycle.
+ * The sense switches seen through IN 0xFF are the upper eight address switches.
+ * */
+pub struct FrontPanel<C: Console> {
+    pub altair: Altair<C>,
+    switches: u16,
+    running: bool,
+    cycle: MachineCycle,
+    address: u16,
+    data: u8
+}
+
+impl<C: Console> FrontPanel<C> {
+    pub fn new(altair: Altair<C>) -> Self {
+        let mut panel = FrontPanel {
+            altair,
+            switches: 0,
+            running: false,
+            cycle: MachineCycle::InstructionFetch,
+            address: 0,
+            data: 0
+        };
+        panel.show_fetch();
+        panel
+    }
+
+    pub fn switches(&self) -> u16 {
+        self.switches
+    }
+
+    /**
+     * Sets all 16 address/data switches, A15 - A8 double as the sense switches
+     * */
+    pub fn set_switches(&mut self, switches: u16) {
+        self.switches = switches;
+        self.altair.io.sense_switches = (switches >> 8) as u8;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn leds(&self) -> Leds {
+        Leds {
+            address: self.address,
+            data: self.data,
+            status: self.cycle.status_word(),
+            inte: self.altair.cpu.interrupts_enabled,
+            wait: !self.running
+        }
+    }
+
+    fn show_fetch(&mut self) {
+        let cpu = &self.altair.cpu;
+        self.cycle = if cpu.halted { MachineCycle::HaltAcknowledge } else { MachineCycle::InstructionFetch };
+        self.address = cpu.pc;
+        self.data = cpu.memory[cpu.pc as usize];
+    }
+
+    pub fn examine(&mut self) {
+        if self.running { return; }
+        self.altair.cpu.pc = self.switches;
+        self.altair.cpu.halted = false;
+        self.show_fetch();
+    }
+
+    pub fn examine_next(&mut self) {
+        if self.running { return; }
+        self.altair.cpu.pc = self.altair.cpu.pc.wrapping_add(1);
+        self.show_fetch();
+    }
+
+    pub fn deposit(&mut self) {
+        if self.running { return; }
+        let pc = self.altair.cpu.pc as usize;
+        self.altair.cpu.memory[pc] = self.switches as u8;
+        self.show_fetch();
+    }
+
+    pub fn deposit_next(&mut self) {
+        self.examine_next();
+        self.deposit();
+    }
+
+    pub fn run(&mut self) {
+        self.running = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+        self.show_fetch();
+    }
+
+    pub fn single_step(&mut self) {
+        if self.running { return; }
+        self.execute();
+        self.show_fetch();
+    }
+
+    /**
+     * RESET clears pc and INTE, the CPU then waits at address 0 (or starts from it when running)
+     * */
+    pub fn reset(&mut self) {
+        let cpu = &mut self.altair.cpu;
+        cpu.pc = 0;
+        cpu.interrupts_enabled = false;
+        cpu.halted = false;
+        self.show_fetch();
+    }
+
+    fn execute(&mut self) -> u32 {
+        let pc = self.altair.cpu.pc;
+        let opcode = self.altair.cpu.memory[pc as usize];
+        let halted = self.altair.cpu.halted;
+
+        let cycles = self.altair.step();
+
+        let cpu = &self.altair.cpu;
+        self.cycle = if halted { MachineCycle::HaltAcknowledge } else { MachineCycle::last_of(opcode) };
+        self.address = cpu.pc;
+        self.data = cpu.memory[cpu.pc as usize];
+        cycles
+    }
+
+    /**
+     * While RUN is on, executes instructions for roughly the given number of cycles. Returns the cycles taken
+     * */
+    pub fn run_for(&mut self, cycles: u64) -> u64 {
+        let mut taken = 0;
+        while self.running && taken < cycles {
+            taken += self.execute() as u64;
+        }
+        taken
+    }
+}
+
+fn lamp(lit: bool) -> &'static str {
+    if lit { "*" } else { "." }
+}
+
+/**
+ * Text rendering of the panel, one lamp per column under its label
+ * */
+impl<C: Console> fmt::Display for FrontPanel<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let leds = self.leds();
+
+        let status = [
+            ("INTE", leds.inte), ("MEMR", leds.memr()), ("INP", leds.inp()), ("M1", leds.m1()),
+            ("OUT", leds.out()), ("HLTA", leds.hlta()), ("STACK", leds.stack()), ("WO", leds.wo()),
+            ("INT", leds.int()), ("WAIT", leds.wait)
+        ];
+        for (label, _) in status.iter() {
+            write!(f, "{:>6}", label)?;
+        }
+        write!(f, "   ")?;
+        for bit in (0..8).rev() {
+            write!(f, " D{}", bit)?;
+        }
+        writeln!(f)?;
+
+        for (_, lit) in status.iter() {
+            write!(f, "{:>6}", lamp(*lit))?;
+        }
+        write!(f, "   ")?;
+        for bit in (0..8).rev() {
+            write!(f, " {:>2}", lamp(leds.data & (1 << bit) != 0))?;
+        }
+        writeln!(f)?;
+
+        for bit in (0..16).rev() {
+            write!(f, " A{:<2}", bit)?;
+        }
+        writeln!(f)?;
+        for bit in (0..16).rev() {
+            write!(f, " {:<3}", lamp(leds.address & (1 << bit) != 0))?;
+        }
+        writeln!(f)?;
+        for bit in (0..16).rev() {
+            write!(f, " {:<3}", if self.switches & (1 << bit) != 0 { "^" } else { "v" })?;
+        }
+        writeln!(f, "   address {:04X}  data {:02X}  switches {:04X}", leds.address, leds.data, self.switches)
+    }
+}
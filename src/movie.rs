@@ -0,0 +1,372 @@
+use crate::Cpu8080;
+use crate::io::IoBus;
+use crate::savestate::{SaveState, Snapshot, StateReader, StateWriter, crc32, invalid};
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const MAGIC: &[u8; 8] = b"I8080MOV";
+pub const VERSION: u16 = 1;
+/**
+ * Default cycles between state hashes, about a second at 2 MHz
+ * */
+pub const DEFAULT_CHECK_INTERVAL: u64 = 2_000_000;
+
+const BUS: [u8; 4] = *b"BUS ";
+
+/**
+ * An input port read returning something other than it did the last time, cycle counting from the start of the
+ * movie
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InputEvent {
+    pub cycle: u64,
+    pub port: u8,
+    pub value: u8
+}
+
+/**
+ * Hash of the machine state after the instruction ending at cycle
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Check {
+    pub cycle: u64,
+    pub hash: u32
+}
+
+/**
+ * A recording of everything read from the input ports (e.g. the coin, start and player controls of an arcade
+ * board) with the CPU cycle it was read at. Played back on the same machine from the same start, it reproduces
+ * the run exactly, which the state hashes taken along the way confirm.
+ *
+ * On disk it is MAGIC, VERSION, the movie data and a CRC-32 of everything before it.
+ * */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Movie {
+    /**
+     * The recorded ports, reads of every other port go to the machine as usual
+     * */
+    pub ports: Vec<u8>,
+    /**
+     * Save state of the CPU and bus the recording started from, None for power-on
+     * */
+    pub start: Option<SaveState>,
+    pub events: Vec<InputEvent>,
+    pub checks: Vec<Check>,
+    /**
+     * Cycles recorded
+     * */
+    pub length: u64
+}
+
+impl Movie {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = StateWriter::new();
+        w.0.extend_from_slice(MAGIC);
+        w.u16(VERSION);
+        w.blob(&self.ports);
+        w.blob(&self.start.as_ref().map(|state| state.to_bytes()).unwrap_or_default());
+        w.u64(self.length);
+        w.u32(self.events.len() as u32);
+        for event in self.events.iter() {
+            w.u64(event.cycle).u8(event.port).u8(event.value);
+        }
+        w.u32(self.checks.len() as u32);
+        for check in self.checks.iter() {
+            w.u64(check.cycle).u32(check.hash);
+        }
+        let checksum = crc32(&w.0);
+        w.u32(checksum);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(invalid("not a movie".to_string()));
+        }
+        if bytes.len() < MAGIC.len() + 6 {
+            return Err(invalid("movie is truncated".to_string()));
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - 4);
+        // as with save states, a damaged version field is corruption rather than another version
+        if crc32(body).to_le_bytes() != checksum {
+            return Err(invalid("movie is corrupt, checksum mismatch".to_string()));
+        }
+        let mut r = StateReader::new(&body[MAGIC.len()..]);
+        let version = r.u16()?;
+        if version != VERSION {
+            return Err(invalid(format!("movie is version {}, this build reads version {}", version, VERSION)));
+        }
+
+        let ports = r.blob()?.to_vec();
+        let start = match r.blob()? {
+            [] => None,
+            state => Some(SaveState::from_bytes(state)?)
+        };
+        let length = r.u64()?;
+        let mut events = Vec::new();
+        for _ in 0..r.u32()? {
+            events.push(InputEvent { cycle: r.u64()?, port: r.u8()?, value: r.u8()? });
+        }
+        let mut checks = Vec::new();
+        for _ in 0..r.u32()? {
+            checks.push(Check { cycle: r.u64()?, hash: r.u32()? });
+        }
+        Ok(Movie { ports, start, events, checks, length })
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+/**
+ * Hash of the CPU, its memory and the bus's device state: the CRC-32 a save state of them ends with
+ * */
+pub fn state_hash(cpu: &Cpu8080, bus: &dyn IoBus) -> u32 {
+    let mut state = SaveState::of(cpu);
+    state.put(BUS, bus.save_state());
+    let bytes = state.to_bytes();
+    u32::from_le_bytes(bytes[bytes.len() - 4..].try_into().unwrap())
+}
+
+fn port_set(ports: &[u8]) -> [bool; 256] {
+    let mut set = [false; 256];
+    for &port in ports {
+        set[port as usize] = true;
+    }
+    set
+}
+
+/**
+ * Records a movie while the machine runs: sits between the CPU and the bus, noting every change in what the
+ * recorded ports read. Run the machine through step instead of `Cpu8080::step`; anything else that affects it,
+ * such as interrupts, has to happen on a cycle count for playback to reproduce it.
+ *
+ *  let mut recorder = Recorder::power_on(&cpu, bus, &[1, 2]);
+ *  while running {
+ *      recorder.step(&mut cpu);
+ *  }
+ *  let (movie, bus) = recorder.finish(&cpu);
+ * */
+pub struct Recorder<B: IoBus> {
+    pub bus: B,
+    movie: Movie,
+    recorded: [bool; 256],
+    last: [Option<u8>; 256],
+    base: u64,
+    now: u64,
+    check_interval: u64,
+    next_check: u64
+}
+
+impl<B: IoBus> Recorder<B> {
+    /**
+     * Starts recording from a freshly powered on machine, ROMs loaded. Playback needs the same
+     * */
+    pub fn power_on(cpu: &Cpu8080, bus: B, ports: &[u8]) -> Self {
+        Self::start(cpu, bus, ports, None)
+    }
+
+    /**
+     * Starts recording from wherever the machine is, the movie carries a save state of the CPU and bus to
+     * start playback from
+     * */
+    pub fn from_state(cpu: &Cpu8080, bus: B, ports: &[u8]) -> Self {
+        let mut state = SaveState::of(cpu);
+        state.put(BUS, bus.save_state());
+        Self::start(cpu, bus, ports, Some(state))
+    }
+
+    fn start(cpu: &Cpu8080, bus: B, ports: &[u8], start: Option<SaveState>) -> Self {
+        let hash = state_hash(cpu, &bus);
+        let movie = Movie { ports: ports.to_vec(), start, checks: vec![Check { cycle: 0, hash }], ..Movie::default() };
+        Recorder {
+            bus,
+            movie,
+            recorded: port_set(ports),
+            last: [None; 256],
+            base: cpu.cycles,
+            now: 0,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            next_check: DEFAULT_CHECK_INTERVAL
+        }
+    }
+
+    /**
+     * Cycles between state hashes, more of them find a desync sooner but make the movie bigger and slower to run
+     * */
+    pub fn set_check_interval(&mut self, cycles: u64) {
+        self.check_interval = cycles.max(1);
+        self.next_check = self.now + self.check_interval;
+    }
+
+    /**
+     * Cycles recorded so far
+     * */
+    pub fn position(&self) -> u64 {
+        self.now
+    }
+
+    pub fn step(&mut self, cpu: &mut Cpu8080) -> u32 {
+        self.now = cpu.cycles - self.base;
+        let cycles = cpu.step(self);
+        self.bus.tick(cycles);
+        self.now = cpu.cycles - self.base;
+        if self.now >= self.next_check {
+            self.movie.checks.push(Check { cycle: self.now, hash: state_hash(cpu, &self.bus) });
+            self.next_check = self.now + self.check_interval;
+        }
+        cycles
+    }
+
+    /**
+     * Ends the recording with a final state hash
+     * */
+    pub fn finish(mut self, cpu: &Cpu8080) -> (Movie, B) {
+        self.movie.length = cpu.cycles - self.base;
+        if self.movie.checks.last().is_none_or(|check| check.cycle != self.movie.length) {
+            self.movie.checks.push(Check { cycle: self.movie.length, hash: state_hash(cpu, &self.bus) });
+        }
+        (self.movie, self.bus)
+    }
+}
+
+impl<B: IoBus> IoBus for Recorder<B> {
+    fn input(&mut self, port: u8) -> u8 {
+        let value = self.bus.input(port);
+        if self.recorded[port as usize] && self.last[port as usize] != Some(value) {
+            self.last[port as usize] = Some(value);
+            self.movie.events.push(InputEvent { cycle: self.now, port, value });
+        }
+        value
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.bus.output(port, value)
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.bus.save_state()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> io::Result<()> {
+        self.bus.load_state(state)
+    }
+}
+
+/**
+ * Plays a movie back: the recorded ports read what they did while recording, at the same cycles, and the state
+ * is hashed where the recording did. The bus still sees every read so devices behave as they did. step fails
+ * on the first hash that doesn't match, or when execution passes a hash's cycle without ending an instruction
+ * on it.
+ * */
+pub struct Player<B: IoBus> {
+    pub bus: B,
+    movie: Movie,
+    recorded: [bool; 256],
+    values: [u8; 256],
+    next_event: usize,
+    next_check: usize,
+    base: u64,
+    now: u64
+}
+
+impl<B: IoBus> Player<B> {
+    /**
+     * Gets cpu and bus to the start of the movie, restoring its save state if it has one, and checks they match it.
+     * For a power-on movie they have to be freshly powered on, ROMs loaded
+     * */
+    pub fn new(movie: Movie, cpu: &mut Cpu8080, mut bus: B) -> io::Result<Self> {
+        if let Some(state) = &movie.start {
+            cpu.load_state(state)?;
+            bus.load_state(state.get(BUS).unwrap_or(&[]))?;
+        }
+        let mut player = Player {
+            bus,
+            recorded: port_set(&movie.ports),
+            movie,
+            values: [0xFF; 256],
+            next_event: 0,
+            next_check: 0,
+            base: cpu.cycles,
+            now: 0
+        };
+        player.verify(cpu)?;
+        Ok(player)
+    }
+
+    pub fn movie(&self) -> &Movie {
+        &self.movie
+    }
+
+    /**
+     * Cycles played so far
+     * */
+    pub fn position(&self) -> u64 {
+        self.now
+    }
+
+    /**
+     * True once playback has reached the end of the recording
+     * */
+    pub fn is_finished(&self) -> bool {
+        self.now >= self.movie.length
+    }
+
+    pub fn step(&mut self, cpu: &mut Cpu8080) -> io::Result<u32> {
+        self.now = cpu.cycles - self.base;
+        let cycles = cpu.step(self);
+        self.bus.tick(cycles);
+        self.now = cpu.cycles - self.base;
+        self.verify(cpu)?;
+        Ok(cycles)
+    }
+
+    fn verify(&mut self, cpu: &Cpu8080) -> io::Result<()> {
+        let Some(&check) = self.movie.checks.get(self.next_check) else { return Ok(()) };
+        if self.now < check.cycle {
+            return Ok(());
+        }
+        self.next_check += 1;
+        if self.now > check.cycle {
+            return Err(invalid(format!("desync: the recording has an instruction ending at cycle {}, playback went on to {}", check.cycle, self.now)));
+        }
+        let hash = state_hash(cpu, &self.bus);
+        if hash != check.hash {
+            return Err(invalid(format!("desync at cycle {}: state hash {:08X}, recorded {:08X}", check.cycle, hash, check.hash)));
+        }
+        Ok(())
+    }
+}
+
+impl<B: IoBus> IoBus for Player<B> {
+    fn input(&mut self, port: u8) -> u8 {
+        let value = self.bus.input(port);
+        if !self.recorded[port as usize] {
+            return value;
+        }
+        while let Some(event) = self.movie.events.get(self.next_event).filter(|event| event.cycle <= self.now) {
+            self.values[event.port as usize] = event.value;
+            self.next_event += 1;
+        }
+        self.values[port as usize]
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.bus.output(port, value)
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.bus.save_state()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> io::Result<()> {
+        self.bus.load_state(state)
+    }
+}
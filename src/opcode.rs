@@ -1,17 +1,94 @@
 use std::collections::HashMap;
-use crate::{Cpu8080, ConditionFlag, combine_bytes, set_byte_pair};
+use crate::{Cpu8080, ConditionFlag, PortAccess, Reg16, combine_bytes};
+use crate::symbols::SymbolTable;
 pub struct OpcodeTable(HashMap<u8, Instruction>);
 
 #[derive(Copy, Clone)]
 pub struct Instruction {
     pub opcode: u8,
     pub size: u8,
+    pub cycles: u8,
     pub disassembly: &'static str,
     pub mnemonic: &'static str,
     pub effected_flags: Option<&'static str>,
     pub func_ptr: fn(&mut Cpu8080, b2: u8, b3: u8)
 }
 
+/**
+ * CALL, Ccc, RST and the undocumented CALL aliases, everything that pushes a return address
+ * */
+pub fn is_call(opcode: u8) -> bool {
+    matches!(opcode, 0xCD | 0xDD | 0xED | 0xFD) || opcode & 0xC7 == 0xC4 || opcode & 0xC7 == 0xC7
+}
+
+/**
+ * RET, Rcc and the undocumented RET alias
+ * */
+pub fn is_return(opcode: u8) -> bool {
+    matches!(opcode, 0xC9 | 0xD9) || opcode & 0xC7 == 0xC0
+}
+
+/**
+ * Jcc, Ccc and Rcc, the instructions that test a flag
+ * */
+pub fn is_conditional(opcode: u8) -> bool {
+    matches!(opcode & 0xC7, 0xC0 | 0xC2 | 0xC4)
+}
+
+/**
+ * The instruction groups of the Intel 8080 manual
+ * */
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    DataTransfer,
+    Arithmetic,
+    Logical,
+    Branch,
+    /**
+     * Stack, I/O and machine control: PUSH, POP, XTHL, SPHL, IN, OUT, EI, DI, HLT and NOP
+     * */
+    Control
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [Category::DataTransfer, Category::Arithmetic, Category::Logical, Category::Branch, Category::Control];
+
+    pub fn of(opcode: u8) -> Self {
+        match opcode {
+            0x76 => Category::Control,
+            0x40..=0x7F => Category::DataTransfer,
+            0x80..=0x9F => Category::Arithmetic,
+            0xA0..=0xBF => Category::Logical,
+            0x02 | 0x0A | 0x12 | 0x1A | 0x22 | 0x2A | 0x32 | 0x3A | 0xEB => Category::DataTransfer,
+            // MVI, LXI
+            _ if opcode & 0xC7 == 0x06 || opcode & 0xCF == 0x01 => Category::DataTransfer,
+            0x27 | 0xC6 | 0xCE | 0xD6 | 0xDE => Category::Arithmetic,
+            // INR, DCR, INX, DCX, DAD
+            _ if opcode & 0xC6 == 0x04 || opcode & 0xC7 == 0x03 || opcode & 0xCF == 0x09 => Category::Arithmetic,
+            0x07 | 0x0F | 0x17 | 0x1F | 0x2F | 0x37 | 0x3F | 0xE6 | 0xEE | 0xF6 | 0xFE => Category::Logical,
+            0xC3 | 0xCB | 0xE9 => Category::Branch,
+            _ if is_call(opcode) || is_return(opcode) || is_conditional(opcode) => Category::Branch,
+            _ => Category::Control
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::DataTransfer => "data transfer",
+            Category::Arithmetic => "arithmetic",
+            Category::Logical => "logical",
+            Category::Branch => "branch",
+            Category::Control => "stack, I/O and control"
+        }
+    }
+}
+
+impl Default for OpcodeTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl OpcodeTable {
 
     fn insert(&mut self, instruction: &Instruction) {
@@ -22,43 +99,79 @@ impl OpcodeTable {
         self.0.get(&opcode)
     }
 
+    /**
+     * Disassembles the instruction at addr with its operands filled in, e.g. "LXI B,$1234". Returns the text and instruction size
+     * */
+    pub fn disassemble(&self, memory: &[u8], addr: u16) -> (String, u8) {
+        self.disassemble_with(memory, addr, |word| format!("${:04X}", word))
+    }
+
+    /**
+     * Like disassemble, but 16 bit operands that have a symbol are shown by name, e.g. "CALL PRINT_STR"
+     * */
+    pub fn disassemble_symbolic(&self, memory: &[u8], addr: u16, symbols: &SymbolTable) -> (String, u8) {
+        self.disassemble_with(memory, addr, |word| match symbols.name_at(word) {
+            Some(name) => name.to_string(),
+            None => format!("${:04X}", word)
+        })
+    }
+
+    fn disassemble_with(&self, memory: &[u8], addr: u16, word_operand: impl Fn(u16) -> String) -> (String, u8) {
+        let byte = |offset: u16| memory[addr.wrapping_add(offset) as usize % memory.len()];
+        let instruction = match self.get(byte(0)) {
+            Some(instruction) => instruction,
+            None => return (format!("DB ${:02X}", byte(0)), 1)
+        };
+
+        let text = instruction.disassembly.replace(", ", ",");
+        let text = if text.contains("D16") || text.contains("adr") {
+            let word = word_operand(combine_bytes(byte(2), byte(1)));
+            text.replace("D16", &word).replace("adr", &word)
+        } else if text.contains("D8") {
+            text.replace("D8", &format!("${:02X}", byte(1)))
+        } else {
+            text
+        };
+        (text, instruction.size)
+    }
+
     pub fn new() -> Self {
         let mut optable = OpcodeTable(HashMap::new());
-        optable.insert(&Instruction { opcode: 0x00, size: 1, disassembly: "NOP",       mnemonic: "",                         effected_flags: None, 
+        optable.insert(&Instruction { opcode: 0x00, size: 1, cycles: 4, disassembly: "NOP",       mnemonic: "",                         effected_flags: None, 
         func_ptr: |_, _, _| { } 
         });
-        optable.insert(&Instruction { opcode: 0x01, size: 3, disassembly: "LXI B,D16", mnemonic: "B <- byte 3, C <- byte 2", effected_flags: None, 
+        optable.insert(&Instruction { opcode: 0x01, size: 3, cycles: 10, disassembly: "LXI B,D16", mnemonic: "B <- byte 3, C <- byte 2", effected_flags: None, 
             func_ptr: |cpu, b2, b3| { cpu.b = b3; cpu.c = b2; } 
         });
-        optable.insert(&Instruction { opcode: 0x02, size: 1, disassembly: "STAX B",    mnemonic: "(BC) <- A",                effected_flags: None, 
-            func_ptr: |cpu, _, _| { cpu.memory[combine_bytes(cpu.b, cpu.c) as usize] = cpu.a } 
+        optable.insert(&Instruction { opcode: 0x02, size: 1, cycles: 7, disassembly: "STAX B",    mnemonic: "(BC) <- A",                effected_flags: None, 
+            func_ptr: |cpu, _, _| { cpu.write_byte(cpu.bc(), cpu.a) } 
         });
 
-        optable.insert(&Instruction { opcode: 0x03, size: 1, disassembly: "INX B",     mnemonic: "BC <- BC + 1",             effected_flags: None, 
+        optable.insert(&Instruction { opcode: 0x03, size: 1, cycles: 5, disassembly: "INX B",     mnemonic: "BC <- BC + 1",             effected_flags: None, 
             func_ptr: |cpu, _, _|  { 
-                let r = combine_bytes(cpu.b, cpu.c) + 1; 
-                set_byte_pair(&mut cpu.b, &mut cpu.c, r) 
+                let r = cpu.bc().wrapping_add(1); 
+                cpu.set_bc(r) 
             } 
         });
-        optable.insert(&Instruction { opcode: 0x04, size: 1, disassembly: "INR B",     mnemonic: "B <- B + 1", effected_flags: "Z,S,P,AC".into(), 
+        optable.insert(&Instruction { opcode: 0x04, size: 1, cycles: 5, disassembly: "INR B",     mnemonic: "B <- B + 1", effected_flags: "Z,S,P,AC".into(), 
             func_ptr: |cpu, _, _|   { 
                 cpu.b = cpu.inr(cpu.b);
             } 
         });
 
-        optable.insert(&Instruction { opcode: 0x05, size: 1, disassembly: "DCR B", mnemonic: "B <- B - 1", effected_flags: "Z,S,P,AC".into(), 
+        optable.insert(&Instruction { opcode: 0x05, size: 1, cycles: 5, disassembly: "DCR B", mnemonic: "B <- B - 1", effected_flags: "Z,S,P,AC".into(), 
             func_ptr: |cpu, _, _| { 
                 cpu.b = cpu.dcr(cpu.b)
             } 
         });
 
-        optable.insert(&Instruction { opcode: 0x06, size: 2, disassembly: "MVI B, D8", mnemonic: "B <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x06, size: 2, cycles: 7, disassembly: "MVI B, D8", mnemonic: "B <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, _| { 
                 cpu.b = b2;
             } 
         });
 
-        optable.insert(&Instruction { opcode: 0x07, size: 1, disassembly: "RLC", mnemonic: "A = A << 1; bit 0 = prev bit 7; CY = prev bit 7", effected_flags: "CY".into(),
+        optable.insert(&Instruction { opcode: 0x07, size: 1, cycles: 4, disassembly: "RLC", mnemonic: "A = A << 1; bit 0 = prev bit 7; CY = prev bit 7", effected_flags: "CY".into(),
             func_ptr: |cpu, _, _| { 
                 let rotated = cpu.a >> 7;
                 cpu.a = (cpu.a << 1) | rotated;
@@ -71,50 +184,50 @@ impl OpcodeTable {
             } 
         });
 
-        optable.insert(&Instruction { opcode: 0x09, size: 1, disassembly: "DAD B", mnemonic: "HL = HL + BC", effected_flags: "CY".into(),
-            func_ptr: |cpu, _, _| { 
-                let hl = combine_bytes(cpu.h, cpu.l);
-                let bc = combine_bytes(cpu.b, cpu.c);
-                let result = hl + bc;
-                cpu.check_carry(result);
+        optable.insert(&Instruction { opcode: 0x08, size: 1, cycles: 4, disassembly: "*NOP", mnemonic: "Undocumented alias of NOP", effected_flags: None,
+            func_ptr: |_, _, _| { }
+        });
 
-                set_byte_pair(&mut cpu.h, &mut cpu.l, result);
+        optable.insert(&Instruction { opcode: 0x09, size: 1, cycles: 10, disassembly: "DAD B", mnemonic: "HL = HL + BC", effected_flags: "CY".into(),
+            func_ptr: |cpu, _, _| { 
+                let bc = cpu.bc();
+                cpu.dad(bc);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x0A, size: 1, disassembly: "LDAX B", mnemonic: "A <- (BC)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x0A, size: 1, cycles: 7, disassembly: "LDAX B", mnemonic: "A <- (BC)", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let bc = combine_bytes(cpu.b, cpu.c) as usize;
-                cpu.a = cpu.memory[bc];
+                let bc = cpu.bc();
+                cpu.a = cpu.read_byte(bc);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x0B, size: 1, disassembly: "DCX B", mnemonic: "BC = BC-1", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x0B, size: 1, cycles: 5, disassembly: "DCX B", mnemonic: "BC = BC-1", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let bc = combine_bytes(cpu.b, cpu.c);
-                set_byte_pair(&mut cpu.b, &mut cpu.c, bc - 1);
+                let bc = cpu.bc();
+                cpu.set_bc(bc.wrapping_sub(1));
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x0C, size: 1, disassembly: "INR C", mnemonic: "C <- C + 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x0C, size: 1, cycles: 5, disassembly: "INR C", mnemonic: "C <- C + 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.c = cpu.inr(cpu.c);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x0D, size: 1, disassembly: "DCR C", mnemonic: "C <- C - 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x0D, size: 1, cycles: 5, disassembly: "DCR C", mnemonic: "C <- C - 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.c = cpu.dcr(cpu.c);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x0E, size: 2, disassembly: "MVI C, D8", mnemonic: "C <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x0E, size: 2, cycles: 7, disassembly: "MVI C, D8", mnemonic: "C <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, _| { 
                 cpu.c = b2;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x0F, size: 1, disassembly: "RRC", mnemonic: "A = A >> 1; bit 7 = prev bit 0; CY = prev bit 0", effected_flags: "CY".into(),
+        optable.insert(&Instruction { opcode: 0x0F, size: 1, cycles: 4, disassembly: "RRC", mnemonic: "A = A >> 1; bit 7 = prev bit 0; CY = prev bit 0", effected_flags: "CY".into(),
             func_ptr: |cpu, _, _| { 
                 let rotated = cpu.a << 7;
                 cpu.a = (cpu.a >> 1) | rotated;
@@ -127,46 +240,50 @@ impl OpcodeTable {
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x11, size: 3, disassembly: "LXI D,D16", mnemonic: "D <- byte 3, E <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x10, size: 1, cycles: 4, disassembly: "*NOP", mnemonic: "Undocumented alias of NOP", effected_flags: None,
+            func_ptr: |_, _, _| { }
+        });
+
+        optable.insert(&Instruction { opcode: 0x11, size: 3, cycles: 10, disassembly: "LXI D,D16", mnemonic: "D <- byte 3, E <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
                 cpu.d = b3;
                 cpu.e = b2;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x12, size: 1, disassembly: "STAX D", mnemonic: "(DE) <- A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x12, size: 1, cycles: 7, disassembly: "STAX D", mnemonic: "(DE) <- A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let de = combine_bytes(cpu.d, cpu.e) as usize;
-                cpu.memory[de] = cpu.a;
+                let de = cpu.de();
+                cpu.write_byte(de, cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x13, size: 1, disassembly: "INX D", mnemonic: "DE <- DE + 1", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x13, size: 1, cycles: 5, disassembly: "INX D", mnemonic: "DE <- DE + 1", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let result = combine_bytes(cpu.d, cpu.e) + 1;
-                set_byte_pair(&mut cpu.d, &mut cpu.e, result);
+                let result = cpu.de().wrapping_add(1);
+                cpu.set_de(result);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x14, size: 1, disassembly: "INR D", mnemonic: "D <- D + 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x14, size: 1, cycles: 5, disassembly: "INR D", mnemonic: "D <- D + 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.d = cpu.inr(cpu.d);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x15, size: 1, disassembly: "DCR D", mnemonic: "D <- D - 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x15, size: 1, cycles: 5, disassembly: "DCR D", mnemonic: "D <- D - 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.d = cpu.dcr(cpu.d);
         }
     });
 
-        optable.insert(&Instruction { opcode: 0x16, size: 2, disassembly: "MVI D, D8", mnemonic: "D <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x16, size: 2, cycles: 7, disassembly: "MVI D, D8", mnemonic: "D <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, _| { 
                 cpu.d = b2;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x17, size: 1, disassembly: "RAL", mnemonic: "A = A << 1; bit 0 = prev CY; CY = prev bit 7", effected_flags: "CY".into(),
+        optable.insert(&Instruction { opcode: 0x17, size: 1, cycles: 4, disassembly: "RAL", mnemonic: "A = A << 1; bit 0 = prev CY; CY = prev bit 7", effected_flags: "CY".into(),
             func_ptr: |cpu, _, _| { 
                 let prev_carry = if cpu.condition_codes.is_set(ConditionFlag::Carry) { 1 } else { 0 };
                 let rotated = cpu.a >> 7;
@@ -180,51 +297,50 @@ impl OpcodeTable {
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x19, size: 1, disassembly: "DAD D", mnemonic: "HL = HL + DE", effected_flags: "CY".into(),
-            func_ptr: |cpu, _, _| { 
-                let hl = combine_bytes(cpu.h, cpu.l);
-                let de = combine_bytes(cpu.d, cpu.e);
-                let result = hl + de;
-
-                cpu.check_carry(result);
+        optable.insert(&Instruction { opcode: 0x18, size: 1, cycles: 4, disassembly: "*NOP", mnemonic: "Undocumented alias of NOP", effected_flags: None,
+            func_ptr: |_, _, _| { }
+        });
 
-                set_byte_pair(&mut cpu.h, &mut cpu.l, result);
+        optable.insert(&Instruction { opcode: 0x19, size: 1, cycles: 10, disassembly: "DAD D", mnemonic: "HL = HL + DE", effected_flags: "CY".into(),
+            func_ptr: |cpu, _, _| { 
+                let de = cpu.de();
+                cpu.dad(de);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x1A, size: 1, disassembly: "LDAX D", mnemonic: "A <- (DE)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x1A, size: 1, cycles: 7, disassembly: "LDAX D", mnemonic: "A <- (DE)", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let de = combine_bytes(cpu.d, cpu.e) as usize;
-                cpu.a = cpu.memory[de];
+                let de = cpu.de();
+                cpu.a = cpu.read_byte(de);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x1B, size: 1, disassembly: "DCX D", mnemonic: "DE <- DE - 1", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x1B, size: 1, cycles: 5, disassembly: "DCX D", mnemonic: "DE <- DE - 1", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let result = combine_bytes(cpu.d, cpu.e) - 1;
-                set_byte_pair(&mut cpu.d, &mut cpu.e, result);
+                let result = cpu.de().wrapping_sub(1);
+                cpu.set_de(result);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x1C, size: 1, disassembly: "INR E", mnemonic: "E <- E + 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x1C, size: 1, cycles: 5, disassembly: "INR E", mnemonic: "E <- E + 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.e = cpu.inr(cpu.e);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x1D, size: 1, disassembly: "DCR E", mnemonic: "E <- E - 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x1D, size: 1, cycles: 5, disassembly: "DCR E", mnemonic: "E <- E - 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.e = cpu.dcr(cpu.e);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x1E, size: 2, disassembly: "MVI E, D8", mnemonic: "E <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x1E, size: 2, cycles: 7, disassembly: "MVI E, D8", mnemonic: "E <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, _| { 
                 cpu.e = b2;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x1F, size: 1, disassembly: "RAR", mnemonic: "A = A >> 1; bit 7 = prev CY; CY = prev bit 0", effected_flags: "CY".into(),
+        optable.insert(&Instruction { opcode: 0x1F, size: 1, cycles: 4, disassembly: "RAR", mnemonic: "A = A >> 1; bit 7 = prev CY; CY = prev bit 0", effected_flags: "CY".into(),
             func_ptr: |cpu, _, _| { 
                 let prev_carry = if cpu.condition_codes.is_set(ConditionFlag::Carry) { 1 } else { 0 };
                 let rotated = cpu.a << 7;
@@ -238,212 +354,202 @@ impl OpcodeTable {
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x21, size: 3, disassembly: "LXI H, D16", mnemonic: "H <- byte 3, L <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x20, size: 1, cycles: 4, disassembly: "*NOP", mnemonic: "Undocumented alias of NOP", effected_flags: None,
+            func_ptr: |_, _, _| { }
+        });
+
+        optable.insert(&Instruction { opcode: 0x21, size: 3, cycles: 10, disassembly: "LXI H, D16", mnemonic: "H <- byte 3, L <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
                 cpu.h = b3;
                 cpu.l = b2;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x22, size: 3, disassembly: "SHLD adr", mnemonic: "(adr) <- L; (adr + 1) <- H", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x22, size: 3, cycles: 16, disassembly: "SHLD adr", mnemonic: "(adr) <- L; (adr + 1) <- H", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
-                let addr = combine_bytes(b3, b2) as usize;
-                cpu.memory[addr] = cpu.l;
-                cpu.memory[addr + 1] = cpu.h;
+                let addr = combine_bytes(b3, b2);
+                cpu.write_byte(addr, cpu.l);
+                cpu.write_byte(addr.wrapping_add(1), cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x23, size: 1, disassembly: "INX H", mnemonic: "HL <- HL + 1", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x23, size: 1, cycles: 5, disassembly: "INX H", mnemonic: "HL <- HL + 1", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let result = combine_bytes(cpu.h, cpu.l);
-                set_byte_pair(&mut cpu.h, &mut cpu.l, result + 1);
+                let result = cpu.hl();
+                cpu.set_hl(result.wrapping_add(1));
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x24, size: 1, disassembly: "INR H", mnemonic: "H <- H + 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x24, size: 1, cycles: 5, disassembly: "INR H", mnemonic: "H <- H + 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.h = cpu.inr(cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x25, size: 1, disassembly: "DCR H", mnemonic: "H <- H - 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x25, size: 1, cycles: 5, disassembly: "DCR H", mnemonic: "H <- H - 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.h = cpu.dcr(cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x26, size: 2, disassembly: "MVI H, D8", mnemonic: "H <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x26, size: 2, cycles: 7, disassembly: "MVI H, D8", mnemonic: "H <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, _| { 
                 cpu.h = b2;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x27, size: 1, disassembly: "DAA", mnemonic: "Decimal Adjust Accumulator", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x27, size: 1, cycles: 4, disassembly: "DAA", mnemonic: "Decimal Adjust Accumulator", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                /*
-                * The 8 bit number in the accumulator is adjusted to form 2 four-bit Binary-Coded-Decimal digits by the following process
-                * 
-                *  1. If the value of the least significant 4 bits of the accumulator is greater than 9 OR if the AC flag is set, 6 is added to the accumulator
-                *  2. If the value of the most significant 4 bits of the accumulator is now greater than 9 OR if the CY flag is set, 6 is added to the most 4 significant bits of the accumulator
-                */
-                let lsb = cpu.a & 0xF;
-                let msb = cpu.a & (0xF << 4);
-
-                let mut acc = 0;
-                
-                if lsb > 9 || cpu.condition_codes.is_set(ConditionFlag::AuxiliaryCarry) {
-                    acc += 0x06;
-                }
-                
-                if msb > 9 || cpu.condition_codes.is_set(ConditionFlag::Carry) {
-                    acc += 0x60;
-                }
-                let result = cpu.a as u16 + acc;
-                cpu.check_zero(result);
-                cpu.check_sign(result);
-                cpu.check_parity(result as u32);
-                cpu.check_carry(result);
-                // aux carry
-
-                cpu.a = result as u8;
+                cpu.daa();
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x29, size: 1, disassembly: "DAD H", mnemonic: "HL <- HL + HL", effected_flags: "CY".into(),
+        optable.insert(&Instruction { opcode: 0x28, size: 1, cycles: 4, disassembly: "*NOP", mnemonic: "Undocumented alias of NOP", effected_flags: None,
+            func_ptr: |_, _, _| { }
+        });
+
+        optable.insert(&Instruction { opcode: 0x29, size: 1, cycles: 10, disassembly: "DAD H", mnemonic: "HL <- HL + HL", effected_flags: "CY".into(),
             func_ptr: |cpu, _, _| { 
-                let hl = combine_bytes(cpu.h, cpu.l) as u16;
-                let result = hl + hl;
-                cpu.check_carry(result);
-                set_byte_pair(&mut cpu.h, &mut cpu.l, result);
+                let hl = cpu.hl();
+                cpu.dad(hl);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x2A, size: 3, disassembly: "LHLD adr", mnemonic: "L <- (adr); H <- (adr + 1)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x2A, size: 3, cycles: 16, disassembly: "LHLD adr", mnemonic: "L <- (adr); H <- (adr + 1)", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
-                let addr = combine_bytes(b3, b2) as usize;
-                cpu.l = cpu.memory[addr];
-                cpu.h = cpu.memory[addr + 1];
+                let addr = combine_bytes(b3, b2);
+                cpu.l = cpu.read_byte(addr);
+                cpu.h = cpu.read_byte(addr.wrapping_add(1));
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x2B, size: 1, disassembly: "DCX H", mnemonic: "HL <- HL - 1", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x2B, size: 1, cycles: 5, disassembly: "DCX H", mnemonic: "HL <- HL - 1", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let hl = combine_bytes(cpu.h, cpu.l);
-                set_byte_pair(&mut cpu.h, &mut cpu.l, hl + 1);
+                let hl = cpu.hl();
+                cpu.set_hl(hl.wrapping_sub(1));
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x2C, size: 1, disassembly: "INR L", mnemonic: "L <- L + 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x2C, size: 1, cycles: 5, disassembly: "INR L", mnemonic: "L <- L + 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.l = cpu.inr(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x2D, size: 1, disassembly: "DCR L", mnemonic: "L <- L - 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x2D, size: 1, cycles: 5, disassembly: "DCR L", mnemonic: "L <- L - 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.l = cpu.dcr(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x2E, size: 2, disassembly: "MVI L, D8", mnemonic: "L <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x2E, size: 2, cycles: 7, disassembly: "MVI L, D8", mnemonic: "L <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, _| { 
                 cpu.l = b2;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x2F, size: 1, disassembly: "CMA", mnemonic: "A <- !A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x2F, size: 1, cycles: 4, disassembly: "CMA", mnemonic: "A <- !A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.a = !cpu.a;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x31, size: 3, disassembly: "LXI SP, D16", mnemonic: "SP.high <- byte 3; SP.low <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x30, size: 1, cycles: 4, disassembly: "*NOP", mnemonic: "Undocumented alias of NOP", effected_flags: None,
+            func_ptr: |_, _, _| { }
+        });
+
+        optable.insert(&Instruction { opcode: 0x31, size: 3, cycles: 10, disassembly: "LXI SP, D16", mnemonic: "SP.high <- byte 3; SP.low <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
                 cpu.sp = combine_bytes(b3, b2); 
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x32, size: 3, disassembly: "STA adr", mnemonic: "(adr) <- A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x32, size: 3, cycles: 13, disassembly: "STA adr", mnemonic: "(adr) <- A", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
-                let addr = combine_bytes(b3, b2) as usize;
-                cpu.memory[addr] = cpu.a;
+                let addr = combine_bytes(b3, b2);
+                cpu.write_byte(addr, cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x33, size: 1, disassembly: "INX SP", mnemonic: "SP = SP + 1", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x33, size: 1, cycles: 5, disassembly: "INX SP", mnemonic: "SP = SP + 1", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                cpu.sp += 1;
+                cpu.sp = cpu.sp.wrapping_add(1);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x34, size: 1, disassembly: "INR M", mnemonic: "(HL) <- (HL) + 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x34, size: 1, cycles: 10, disassembly: "INR M", mnemonic: "(HL) <- (HL) + 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let hl = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.memory[hl] = cpu.inr(cpu.memory[hl]);
+                let hl = cpu.hl();
+                let value = cpu.read_byte(hl);
+                let result = cpu.inr(value);
+                cpu.write_byte(hl, result);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x35, size: 1, disassembly: "DCR M", mnemonic: "(HL) <- (HL) - 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x35, size: 1, cycles: 10, disassembly: "DCR M", mnemonic: "(HL) <- (HL) - 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let hl = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.memory[hl] = cpu.dcr(cpu.memory[hl]);
+                let hl = cpu.hl();
+                let value = cpu.read_byte(hl);
+                let result = cpu.dcr(value);
+                cpu.write_byte(hl, result);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x36, size: 2, disassembly: "MVI M, D8", mnemonic: "(HL) <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x36, size: 2, cycles: 10, disassembly: "MVI M, D8", mnemonic: "(HL) <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, _| { 
-                let hl = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.memory[hl] = b2;
+                let hl = cpu.hl();
+                cpu.write_byte(hl, b2);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x37, size: 1, disassembly: "STC", mnemonic: "CY = 1", effected_flags: "CY".into(),
+        optable.insert(&Instruction { opcode: 0x37, size: 1, cycles: 4, disassembly: "STC", mnemonic: "CY = 1", effected_flags: "CY".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.condition_codes.set(ConditionFlag::Carry);                
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x39, size: 1, disassembly: "DAD SP", mnemonic: "HL <- HL + SP", effected_flags: "CY".into(),
+        optable.insert(&Instruction { opcode: 0x38, size: 1, cycles: 4, disassembly: "*NOP", mnemonic: "Undocumented alias of NOP", effected_flags: None,
+            func_ptr: |_, _, _| { }
+        });
+
+        optable.insert(&Instruction { opcode: 0x39, size: 1, cycles: 10, disassembly: "DAD SP", mnemonic: "HL <- HL + SP", effected_flags: "CY".into(),
             func_ptr: |cpu, _, _| { 
-                let hl = combine_bytes(cpu.h, cpu.l);
-                let result = hl + cpu.sp;
-                cpu.check_carry(result);
-                set_byte_pair(&mut cpu.h, &mut cpu.l, result);           
+                cpu.dad(cpu.sp);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x3A, size: 3, disassembly: "LDA adr", mnemonic: "A <- (adr)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x3A, size: 3, cycles: 13, disassembly: "LDA adr", mnemonic: "A <- (adr)", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
-                let addr = combine_bytes(b3, b2) as usize;
-                cpu.a = cpu.memory[addr];
+                let addr = combine_bytes(b3, b2);
+                cpu.a = cpu.read_byte(addr);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x3B, size: 1, disassembly: "DCX SP", mnemonic: "SP <- SP + 1", effected_flags: None,
-            func_ptr: |cpu, b2, b3| { 
-                cpu.sp += 1;
+        optable.insert(&Instruction { opcode: 0x3B, size: 1, cycles: 5, disassembly: "DCX SP", mnemonic: "SP <- SP - 1", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.sp = cpu.sp.wrapping_sub(1);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x3C, size: 1, disassembly: "INR A", mnemonic: "A <- A + 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x3C, size: 1, cycles: 5, disassembly: "INR A", mnemonic: "A <- A + 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.a = cpu.inr(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x3D, size: 1, disassembly: "DCR A", mnemonic: "A <- A - 1", effected_flags: "Z,S,P,AC".into(),
+        optable.insert(&Instruction { opcode: 0x3D, size: 1, cycles: 5, disassembly: "DCR A", mnemonic: "A <- A - 1", effected_flags: "Z,S,P,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.a = cpu.dcr(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x3E, size: 2, disassembly: "MVI A, D8", mnemonic: "A <- byte 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x3E, size: 2, cycles: 7, disassembly: "MVI A, D8", mnemonic: "A <- byte 2", effected_flags: None,
             func_ptr: |cpu, b2, _| { 
                 cpu.a = b2;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x3F, size: 1, disassembly: "CMC", mnemonic: "CY = !CY", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x3F, size: 1, cycles: 4, disassembly: "CMC", mnemonic: "CY = !CY", effected_flags: "CY".into(),
             func_ptr: |cpu, _, _| { 
                 if cpu.condition_codes.is_set(ConditionFlag::Carry) {
                     cpu.condition_codes.unset(ConditionFlag::Carry);
@@ -453,797 +559,800 @@ impl OpcodeTable {
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x40, size: 1, disassembly: "MOV B, B", mnemonic: "B <- B", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-            cpu.b = cpu.b;
-            }
-        });
-
-        optable.insert(&Instruction { opcode: 0x40, size: 1, disassembly: "MOV B, B", mnemonic: "B <- B", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-            cpu.b = cpu.b;
-            }
+        optable.insert(&Instruction { opcode: 0x40, size: 1, cycles: 5, disassembly: "MOV B, B", mnemonic: "B <- B", effected_flags: None,
+            func_ptr: |_, _, _| { }
         });
 
-        optable.insert(&Instruction { opcode: 0x41, size: 1, disassembly: "MOV B, C", mnemonic: "B <- C", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x41, size: 1, cycles: 5, disassembly: "MOV B, C", mnemonic: "B <- C", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.b = cpu.c;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x42, size: 1, disassembly: "MOV B, D", mnemonic: "B <- D", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x42, size: 1, cycles: 5, disassembly: "MOV B, D", mnemonic: "B <- D", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.b = cpu.d;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x43, size: 1, disassembly: "MOV B, E", mnemonic: "B <- E", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x43, size: 1, cycles: 5, disassembly: "MOV B, E", mnemonic: "B <- E", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.b = cpu.e;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x44, size: 1, disassembly: "MOV B, H", mnemonic: "B <- H", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x44, size: 1, cycles: 5, disassembly: "MOV B, H", mnemonic: "B <- H", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.b = cpu.h;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x45, size: 1, disassembly: "MOV B, L", mnemonic: "B <- L", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x45, size: 1, cycles: 5, disassembly: "MOV B, L", mnemonic: "B <- L", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.b = cpu.l;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x46, size: 1, disassembly: "MOV B, M", mnemonic: "B <- (HL)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x46, size: 1, cycles: 7, disassembly: "MOV B, M", mnemonic: "B <- (HL)", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;   
-                cpu.b = cpu.memory[addr];
+                let addr = cpu.hl();   
+                cpu.b = cpu.read_byte(addr);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x47, size: 1, disassembly: "MOV B, A", mnemonic: "B <- A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x47, size: 1, cycles: 5, disassembly: "MOV B, A", mnemonic: "B <- A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.b = cpu.a;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x48, size: 1, disassembly: "MOV C, B", mnemonic: "C <- B", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x48, size: 1, cycles: 5, disassembly: "MOV C, B", mnemonic: "C <- B", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.c = cpu.b;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x49, size: 1, disassembly: "MOV C, C", mnemonic: "C <- C", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-            cpu.c = cpu.c;
-            }
+        optable.insert(&Instruction { opcode: 0x49, size: 1, cycles: 5, disassembly: "MOV C, C", mnemonic: "C <- C", effected_flags: None,
+            func_ptr: |_, _, _| { }
         });
 
-        optable.insert(&Instruction { opcode: 0x4A, size: 1, disassembly: "MOV C, D", mnemonic: "C <- D", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x4A, size: 1, cycles: 5, disassembly: "MOV C, D", mnemonic: "C <- D", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.c = cpu.d;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x4B, size: 1, disassembly: "MOV C, E", mnemonic: "C <- E", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x4B, size: 1, cycles: 5, disassembly: "MOV C, E", mnemonic: "C <- E", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.c = cpu.e;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x4C, size: 1, disassembly: "MOV C, H", mnemonic: "C <- H", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x4C, size: 1, cycles: 5, disassembly: "MOV C, H", mnemonic: "C <- H", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.c = cpu.h;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x4D, size: 1, disassembly: "MOV C, L", mnemonic: "C <- L", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x4D, size: 1, cycles: 5, disassembly: "MOV C, L", mnemonic: "C <- L", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.c = cpu.l;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x4E, size: 1, disassembly: "MOV C, M", mnemonic: "C <- (HL)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x4E, size: 1, cycles: 7, disassembly: "MOV C, M", mnemonic: "C <- (HL)", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let addr = cpu.hl();
+                cpu.c = cpu.read_byte(addr);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0x4F, size: 1, cycles: 5, disassembly: "MOV C, A", mnemonic: "C <- A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.c = cpu.memory[addr];
+                cpu.c = cpu.a;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x50, size: 1, disassembly: "MOV D, B", mnemonic: "D <- B", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x50, size: 1, cycles: 5, disassembly: "MOV D, B", mnemonic: "D <- B", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.d = cpu.b;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x51, size: 1, disassembly: "MOV D, C", mnemonic: "D <- C", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x51, size: 1, cycles: 5, disassembly: "MOV D, C", mnemonic: "D <- C", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.d = cpu.c;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x52, size: 1, disassembly: "MOV D, D", mnemonic: "D <- D", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-            cpu.d = cpu.d;
-            }
+        optable.insert(&Instruction { opcode: 0x52, size: 1, cycles: 5, disassembly: "MOV D, D", mnemonic: "D <- D", effected_flags: None,
+            func_ptr: |_, _, _| { }
         });
         
-        optable.insert(&Instruction { opcode: 0x53, size: 1, disassembly: "MOV D, E", mnemonic: "D <- E", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x53, size: 1, cycles: 5, disassembly: "MOV D, E", mnemonic: "D <- E", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.d = cpu.e;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x54, size: 1, disassembly: "MOV D, H", mnemonic: "D <- H", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x54, size: 1, cycles: 5, disassembly: "MOV D, H", mnemonic: "D <- H", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.d = cpu.h;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x55, size: 1, disassembly: "MOV D, L", mnemonic: "D <- L", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x55, size: 1, cycles: 5, disassembly: "MOV D, L", mnemonic: "D <- L", effected_flags: None,
             func_ptr: |cpu, _, _| { 
             cpu.d = cpu.l;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x56, size: 1, disassembly: "MOV D, M", mnemonic: "D <- (HL)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x56, size: 1, cycles: 7, disassembly: "MOV D, M", mnemonic: "D <- (HL)", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.d = cpu.memory[addr];
+                let addr = cpu.hl();
+                cpu.d = cpu.read_byte(addr);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x57, size: 1, disassembly: "MOV D, A", mnemonic: "D <- A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x57, size: 1, cycles: 5, disassembly: "MOV D, A", mnemonic: "D <- A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.d = cpu.a
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x58, size: 1, disassembly: "MOV E, B", mnemonic: "E <- B", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x58, size: 1, cycles: 5, disassembly: "MOV E, B", mnemonic: "E <- B", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.e = cpu.b
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x59, size: 1, disassembly: "MOV E, C", mnemonic: "E <- C", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x59, size: 1, cycles: 5, disassembly: "MOV E, C", mnemonic: "E <- C", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.e = cpu.c;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x5A, size: 1, disassembly: "MOV E, D", mnemonic: "E <- D", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x5A, size: 1, cycles: 5, disassembly: "MOV E, D", mnemonic: "E <- D", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.e = cpu.d;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x5B, size: 1, disassembly: "MOV E, E", mnemonic: "E <- E", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-                cpu.e = cpu.e;
-            }
+        optable.insert(&Instruction { opcode: 0x5B, size: 1, cycles: 5, disassembly: "MOV E, E", mnemonic: "E <- E", effected_flags: None,
+            func_ptr: |_, _, _| { }
         });
         
-        optable.insert(&Instruction { opcode: 0x5C, size: 1, disassembly: "MOV E, H", mnemonic: "E <- H", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x5C, size: 1, cycles: 5, disassembly: "MOV E, H", mnemonic: "E <- H", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.e = cpu.h;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x5D, size: 1, disassembly: "MOV E, L", mnemonic: "E <- L", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x5D, size: 1, cycles: 5, disassembly: "MOV E, L", mnemonic: "E <- L", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.e = cpu.l;
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x5E, size: 1, disassembly: "MOV E, M", mnemonic: "E <- (HL)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x5E, size: 1, cycles: 7, disassembly: "MOV E, M", mnemonic: "E <- (HL)", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;            
-                cpu.e = cpu.memory[addr];
+                let addr = cpu.hl();            
+                cpu.e = cpu.read_byte(addr);
             }
         });
             
-        optable.insert(&Instruction { opcode: 0x5F, size: 1, disassembly: "MOV E, A", mnemonic: "E <- A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x5F, size: 1, cycles: 5, disassembly: "MOV E, A", mnemonic: "E <- A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.e = cpu.a;
             }
         });
             
-        optable.insert(&Instruction { opcode: 0x60, size: 1, disassembly: "MOV H, B", mnemonic: "H <- B", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x60, size: 1, cycles: 5, disassembly: "MOV H, B", mnemonic: "H <- B", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.h = cpu.b;
             }
         });
             
-        optable.insert(&Instruction { opcode: 0x61, size: 1, disassembly: "MOV H, C", mnemonic: "H <- C", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x61, size: 1, cycles: 5, disassembly: "MOV H, C", mnemonic: "H <- C", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.h = cpu.c;
             }
         });
             
-        optable.insert(&Instruction { opcode: 0x62, size: 1, disassembly: "MOV H, D", mnemonic: "H <- D", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x62, size: 1, cycles: 5, disassembly: "MOV H, D", mnemonic: "H <- D", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.h = cpu.d;
             }
         });
             
-        optable.insert(&Instruction { opcode: 0x63, size: 1, disassembly: "MOV H, E", mnemonic: "H <- E", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x63, size: 1, cycles: 5, disassembly: "MOV H, E", mnemonic: "H <- E", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.h = cpu.e;
             }
         });
             
-        optable.insert(&Instruction { opcode: 0x64, size: 1, disassembly: "MOV H, H", mnemonic: "H <- H", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-                cpu.h = cpu.h;
-            }
+        optable.insert(&Instruction { opcode: 0x64, size: 1, cycles: 5, disassembly: "MOV H, H", mnemonic: "H <- H", effected_flags: None,
+            func_ptr: |_, _, _| { }
         });
             
-        optable.insert(&Instruction { opcode: 0x65, size: 1, disassembly: "MOV H, L", mnemonic: "H <- L", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x65, size: 1, cycles: 5, disassembly: "MOV H, L", mnemonic: "H <- L", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.h = cpu.l;
             }
         });
             
-        optable.insert(&Instruction { opcode: 0x66, size: 1, disassembly: "MOV H, M", mnemonic: "H <- (HL)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x66, size: 1, cycles: 7, disassembly: "MOV H, M", mnemonic: "H <- (HL)", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.h = cpu.memory[addr];
+                let addr = cpu.hl();
+                cpu.h = cpu.read_byte(addr);
             }
         });
                 
-        optable.insert(&Instruction { opcode: 0x67, size: 1, disassembly: "MOV H, A", mnemonic: "H <- A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x67, size: 1, cycles: 5, disassembly: "MOV H, A", mnemonic: "H <- A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.h = cpu.a;
             }
         });
                 
-        optable.insert(&Instruction { opcode: 0x68, size: 1, disassembly: "MOV L, B", mnemonic: "L <- B", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x68, size: 1, cycles: 5, disassembly: "MOV L, B", mnemonic: "L <- B", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.l = cpu.b;
             }
         });
                 
-        optable.insert(&Instruction { opcode: 0x69, size: 1, disassembly: "MOV L, C", mnemonic: "L <- C", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x69, size: 1, cycles: 5, disassembly: "MOV L, C", mnemonic: "L <- C", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.l = cpu.c;
             }
         });
                 
-        optable.insert(&Instruction { opcode: 0x6A, size: 1, disassembly: "MOV L, D", mnemonic: "L <- D", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x6A, size: 1, cycles: 5, disassembly: "MOV L, D", mnemonic: "L <- D", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.l = cpu.d;
             }
         });
                 
-        optable.insert(&Instruction { opcode: 0x6A, size: 1, disassembly: "MOV L, D", mnemonic: "L <- D", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-                cpu.l = cpu.d;
-            }
-        });
-                
-        optable.insert(&Instruction { opcode: 0x6B, size: 1, disassembly: "MOV L, E", mnemonic: "L <- E", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x6B, size: 1, cycles: 5, disassembly: "MOV L, E", mnemonic: "L <- E", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.l = cpu.e;
             }
         });
                     
-        optable.insert(&Instruction { opcode: 0x6C, size: 1, disassembly: "MOV L, H", mnemonic: "L <- H", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x6C, size: 1, cycles: 5, disassembly: "MOV L, H", mnemonic: "L <- H", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.l = cpu.h;
             }
         });
                     
-        optable.insert(&Instruction { opcode: 0x6D, size: 1, disassembly: "MOV L, L", mnemonic: "L <- L", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-                cpu.l = cpu.l;
-            }
+        optable.insert(&Instruction { opcode: 0x6D, size: 1, cycles: 5, disassembly: "MOV L, L", mnemonic: "L <- L", effected_flags: None,
+            func_ptr: |_, _, _| { }
         });
                     
-        optable.insert(&Instruction { opcode: 0x6E, size: 1, disassembly: "MOV L, M", mnemonic: "L <- (HL)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x6E, size: 1, cycles: 7, disassembly: "MOV L, M", mnemonic: "L <- (HL)", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.l = cpu.memory[addr];
+                let addr = cpu.hl();
+                cpu.l = cpu.read_byte(addr);
             }
         });
                     
-        optable.insert(&Instruction { opcode: 0x6F, size: 1, disassembly: "MOV L, A", mnemonic: "L <- A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x6F, size: 1, cycles: 5, disassembly: "MOV L, A", mnemonic: "L <- A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.l = cpu.a;
             }
         });
                     
-        optable.insert(&Instruction { opcode: 0x70, size: 1, disassembly: "MOV M, B", mnemonic: "(HL) <- B", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x70, size: 1, cycles: 7, disassembly: "MOV M, B", mnemonic: "(HL) <- B", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.memory[addr] = cpu.b;
+                let addr = cpu.hl();
+                cpu.write_byte(addr, cpu.b);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0x71, size: 1, cycles: 7, disassembly: "MOV M, C", mnemonic: "(HL) <- C", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let addr = cpu.hl();
+                cpu.write_byte(addr, cpu.c);
             }
         });
                     
-        optable.insert(&Instruction { opcode: 0x72, size: 1, disassembly: "MOV M, D", mnemonic: "(HL) <- D", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x72, size: 1, cycles: 7, disassembly: "MOV M, D", mnemonic: "(HL) <- D", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.memory[addr] = cpu.d;
+                let addr = cpu.hl();
+                cpu.write_byte(addr, cpu.d);
             }
         });
                     
-        optable.insert(&Instruction { opcode: 0x73, size: 1, disassembly: "MOV M, E", mnemonic: "(HL) <- E", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x73, size: 1, cycles: 7, disassembly: "MOV M, E", mnemonic: "(HL) <- E", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.memory[addr] = cpu.e;
+                let addr = cpu.hl();
+                cpu.write_byte(addr, cpu.e);
             }
         });
                     
-        optable.insert(&Instruction { opcode: 0x74, size: 1, disassembly: "MOV M, H", mnemonic: "(HL) <- H", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x74, size: 1, cycles: 7, disassembly: "MOV M, H", mnemonic: "(HL) <- H", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.memory[addr] = cpu.h;
+                let addr = cpu.hl();
+                cpu.write_byte(addr, cpu.h);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0x75, size: 1, cycles: 7, disassembly: "MOV M, L", mnemonic: "(HL) <- L", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let addr = cpu.hl();
+                cpu.write_byte(addr, cpu.l);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x76, size: 1, disassembly: "HLT", mnemonic: "Halt - Processor is stopped", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x76, size: 1, cycles: 7, disassembly: "HLT", mnemonic: "Halt - Processor is stopped", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.halted = true;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x77, size: 1, disassembly: "MOV M, A", mnemonic: "(HL) <- A", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x77, size: 1, cycles: 7, disassembly: "MOV M, A", mnemonic: "(HL) <- A", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.memory[addr] = cpu.a;
+                let addr = cpu.hl();
+                cpu.write_byte(addr, cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x78, size: 1, disassembly: "MOV A, B", mnemonic: "A <- B", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x78, size: 1, cycles: 5, disassembly: "MOV A, B", mnemonic: "A <- B", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.a = cpu.b;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x79, size: 1, disassembly: "MOV A, C", mnemonic: "A <- C", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x79, size: 1, cycles: 5, disassembly: "MOV A, C", mnemonic: "A <- C", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.a = cpu.c;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x7A, size: 1, disassembly: "MOV A, D", mnemonic: "A <- D", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x7A, size: 1, cycles: 5, disassembly: "MOV A, D", mnemonic: "A <- D", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.a = cpu.d;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x7B, size: 1, disassembly: "MOV A, E", mnemonic: "A <- E", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x7B, size: 1, cycles: 5, disassembly: "MOV A, E", mnemonic: "A <- E", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.a = cpu.e;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x7C, size: 1, disassembly: "MOV A, H", mnemonic: "A <- H", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x7C, size: 1, cycles: 5, disassembly: "MOV A, H", mnemonic: "A <- H", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.a = cpu.h;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x7D, size: 1, disassembly: "MOV A, L", mnemonic: "A <- L", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x7D, size: 1, cycles: 5, disassembly: "MOV A, L", mnemonic: "A <- L", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.a = cpu.l;
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x7E, size: 1, disassembly: "MOV A, M", mnemonic: "A <- (HL)", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0x7E, size: 1, cycles: 7, disassembly: "MOV A, M", mnemonic: "A <- (HL)", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.a = cpu.memory[addr];
+                let addr = cpu.hl();
+                cpu.a = cpu.read_byte(addr);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x7F, size: 1, disassembly: "MOV A, A", mnemonic: "A <- A", effected_flags: None,
-            func_ptr: |cpu, _, _| { 
-                cpu.a = cpu.a;
-            }
+        optable.insert(&Instruction { opcode: 0x7F, size: 1, cycles: 5, disassembly: "MOV A, A", mnemonic: "A <- A", effected_flags: None,
+            func_ptr: |_, _, _| { }
         });
         
-        optable.insert(&Instruction { opcode: 0x80, size: 1, disassembly: "ADD B", mnemonic: "A <- A + B", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x80, size: 1, cycles: 4, disassembly: "ADD B", mnemonic: "A <- A + B", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.add(cpu.b);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x81, size: 1, disassembly: "ADD C", mnemonic: "A <- A + C", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x81, size: 1, cycles: 4, disassembly: "ADD C", mnemonic: "A <- A + C", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.add(cpu.c);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x82, size: 1, disassembly: "ADD D", mnemonic: "A <- A + D", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x82, size: 1, cycles: 4, disassembly: "ADD D", mnemonic: "A <- A + D", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.add(cpu.d);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x83, size: 1, disassembly: "ADD E", mnemonic: "A <- A + E", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x83, size: 1, cycles: 4, disassembly: "ADD E", mnemonic: "A <- A + E", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.add(cpu.e);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x84, size: 1, disassembly: "ADD H", mnemonic: "A <- A + H", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x84, size: 1, cycles: 4, disassembly: "ADD H", mnemonic: "A <- A + H", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.add(cpu.h);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x85, size: 1, disassembly: "ADD L", mnemonic: "A <- A + L", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x85, size: 1, cycles: 4, disassembly: "ADD L", mnemonic: "A <- A + L", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.add(cpu.l);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x86, size: 1, disassembly: "ADD M", mnemonic: "A <- A + (HL)", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x86, size: 1, cycles: 7, disassembly: "ADD M", mnemonic: "A <- A + (HL)", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.add(cpu.memory[addr]);
+                let addr = cpu.hl();
+                let value = cpu.read_byte(addr);
+                cpu.add(value);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x87, size: 1, disassembly: "ADD A", mnemonic: "A <- A + A", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x87, size: 1, cycles: 4, disassembly: "ADD A", mnemonic: "A <- A + A", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.add(cpu.a);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x88, size: 1, disassembly: "ADC B", mnemonic: "A <- A + B + CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x88, size: 1, cycles: 4, disassembly: "ADC B", mnemonic: "A <- A + B + CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.adc(cpu.b);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x89, size: 1, disassembly: "ADC C", mnemonic: "A <- A + C + CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x89, size: 1, cycles: 4, disassembly: "ADC C", mnemonic: "A <- A + C + CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.adc(cpu.c);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x8A, size: 1, disassembly: "ADC D", mnemonic: "A <- A + D + CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x8A, size: 1, cycles: 4, disassembly: "ADC D", mnemonic: "A <- A + D + CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.adc(cpu.d);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x8B, size: 1, disassembly: "ADC E", mnemonic: "A <- A + E + CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x8B, size: 1, cycles: 4, disassembly: "ADC E", mnemonic: "A <- A + E + CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.adc(cpu.e);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x8C, size: 1, disassembly: "ADC H", mnemonic: "A <- A + H + CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x8C, size: 1, cycles: 4, disassembly: "ADC H", mnemonic: "A <- A + H + CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.adc(cpu.h);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0x8D, size: 1, disassembly: "ADC L", mnemonic: "A <- A + L + CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x8D, size: 1, cycles: 4, disassembly: "ADC L", mnemonic: "A <- A + L + CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.adc(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x8E, size: 1, disassembly: "ADC M", mnemonic: "A <- A + (HL) + CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x8E, size: 1, cycles: 7, disassembly: "ADC M", mnemonic: "A <- A + (HL) + CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.adc(cpu.memory[addr]);
+                let addr = cpu.hl();
+                let value = cpu.read_byte(addr);
+                cpu.adc(value);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x8F, size: 1, disassembly: "ADC A", mnemonic: "A <- A + A + CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x8F, size: 1, cycles: 4, disassembly: "ADC A", mnemonic: "A <- A + A + CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.adc(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x90, size: 1, disassembly: "SUB B", mnemonic: "A <- A - B", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x90, size: 1, cycles: 4, disassembly: "SUB B", mnemonic: "A <- A - B", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sub(cpu.b);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x91, size: 1, disassembly: "SUB C", mnemonic: "A <- A - C", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x91, size: 1, cycles: 4, disassembly: "SUB C", mnemonic: "A <- A - C", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sub(cpu.c);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x92, size: 1, disassembly: "SUB D", mnemonic: "A <- A - D", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x92, size: 1, cycles: 4, disassembly: "SUB D", mnemonic: "A <- A - D", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sub(cpu.d);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x93, size: 1, disassembly: "SUB E", mnemonic: "A <- A - E", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x93, size: 1, cycles: 4, disassembly: "SUB E", mnemonic: "A <- A - E", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sub(cpu.e);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x94, size: 1, disassembly: "SUB H", mnemonic: "A <- A - H", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x94, size: 1, cycles: 4, disassembly: "SUB H", mnemonic: "A <- A - H", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sub(cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x95, size: 1, disassembly: "SUB L", mnemonic: "A <- A - L", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x95, size: 1, cycles: 4, disassembly: "SUB L", mnemonic: "A <- A - L", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sub(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x96, size: 1, disassembly: "SUB M", mnemonic: "A <- A - (HL)", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x96, size: 1, cycles: 7, disassembly: "SUB M", mnemonic: "A <- A - (HL)", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.sub(cpu.memory[addr]);
+                let addr = cpu.hl();
+                let value = cpu.read_byte(addr);
+                cpu.sub(value);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x97, size: 1, disassembly: "SUB A", mnemonic: "A <- A - A", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x97, size: 1, cycles: 4, disassembly: "SUB A", mnemonic: "A <- A - A", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sub(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x98, size: 1, disassembly: "SBB B", mnemonic: "A <- A - B - CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x98, size: 1, cycles: 4, disassembly: "SBB B", mnemonic: "A <- A - B - CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sbb(cpu.b);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x99, size: 1, disassembly: "SBB C", mnemonic: "A <- A - C - CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x99, size: 1, cycles: 4, disassembly: "SBB C", mnemonic: "A <- A - C - CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sbb(cpu.c);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x9A, size: 1, disassembly: "SBB D", mnemonic: "A <- A - D - CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x9A, size: 1, cycles: 4, disassembly: "SBB D", mnemonic: "A <- A - D - CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sbb(cpu.d);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x9B, size: 1, disassembly: "SBB E", mnemonic: "A <- A - E - CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x9B, size: 1, cycles: 4, disassembly: "SBB E", mnemonic: "A <- A - E - CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sbb(cpu.e);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x9C, size: 1, disassembly: "SBB H", mnemonic: "A <- A - H - CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x9C, size: 1, cycles: 4, disassembly: "SBB H", mnemonic: "A <- A - H - CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                cpu.sbb(cpu.e);
+                cpu.sbb(cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x9D, size: 1, disassembly: "SBB L", mnemonic: "A <- A - L - CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x9D, size: 1, cycles: 4, disassembly: "SBB L", mnemonic: "A <- A - L - CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sbb(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x9E, size: 1, disassembly: "SBB M", mnemonic: "A <- A - (HL) - CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x9E, size: 1, cycles: 7, disassembly: "SBB M", mnemonic: "A <- A - (HL) - CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.sbb(cpu.memory[addr]);
+                let addr = cpu.hl();
+                let value = cpu.read_byte(addr);
+                cpu.sbb(value);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0x9F, size: 1, disassembly: "SBB A", mnemonic: "A <- A - A - CY", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0x9F, size: 1, cycles: 4, disassembly: "SBB A", mnemonic: "A <- A - A - CY", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.sbb(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA0, size: 1, disassembly: "ANA B", mnemonic: "A <- A & B", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA0, size: 1, cycles: 4, disassembly: "ANA B", mnemonic: "A <- A & B", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ana(cpu.b);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA1, size: 1, disassembly: "ANA C", mnemonic: "A <- A & C", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA1, size: 1, cycles: 4, disassembly: "ANA C", mnemonic: "A <- A & C", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ana(cpu.c);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA2, size: 1, disassembly: "ANA D", mnemonic: "A <- A & D", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA2, size: 1, cycles: 4, disassembly: "ANA D", mnemonic: "A <- A & D", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ana(cpu.d);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA3, size: 1, disassembly: "ANA E", mnemonic: "A <- A & E", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA3, size: 1, cycles: 4, disassembly: "ANA E", mnemonic: "A <- A & E", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ana(cpu.e);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA4, size: 1, disassembly: "ANA H", mnemonic: "A <- A & H", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA4, size: 1, cycles: 4, disassembly: "ANA H", mnemonic: "A <- A & H", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ana(cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA5, size: 1, disassembly: "ANA L", mnemonic: "A <- A & L", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA5, size: 1, cycles: 4, disassembly: "ANA L", mnemonic: "A <- A & L", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ana(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA6, size: 1, disassembly: "ANA M", mnemonic: "A <- A & (HL)", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA6, size: 1, cycles: 7, disassembly: "ANA M", mnemonic: "A <- A & (HL)", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.ana(cpu.memory[addr]);
+                let addr = cpu.hl();
+                let value = cpu.read_byte(addr);
+                cpu.ana(value);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA7, size: 1, disassembly: "ANA A", mnemonic: "A <- A & A", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA7, size: 1, cycles: 4, disassembly: "ANA A", mnemonic: "A <- A & A", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ana(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA8, size: 1, disassembly: "XRA B", mnemonic: "A <- A ^ B", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA8, size: 1, cycles: 4, disassembly: "XRA B", mnemonic: "A <- A ^ B", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.xra(cpu.b);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xA9, size: 1, disassembly: "XRA C", mnemonic: "A <- A ^ C", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xA9, size: 1, cycles: 4, disassembly: "XRA C", mnemonic: "A <- A ^ C", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.xra(cpu.c);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xAA, size: 1, disassembly: "XRA D", mnemonic: "A <- A ^ D", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xAA, size: 1, cycles: 4, disassembly: "XRA D", mnemonic: "A <- A ^ D", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.xra(cpu.d);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xAB, size: 1, disassembly: "XRA E", mnemonic: "A <- A ^ E", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xAB, size: 1, cycles: 4, disassembly: "XRA E", mnemonic: "A <- A ^ E", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.xra(cpu.e);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xAC, size: 1, disassembly: "XRA H", mnemonic: "A <- A ^ H", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xAC, size: 1, cycles: 4, disassembly: "XRA H", mnemonic: "A <- A ^ H", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.xra(cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xAD, size: 1, disassembly: "XRA L", mnemonic: "A <- A ^ L", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xAD, size: 1, cycles: 4, disassembly: "XRA L", mnemonic: "A <- A ^ L", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.xra(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xAE, size: 1, disassembly: "XRA M", mnemonic: "A <- A ^ (HL)", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xAE, size: 1, cycles: 7, disassembly: "XRA M", mnemonic: "A <- A ^ (HL)", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.xra(cpu.memory[addr]);
+                let addr = cpu.hl();
+                let value = cpu.read_byte(addr);
+                cpu.xra(value);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xAF, size: 1, disassembly: "XRA A", mnemonic: "A <- A ^ A", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xAF, size: 1, cycles: 4, disassembly: "XRA A", mnemonic: "A <- A ^ A", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.xra(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB0, size: 1, disassembly: "ORA B", mnemonic: "A <- A | B", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB0, size: 1, cycles: 4, disassembly: "ORA B", mnemonic: "A <- A | B", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ora(cpu.b);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB1, size: 1, disassembly: "ORA C", mnemonic: "A <- A | C", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB1, size: 1, cycles: 4, disassembly: "ORA C", mnemonic: "A <- A | C", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ora(cpu.c);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB2, size: 1, disassembly: "ORA D", mnemonic: "A <- A | D", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB2, size: 1, cycles: 4, disassembly: "ORA D", mnemonic: "A <- A | D", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ora(cpu.d);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB3, size: 1, disassembly: "ORA E", mnemonic: "A <- A | E", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB3, size: 1, cycles: 4, disassembly: "ORA E", mnemonic: "A <- A | E", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ora(cpu.e);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB4, size: 1, disassembly: "ORA H", mnemonic: "A <- A | H", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB4, size: 1, cycles: 4, disassembly: "ORA H", mnemonic: "A <- A | H", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ora(cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB5, size: 1, disassembly: "ORA L", mnemonic: "A <- A | L", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB5, size: 1, cycles: 4, disassembly: "ORA L", mnemonic: "A <- A | L", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ora(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB6, size: 1, disassembly: "ORA M", mnemonic: "A <- A | (HL)", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB6, size: 1, cycles: 7, disassembly: "ORA M", mnemonic: "A <- A | (HL)", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.ora(cpu.memory[addr]);
+                let addr = cpu.hl();
+                let value = cpu.read_byte(addr);
+                cpu.ora(value);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB7, size: 1, disassembly: "ORA A", mnemonic: "A <- A | A", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB7, size: 1, cycles: 4, disassembly: "ORA A", mnemonic: "A <- A | A", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.ora(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB8, size: 1, disassembly: "CMP B", mnemonic: "A - B", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB8, size: 1, cycles: 4, disassembly: "CMP B", mnemonic: "A - B", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.cmp(cpu.b);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xB9, size: 1, disassembly: "CMP C", mnemonic: "A - C", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xB9, size: 1, cycles: 4, disassembly: "CMP C", mnemonic: "A - C", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.cmp(cpu.c);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xBA, size: 1, disassembly: "CMP D", mnemonic: "A - D", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xBA, size: 1, cycles: 4, disassembly: "CMP D", mnemonic: "A - D", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.cmp(cpu.d);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xBB, size: 1, disassembly: "CMP E", mnemonic: "A - E", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xBB, size: 1, cycles: 4, disassembly: "CMP E", mnemonic: "A - E", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.cmp(cpu.e);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xBC, size: 1, disassembly: "CMP H", mnemonic: "A - H", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xBC, size: 1, cycles: 4, disassembly: "CMP H", mnemonic: "A - H", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.cmp(cpu.h);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xBD, size: 1, disassembly: "CMP L", mnemonic: "A - L", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xBD, size: 1, cycles: 4, disassembly: "CMP L", mnemonic: "A - L", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.cmp(cpu.l);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xBE, size: 1, disassembly: "CMP M", mnemonic: "A - (HL)", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xBE, size: 1, cycles: 7, disassembly: "CMP M", mnemonic: "A - (HL)", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
-                let addr = combine_bytes(cpu.h, cpu.l) as usize;
-                cpu.cmp(cpu.memory[addr]);
+                let addr = cpu.hl();
+                let value = cpu.read_byte(addr);
+                cpu.cmp(value);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xBF, size: 1, disassembly: "CMP A", mnemonic: "A - A", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xBF, size: 1, cycles: 4, disassembly: "CMP A", mnemonic: "A - A", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, _, _| { 
                 cpu.cmp(cpu.a);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xC0, size: 1, disassembly: "RNZ", mnemonic: "if NZ, RET", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0xC0, size: 1, cycles: 5, disassembly: "RNZ", mnemonic: "if NZ, RET", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 if !cpu.condition_codes.is_set(ConditionFlag::Zero) {
                     cpu.ret();
+                    cpu.cycles += 6;
                 }
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xC1, size: 1, disassembly: "POP B", mnemonic: "C <- (sp); B <- (sp + 1); sp <- sp + 2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0xC1, size: 1, cycles: 10, disassembly: "POP B", mnemonic: "C <- (sp); B <- (sp + 1); sp <- sp + 2", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 let (high, low) = cpu.pop_stack_parts();
                 cpu.b = high;
@@ -1251,7 +1360,7 @@ impl OpcodeTable {
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xC2, size: 3, disassembly: "JNZ adr", mnemonic: "if NZ, PC <- adr", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0xC2, size: 3, cycles: 10, disassembly: "JNZ adr", mnemonic: "if NZ, PC <- adr", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
                 if !cpu.condition_codes.is_set(ConditionFlag::Zero) {
                     let addr = combine_bytes(b3, b2);
@@ -1260,49 +1369,469 @@ impl OpcodeTable {
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xC3, size: 3, disassembly: "JMP adr", mnemonic: "PC <- adr", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0xC3, size: 3, cycles: 10, disassembly: "JMP adr", mnemonic: "PC <- adr", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
                 let addr = combine_bytes(b3, b2);
                 cpu.jmp(addr);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xC4, size: 3, disassembly: "CNZ adr", mnemonic: "if NZ, CALL adr", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0xC4, size: 3, cycles: 11, disassembly: "CNZ adr", mnemonic: "if NZ, CALL adr", effected_flags: None,
             func_ptr: |cpu, b2, b3| { 
                 if !cpu.condition_codes.is_set(ConditionFlag::Zero) {
                     let addr = combine_bytes(b3, b2);
                     cpu.call(addr);
+                    cpu.cycles += 6;
                 }
             }
         });
         
-        optable.insert(&Instruction { opcode: 0xC5, size: 1, disassembly: "PUSH B", mnemonic: "(sp-2) <- C; (sp-1) <- B; sp <- sp-2", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0xC5, size: 1, cycles: 11, disassembly: "PUSH B", mnemonic: "(sp-2) <- C; (sp-1) <- B; sp <- sp-2", effected_flags: None,
             func_ptr: |cpu, _, _| { 
-                let val = combine_bytes(cpu.b, cpu.c);
+                let val = cpu.bc();
                 cpu.push_stack(val);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xC6, size: 2, disassembly: "ADI D8", mnemonic: "A <- A + byte", effected_flags: "Z,S,P,CY,AC".into(),
+        optable.insert(&Instruction { opcode: 0xC6, size: 2, cycles: 7, disassembly: "ADI D8", mnemonic: "A <- A + byte", effected_flags: "Z,S,P,CY,AC".into(),
             func_ptr: |cpu, b2, _| { 
                 cpu.add(b2);
             }
         });
         
-        optable.insert(&Instruction { opcode: 0xC7, size: 1, disassembly: "RST 0", mnemonic: "CALL $0", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0xC7, size: 1, cycles: 11, disassembly: "RST 0", mnemonic: "CALL $0", effected_flags: None,
             func_ptr: |cpu, _, _| { 
                 cpu.call(0x00);
             }
         });
 
-        optable.insert(&Instruction { opcode: 0xC8, size: 1, disassembly: "RZ", mnemonic: "if Z, RET", effected_flags: None,
+        optable.insert(&Instruction { opcode: 0xC8, size: 1, cycles: 5, disassembly: "RZ", mnemonic: "if Z, RET", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Zero) {
+                    cpu.ret();
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xC9, size: 1, cycles: 10, disassembly: "RET", mnemonic: "PC.lo <- (sp); PC.hi <- (sp+1); SP <- SP+2", effected_flags: None,
             func_ptr: |cpu, _, _| { 
+                cpu.ret();
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xCA, size: 3, cycles: 10, disassembly: "JZ adr", mnemonic: "if Z, PC <- adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Zero) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.jmp(addr);
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xCB, size: 3, cycles: 10, disassembly: "*JMP adr", mnemonic: "Undocumented alias of JMP", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                let addr = combine_bytes(b3, b2);
+                cpu.jmp(addr);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xCC, size: 3, cycles: 11, disassembly: "CZ adr", mnemonic: "if Z, CALL adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
                 if cpu.condition_codes.is_set(ConditionFlag::Zero) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.call(addr);
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xCD, size: 3, cycles: 17, disassembly: "CALL adr", mnemonic: "(SP-1) <- PC.hi; (SP-2) <- PC.lo; SP <- SP-2; PC <- adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                let addr = combine_bytes(b3, b2);
+                cpu.call(addr);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xCE, size: 2, cycles: 7, disassembly: "ACI D8", mnemonic: "A <- A + data + CY", effected_flags: "Z,S,P,CY,AC".into(),
+            func_ptr: |cpu, b2, _| { 
+                cpu.adc(b2);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xCF, size: 1, cycles: 11, disassembly: "RST 1", mnemonic: "CALL $8", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.call(0x08);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD0, size: 1, cycles: 5, disassembly: "RNC", mnemonic: "if NC, RET", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Carry) {
+                    cpu.ret();
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD1, size: 1, cycles: 10, disassembly: "POP D", mnemonic: "E <- (sp); D <- (sp + 1); sp <- sp + 2", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let (high, low) = cpu.pop_stack_parts();
+                cpu.d = high;
+                cpu.e = low;
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD2, size: 3, cycles: 10, disassembly: "JNC adr", mnemonic: "if NC, PC <- adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Carry) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.jmp(addr);
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD3, size: 2, cycles: 10, disassembly: "OUT D8", mnemonic: "port <- A", effected_flags: None,
+            func_ptr: |cpu, b2, _| { 
+                cpu.port_access = Some(PortAccess::Output(b2, cpu.a));
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD4, size: 3, cycles: 11, disassembly: "CNC adr", mnemonic: "if NC, CALL adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Carry) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.call(addr);
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD5, size: 1, cycles: 11, disassembly: "PUSH D", mnemonic: "(sp-2) <- E; (sp-1) <- D; sp <- sp-2", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let val = cpu.de();
+                cpu.push_stack(val);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD6, size: 2, cycles: 7, disassembly: "SUI D8", mnemonic: "A <- A - data", effected_flags: "Z,S,P,CY,AC".into(),
+            func_ptr: |cpu, b2, _| { 
+                cpu.sub(b2);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD7, size: 1, cycles: 11, disassembly: "RST 2", mnemonic: "CALL $10", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.call(0x10);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD8, size: 1, cycles: 5, disassembly: "RC", mnemonic: "if C, RET", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Carry) {
+                    cpu.ret();
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xD9, size: 1, cycles: 10, disassembly: "*RET", mnemonic: "Undocumented alias of RET", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.ret();
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xDA, size: 3, cycles: 10, disassembly: "JC adr", mnemonic: "if C, PC <- adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Carry) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.jmp(addr);
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xDB, size: 2, cycles: 10, disassembly: "IN D8", mnemonic: "A <- port", effected_flags: None,
+            func_ptr: |cpu, b2, _| { 
+                cpu.port_access = Some(PortAccess::Input(b2));
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xDC, size: 3, cycles: 11, disassembly: "CC adr", mnemonic: "if C, CALL adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Carry) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.call(addr);
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xDD, size: 3, cycles: 17, disassembly: "*CALL adr", mnemonic: "Undocumented alias of CALL", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                let addr = combine_bytes(b3, b2);
+                cpu.call(addr);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xDE, size: 2, cycles: 7, disassembly: "SBI D8", mnemonic: "A <- A - data - CY", effected_flags: "Z,S,P,CY,AC".into(),
+            func_ptr: |cpu, b2, _| { 
+                cpu.sbb(b2);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xDF, size: 1, cycles: 11, disassembly: "RST 3", mnemonic: "CALL $18", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.call(0x18);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE0, size: 1, cycles: 5, disassembly: "RPO", mnemonic: "if PO, RET", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Parity) {
+                    cpu.ret();
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE1, size: 1, cycles: 10, disassembly: "POP H", mnemonic: "L <- (sp); H <- (sp + 1); sp <- sp + 2", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let (high, low) = cpu.pop_stack_parts();
+                cpu.h = high;
+                cpu.l = low;
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE2, size: 3, cycles: 10, disassembly: "JPO adr", mnemonic: "if PO, PC <- adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Parity) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.jmp(addr);
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE3, size: 1, cycles: 18, disassembly: "XTHL", mnemonic: "L <-> (SP); H <-> (SP+1)", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let sp = cpu.sp;
+                let low = cpu.read_byte(sp);
+                let high = cpu.read_byte(sp.wrapping_add(1));
+                cpu.write_byte(sp, cpu.l);
+                cpu.write_byte(sp.wrapping_add(1), cpu.h);
+                cpu.h = high;
+                cpu.l = low;
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE4, size: 3, cycles: 11, disassembly: "CPO adr", mnemonic: "if PO, CALL adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Parity) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.call(addr);
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE5, size: 1, cycles: 11, disassembly: "PUSH H", mnemonic: "(sp-2) <- L; (sp-1) <- H; sp <- sp-2", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let val = cpu.hl();
+                cpu.push_stack(val);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE6, size: 2, cycles: 7, disassembly: "ANI D8", mnemonic: "A <- A & data", effected_flags: "Z,S,P,CY,AC".into(),
+            func_ptr: |cpu, b2, _| { 
+                cpu.ana(b2);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE7, size: 1, cycles: 11, disassembly: "RST 4", mnemonic: "CALL $20", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.call(0x20);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE8, size: 1, cycles: 5, disassembly: "RPE", mnemonic: "if PE, RET", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Parity) {
+                    cpu.ret();
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xE9, size: 1, cycles: 5, disassembly: "PCHL", mnemonic: "PC.hi <- H; PC.lo <- L", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let addr = cpu.hl();
+                cpu.jmp(addr);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xEA, size: 3, cycles: 10, disassembly: "JPE adr", mnemonic: "if PE, PC <- adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Parity) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.jmp(addr);
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xEB, size: 1, cycles: 5, disassembly: "XCHG", mnemonic: "H <-> D; L <-> E", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                std::mem::swap(&mut cpu.h, &mut cpu.d);
+                std::mem::swap(&mut cpu.l, &mut cpu.e);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xEC, size: 3, cycles: 11, disassembly: "CPE adr", mnemonic: "if PE, CALL adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Parity) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.call(addr);
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xED, size: 3, cycles: 17, disassembly: "*CALL adr", mnemonic: "Undocumented alias of CALL", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                let addr = combine_bytes(b3, b2);
+                cpu.call(addr);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xEE, size: 2, cycles: 7, disassembly: "XRI D8", mnemonic: "A <- A ^ data", effected_flags: "Z,S,P,CY,AC".into(),
+            func_ptr: |cpu, b2, _| { 
+                cpu.xra(b2);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xEF, size: 1, cycles: 11, disassembly: "RST 5", mnemonic: "CALL $28", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.call(0x28);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF0, size: 1, cycles: 5, disassembly: "RP", mnemonic: "if P, RET", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Sign) {
                     cpu.ret();
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF1, size: 1, cycles: 10, disassembly: "POP PSW", mnemonic: "flags <- (sp); A <- (sp + 1); sp <- sp + 2", effected_flags: "Z,S,P,CY,AC".into(),
+            func_ptr: |cpu, _, _| { 
+                let val = cpu.pop_stack();
+                cpu.set(Reg16::PSW, val);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF2, size: 3, cycles: 10, disassembly: "JP adr", mnemonic: "if P, PC <- adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Sign) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.jmp(addr);
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF3, size: 1, cycles: 4, disassembly: "DI", mnemonic: "Disable interrupts", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.interrupts_enabled = false;
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF4, size: 3, cycles: 11, disassembly: "CP adr", mnemonic: "if P, CALL adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if !cpu.condition_codes.is_set(ConditionFlag::Sign) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.call(addr);
+                    cpu.cycles += 6;
                 }
             }
         });
 
+        optable.insert(&Instruction { opcode: 0xF5, size: 1, cycles: 11, disassembly: "PUSH PSW", mnemonic: "(sp-2) <- flags; (sp-1) <- A; sp <- sp-2", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                let val = cpu.get(Reg16::PSW);
+                cpu.push_stack(val);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF6, size: 2, cycles: 7, disassembly: "ORI D8", mnemonic: "A <- A | data", effected_flags: "Z,S,P,CY,AC".into(),
+            func_ptr: |cpu, b2, _| { 
+                cpu.ora(b2);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF7, size: 1, cycles: 11, disassembly: "RST 6", mnemonic: "CALL $30", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.call(0x30);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF8, size: 1, cycles: 5, disassembly: "RM", mnemonic: "if M, RET", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Sign) {
+                    cpu.ret();
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xF9, size: 1, cycles: 5, disassembly: "SPHL", mnemonic: "SP <- HL", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.sp = cpu.hl();
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xFA, size: 3, cycles: 10, disassembly: "JM adr", mnemonic: "if M, PC <- adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Sign) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.jmp(addr);
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xFB, size: 1, cycles: 4, disassembly: "EI", mnemonic: "Enable interrupts", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.interrupts_enabled = true;
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xFC, size: 3, cycles: 11, disassembly: "CM adr", mnemonic: "if M, CALL adr", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                if cpu.condition_codes.is_set(ConditionFlag::Sign) {
+                    let addr = combine_bytes(b3, b2);
+                    cpu.call(addr);
+                    cpu.cycles += 6;
+                }
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xFD, size: 3, cycles: 17, disassembly: "*CALL adr", mnemonic: "Undocumented alias of CALL", effected_flags: None,
+            func_ptr: |cpu, b2, b3| { 
+                let addr = combine_bytes(b3, b2);
+                cpu.call(addr);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xFE, size: 2, cycles: 7, disassembly: "CPI D8", mnemonic: "A - data", effected_flags: "Z,S,P,CY,AC".into(),
+            func_ptr: |cpu, b2, _| { 
+                cpu.cmp(b2);
+            }
+        });
+
+        optable.insert(&Instruction { opcode: 0xFF, size: 1, cycles: 11, disassembly: "RST 7", mnemonic: "CALL $38", effected_flags: None,
+            func_ptr: |cpu, _, _| { 
+                cpu.call(0x38);
+            }
+        });
+
         optable
     }
 }
@@ -0,0 +1,200 @@
+use crate::Cpu8080;
+use crate::io::IoBus;
+use crate::symbols::SymbolTable;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/**
+ * Name of the code outside any subroutine when no symbol covers it
+ * */
+pub const TOP_LEVEL: &str = "(top)";
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Counts {
+    pub instructions: u64,
+    pub cycles: u64
+}
+
+impl Counts {
+    fn add(&mut self, other: Counts) {
+        self.instructions += other.instructions;
+        self.cycles += other.cycles;
+    }
+}
+
+/**
+ * Where a routine's time went
+ * */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Routine {
+    pub name: String,
+    /**
+     * Instructions of the routine itself
+     * */
+    pub own: Counts,
+    /**
+     * The routine and everything it called
+     * */
+    pub total: Counts
+}
+
+/**
+ * Counts instructions and cycles per address and per call stack. With `Cpu8080::call_stack` on, code belongs to
+ * the routine the innermost CALL or RST went to, and code outside any call to the symbol at or below it, or to
+ * TOP_LEVEL. Symbols are only looked up when reporting, so they can be loaded after profiling.
+ *
+ *  let mut profiler = Profiler::new();
+ *  while running {
+ *      profiler.step(&mut cpu, &mut bus);
+ *  }
+ *  profiler.write_report(&mut io::stdout(), &cpu, &symbols, 20)?;
+ * */
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    /**
+     * Keyed by the address of the top level code, the target of every call frame, and pc
+     * */
+    samples: HashMap<Vec<u16>, Counts>,
+    key: Vec<u16>,
+    total: Counts
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Notes where the instruction at pc runs from. Call it right before executing each instruction, then end
+     * with the cycles it took
+     * */
+    pub fn begin(&mut self, cpu: &Cpu8080) {
+        self.key.clear();
+        let frames = cpu.call_stack.as_ref().map(|stack| stack.frames()).unwrap_or(&[]);
+        self.key.push(frames.first().map_or(cpu.pc, |frame| frame.call_site));
+        self.key.extend(frames.iter().map(|frame| frame.target));
+        self.key.push(cpu.pc);
+    }
+
+    pub fn end(&mut self, cycles: u32) {
+        let counts = Counts { instructions: 1, cycles: cycles as u64 };
+        match self.samples.get_mut(self.key.as_slice()) {
+            Some(sample) => sample.add(counts),
+            None => {
+                self.samples.insert(self.key.clone(), counts);
+            }
+        }
+        self.total.add(counts);
+    }
+
+    /**
+     * Profiles then executes one instruction
+     * */
+    pub fn step(&mut self, cpu: &mut Cpu8080, io: &mut dyn IoBus) -> u32 {
+        self.begin(cpu);
+        let cycles = cpu.step(io);
+        self.end(cycles);
+        cycles
+    }
+
+    pub fn total(&self) -> Counts {
+        self.total
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.total = Counts::default();
+    }
+
+    /**
+     * Counts per instruction address, most cycles first
+     * */
+    pub fn hotspots(&self) -> Vec<(u16, Counts)> {
+        let mut by_addr: HashMap<u16, Counts> = HashMap::new();
+        for (key, counts) in self.samples.iter() {
+            by_addr.entry(*key.last().unwrap()).or_default().add(*counts);
+        }
+        let mut hotspots: Vec<(u16, Counts)> = by_addr.into_iter().collect();
+        hotspots.sort_by(|a, b| b.1.cycles.cmp(&a.1.cycles).then(a.0.cmp(&b.0)));
+        hotspots
+    }
+
+    /**
+     * Counts per routine, most cycles spent in the routine itself first
+     * */
+    pub fn routines(&self, symbols: &SymbolTable) -> Vec<Routine> {
+        let mut routines: HashMap<String, Routine> = HashMap::new();
+        for (key, counts) in self.samples.iter() {
+            let names = stack_names(key, symbols);
+            let mut seen = HashSet::new();
+            for (i, name) in names.iter().enumerate() {
+                let routine = routines.entry(name.clone())
+                    .or_insert_with(|| Routine { name: name.clone(), own: Counts::default(), total: Counts::default() });
+                if i == names.len() - 1 {
+                    routine.own.add(*counts);
+                }
+                // recursion puts a routine on the stack more than once, its time only counts once
+                if seen.insert(name) {
+                    routine.total.add(*counts);
+                }
+            }
+        }
+        let mut routines: Vec<Routine> = routines.into_values().collect();
+        routines.sort_by(|a, b| b.own.cycles.cmp(&a.own.cycles).then_with(|| a.name.cmp(&b.name)));
+        routines
+    }
+
+    /**
+     * Writes the top routines and the top addresses, with the instruction at each, sorted by cycles
+     * */
+    pub fn write_report<W: Write>(&self, sink: &mut W, cpu: &Cpu8080, symbols: &SymbolTable, top: usize) -> io::Result<()> {
+        let percent = |cycles: u64| if self.total.cycles == 0 { 0.0 } else { cycles as f64 * 100.0 / self.total.cycles as f64 };
+        writeln!(sink, "{} instructions, {} cycles", self.total.instructions, self.total.cycles)?;
+
+        writeln!(sink)?;
+        writeln!(sink, "{:>12} {:>7} {:>12} {:>7} {:>12}  routine", "own cycles", "%", "total", "%", "instructions")?;
+        for routine in self.routines(symbols).iter().take(top) {
+            writeln!(sink, "{:>12} {:>6.2}% {:>12} {:>6.2}% {:>12}  {}",
+                routine.own.cycles, percent(routine.own.cycles), routine.total.cycles, percent(routine.total.cycles),
+                routine.own.instructions, routine.name)?;
+        }
+
+        writeln!(sink)?;
+        writeln!(sink, "{:>12} {:>7} {:>12}  address", "cycles", "%", "instructions")?;
+        for (addr, counts) in self.hotspots().iter().take(top) {
+            let (disassembly, _) = cpu.opcode_table.disassemble_symbolic(&cpu.memory, *addr, symbols);
+            let label = symbols.describe(*addr).map(|name| format!(" {}", name)).unwrap_or_default();
+            writeln!(sink, "{:>12} {:>6.2}% {:>12}  {:04X}{:<16}  {}",
+                counts.cycles, percent(counts.cycles), counts.instructions, addr, label, disassembly)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Writes the cycles of every call stack in the folded format of flamegraph.pl and inferno: routine names from
+     * the outermost in, separated by ';', then a space and the count
+     * */
+    pub fn write_folded<W: Write>(&self, sink: &mut W, symbols: &SymbolTable) -> io::Result<()> {
+        let mut stacks: HashMap<String, u64> = HashMap::new();
+        for (key, counts) in self.samples.iter() {
+            *stacks.entry(stack_names(key, symbols).join(";")).or_default() += counts.cycles;
+        }
+        let mut stacks: Vec<(String, u64)> = stacks.into_iter().collect();
+        stacks.sort();
+        for (stack, cycles) in stacks {
+            writeln!(sink, "{} {}", stack, cycles)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Routine names of a sample from the outermost in: the top level code, then the target of every call
+ * */
+fn stack_names(key: &[u16], symbols: &SymbolTable) -> Vec<String> {
+    let top = symbols.nearest(key[0]).map_or_else(|| TOP_LEVEL.to_string(), |(_, name)| name.to_string());
+    let calls = key[1..key.len() - 1].iter().map(|&target| {
+        symbols.describe(target).unwrap_or_else(|| format!("{:04X}", target))
+    });
+    std::iter::once(top).chain(calls).collect()
+}
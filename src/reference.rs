@@ -0,0 +1,463 @@
+use crate::{Cpu8080, ConditionBitset};
+use crate::io::IoBus;
+
+// Flag bits in the PSW layout: S Z 0 AC 0 P 1 CY
+const CY: u8 = 0x01;
+const P: u8 = 0x04;
+const AC: u8 = 0x10;
+const Z: u8 = 0x40;
+const S: u8 = 0x80;
+
+/**
+ * Register named by a 3 bit field: B C D E H L M A, with M being memory at HL
+ * */
+fn get_reg(cpu: &Cpu8080, r: u8) -> u8 {
+    match r {
+        0 => cpu.b,
+        1 => cpu.c,
+        2 => cpu.d,
+        3 => cpu.e,
+        4 => cpu.h,
+        5 => cpu.l,
+        6 => read(cpu, hl(cpu)),
+        _ => cpu.a
+    }
+}
+
+fn set_reg(cpu: &mut Cpu8080, r: u8, value: u8) {
+    match r {
+        0 => cpu.b = value,
+        1 => cpu.c = value,
+        2 => cpu.d = value,
+        3 => cpu.e = value,
+        4 => cpu.h = value,
+        5 => cpu.l = value,
+        6 => write(cpu, hl(cpu), value),
+        _ => cpu.a = value
+    }
+}
+
+fn hl(cpu: &Cpu8080) -> u16 {
+    (cpu.h as u16) << 8 | cpu.l as u16
+}
+
+/**
+ * Register pair named by a 2 bit field: BC DE HL SP
+ * */
+fn get_pair(cpu: &Cpu8080, rp: u8) -> u16 {
+    match rp {
+        0 => (cpu.b as u16) << 8 | cpu.c as u16,
+        1 => (cpu.d as u16) << 8 | cpu.e as u16,
+        2 => hl(cpu),
+        _ => cpu.sp
+    }
+}
+
+fn set_pair(cpu: &mut Cpu8080, rp: u8, value: u16) {
+    let (high, low) = ((value >> 8) as u8, value as u8);
+    match rp {
+        0 => { cpu.b = high; cpu.c = low; },
+        1 => { cpu.d = high; cpu.e = low; },
+        2 => { cpu.h = high; cpu.l = low; },
+        _ => cpu.sp = value
+    }
+}
+
+fn read(cpu: &Cpu8080, addr: u16) -> u8 {
+    cpu.memory[addr as usize]
+}
+
+fn write(cpu: &mut Cpu8080, addr: u16, value: u8) {
+    cpu.memory[addr as usize] = value;
+}
+
+fn read_word(cpu: &Cpu8080, addr: u16) -> u16 {
+    (read(cpu, addr.wrapping_add(1)) as u16) << 8 | read(cpu, addr) as u16
+}
+
+fn fetch(cpu: &mut Cpu8080) -> u8 {
+    let value = read(cpu, cpu.pc);
+    cpu.pc = cpu.pc.wrapping_add(1);
+    value
+}
+
+fn fetch_word(cpu: &mut Cpu8080) -> u16 {
+    let low = fetch(cpu) as u16;
+    let high = fetch(cpu) as u16;
+    high << 8 | low
+}
+
+fn push(cpu: &mut Cpu8080, value: u16) {
+    cpu.sp = cpu.sp.wrapping_sub(1);
+    write(cpu, cpu.sp, (value >> 8) as u8);
+    cpu.sp = cpu.sp.wrapping_sub(1);
+    write(cpu, cpu.sp, value as u8);
+}
+
+fn pop(cpu: &mut Cpu8080) -> u16 {
+    let value = read_word(cpu, cpu.sp);
+    cpu.sp = cpu.sp.wrapping_add(2);
+    value
+}
+
+/**
+ * S, Z and P of a result
+ * */
+fn szp(value: u8) -> u8 {
+    let mut flags = 0;
+    if value & 0x80 != 0 { flags |= S; }
+    if value == 0 { flags |= Z; }
+    if value.count_ones().is_multiple_of(2) { flags |= P; }
+    flags
+}
+
+/**
+ * Condition named by a 3 bit field: NZ Z NC C PO PE P M
+ * */
+fn condition(flags: u8, ccc: u8) -> bool {
+    let set = match ccc >> 1 {
+        0 => flags & Z,
+        1 => flags & CY,
+        2 => flags & P,
+        _ => flags & S
+    } != 0;
+    if ccc & 1 == 0 { !set } else { set }
+}
+
+/**
+ * Arithmetic/logic operation named by a 3 bit field: ADD ADC SUB SBB ANA XRA ORA CMP.
+ * Returns the new accumulator (unchanged for CMP) and flags
+ * */
+fn alu(op: u8, a: u8, value: u8, flags: u8) -> (u8, u8) {
+    let carry = (flags & CY) as u16;
+    match op {
+        0 | 1 => {
+            let c = if op == 1 { carry } else { 0 };
+            let sum = a as u16 + value as u16 + c;
+            let half = (a as u16 & 0xF) + (value as u16 & 0xF) + c;
+            let result = sum as u8;
+            let mut f = szp(result);
+            if sum > 0xFF { f |= CY; }
+            if half > 0xF { f |= AC; }
+            (result, f)
+        },
+        2 | 3 | 7 => {
+            // two's complement addition, the carry out is inverted to become the borrow
+            let borrow = if op == 3 { carry } else { 0 };
+            let sum = a as u16 + (!value) as u16 + (1 - borrow);
+            let half = (a as u16 & 0xF) + (!value as u16 & 0xF) + (1 - borrow);
+            let result = sum as u8;
+            let mut f = szp(result);
+            if sum <= 0xFF { f |= CY; }
+            if half > 0xF { f |= AC; }
+            (if op == 7 { a } else { result }, f)
+        },
+        4 => {
+            let result = a & value;
+            let mut f = szp(result);
+            if (a | value) & 0x08 != 0 { f |= AC; }
+            (result, f)
+        },
+        5 => {
+            let result = a ^ value;
+            (result, szp(result))
+        },
+        _ => {
+            let result = a | value;
+            (result, szp(result))
+        }
+    }
+}
+
+/**
+ * Executes one instruction at pc, returning its cycle count. A halted CPU idles for 4 cycles like `Cpu8080::step`.
+ *
+ * This is a deliberately simple and slow interpreter written straight from the instruction set description in
+ * the 8080 programmer's manual. It decodes opcodes from their bit fields and does its own flag arithmetic
+ * instead of going through the opcode table and ALU helpers, so it can be run next to `Cpu8080::step`
+ * on the same state to catch divergences. It is not meant for running software.
+ * */
+pub fn step(cpu: &mut Cpu8080, io: &mut dyn IoBus) -> u32 {
+    if cpu.halted {
+        cpu.cycles += 4;
+        return 4;
+    }
+
+    let mut flags = cpu.condition_codes.to_psw();
+    let opcode = fetch(cpu);
+    let ddd = (opcode >> 3) & 0x07;
+    let sss = opcode & 0x07;
+    let rp = (opcode >> 4) & 0x03;
+
+    let cycles = match opcode {
+        // MOV, with HLT in place of MOV M,M
+        0x76 => {
+            cpu.halted = true;
+            7
+        },
+        0x40..=0x7F => {
+            let value = get_reg(cpu, sss);
+            set_reg(cpu, ddd, value);
+            if ddd == 6 || sss == 6 { 7 } else { 5 }
+        },
+        // register/memory arithmetic and logic
+        0x80..=0xBF => {
+            let (a, f) = alu(ddd, cpu.a, get_reg(cpu, sss), flags);
+            cpu.a = a;
+            flags = f;
+            if sss == 6 { 7 } else { 4 }
+        },
+        // immediate arithmetic and logic
+        op if op & 0xC7 == 0xC6 => {
+            let value = fetch(cpu);
+            let (a, f) = alu(ddd, cpu.a, value, flags);
+            cpu.a = a;
+            flags = f;
+            7
+        },
+        // NOP and its undocumented aliases
+        op if op & 0xC7 == 0x00 => 4,
+        // LXI
+        op if op & 0xCF == 0x01 => {
+            let value = fetch_word(cpu);
+            set_pair(cpu, rp, value);
+            10
+        },
+        // DAD
+        op if op & 0xCF == 0x09 => {
+            let sum = hl(cpu) as u32 + get_pair(cpu, rp) as u32;
+            set_pair(cpu, 2, sum as u16);
+            flags = (flags & !CY) | if sum > 0xFFFF { CY } else { 0 };
+            10
+        },
+        // INX, DCX
+        op if op & 0xCF == 0x03 => {
+            let value = get_pair(cpu, rp).wrapping_add(1);
+            set_pair(cpu, rp, value);
+            5
+        },
+        op if op & 0xCF == 0x0B => {
+            let value = get_pair(cpu, rp).wrapping_sub(1);
+            set_pair(cpu, rp, value);
+            5
+        },
+        // INR, DCR: CY is left alone
+        op if op & 0xC7 == 0x04 => {
+            let value = get_reg(cpu, ddd);
+            let result = value.wrapping_add(1);
+            set_reg(cpu, ddd, result);
+            flags = (flags & CY) | szp(result) | if value & 0xF == 0xF { AC } else { 0 };
+            if ddd == 6 { 10 } else { 5 }
+        },
+        op if op & 0xC7 == 0x05 => {
+            let value = get_reg(cpu, ddd);
+            let result = value.wrapping_sub(1);
+            set_reg(cpu, ddd, result);
+            flags = (flags & CY) | szp(result) | if value & 0xF != 0 { AC } else { 0 };
+            if ddd == 6 { 10 } else { 5 }
+        },
+        // MVI
+        op if op & 0xC7 == 0x06 => {
+            let value = fetch(cpu);
+            set_reg(cpu, ddd, value);
+            if ddd == 6 { 10 } else { 7 }
+        },
+        // STAX, LDAX
+        0x02 | 0x12 => {
+            let addr = get_pair(cpu, rp);
+            write(cpu, addr, cpu.a);
+            7
+        },
+        0x0A | 0x1A => {
+            cpu.a = read(cpu, get_pair(cpu, rp));
+            7
+        },
+        0x22 => {
+            let addr = fetch_word(cpu);
+            write(cpu, addr, cpu.l);
+            write(cpu, addr.wrapping_add(1), cpu.h);
+            16
+        },
+        0x2A => {
+            let addr = fetch_word(cpu);
+            cpu.l = read(cpu, addr);
+            cpu.h = read(cpu, addr.wrapping_add(1));
+            16
+        },
+        0x32 => {
+            let addr = fetch_word(cpu);
+            write(cpu, addr, cpu.a);
+            13
+        },
+        0x3A => {
+            let addr = fetch_word(cpu);
+            cpu.a = read(cpu, addr);
+            13
+        },
+        // rotates only touch CY
+        0x07 => {
+            let out = cpu.a >> 7;
+            cpu.a = cpu.a << 1 | out;
+            flags = (flags & !CY) | out;
+            4
+        },
+        0x0F => {
+            let out = cpu.a & 1;
+            cpu.a = cpu.a >> 1 | out << 7;
+            flags = (flags & !CY) | out;
+            4
+        },
+        0x17 => {
+            let out = cpu.a >> 7;
+            cpu.a = cpu.a << 1 | (flags & CY);
+            flags = (flags & !CY) | out;
+            4
+        },
+        0x1F => {
+            let out = cpu.a & 1;
+            cpu.a = cpu.a >> 1 | (flags & CY) << 7;
+            flags = (flags & !CY) | out;
+            4
+        },
+        0x27 => {
+            let a = cpu.a;
+            let mut correction = 0u8;
+            let mut carry = flags & CY;
+            if a & 0xF > 9 || flags & AC != 0 {
+                correction |= 0x06;
+            }
+            if a > 0x99 || carry != 0 {
+                correction |= 0x60;
+                carry = CY;
+            }
+            let result = a.wrapping_add(correction);
+            let half = if (a & 0xF) + (correction & 0xF) > 0xF { AC } else { 0 };
+            cpu.a = result;
+            flags = szp(result) | carry | half;
+            4
+        },
+        0x2F => {
+            cpu.a = !cpu.a;
+            4
+        },
+        0x37 => {
+            flags |= CY;
+            4
+        },
+        0x3F => {
+            flags ^= CY;
+            4
+        },
+        // Rcc, Jcc, Ccc
+        op if op & 0xC7 == 0xC0 => {
+            if condition(flags, ddd) {
+                cpu.pc = pop(cpu);
+                11
+            } else {
+                5
+            }
+        },
+        op if op & 0xC7 == 0xC2 => {
+            let addr = fetch_word(cpu);
+            if condition(flags, ddd) {
+                cpu.pc = addr;
+            }
+            10
+        },
+        op if op & 0xC7 == 0xC4 => {
+            let addr = fetch_word(cpu);
+            if condition(flags, ddd) {
+                push(cpu, cpu.pc);
+                cpu.pc = addr;
+                17
+            } else {
+                11
+            }
+        },
+        // RST
+        op if op & 0xC7 == 0xC7 => {
+            push(cpu, cpu.pc);
+            cpu.pc = (ddd as u16) << 3;
+            11
+        },
+        // POP, PUSH, with PSW in place of SP
+        0xF1 => {
+            let value = pop(cpu);
+            cpu.a = (value >> 8) as u8;
+            flags = value as u8;
+            10
+        },
+        op if op & 0xCF == 0xC1 => {
+            let value = pop(cpu);
+            set_pair(cpu, rp, value);
+            10
+        },
+        0xF5 => {
+            push(cpu, (cpu.a as u16) << 8 | flags as u16);
+            11
+        },
+        op if op & 0xCF == 0xC5 => {
+            push(cpu, get_pair(cpu, rp));
+            11
+        },
+        // JMP, RET, CALL and their undocumented aliases
+        0xC3 | 0xCB => {
+            cpu.pc = fetch_word(cpu);
+            10
+        },
+        0xC9 | 0xD9 => {
+            cpu.pc = pop(cpu);
+            10
+        },
+        0xCD | 0xDD | 0xED | 0xFD => {
+            let addr = fetch_word(cpu);
+            push(cpu, cpu.pc);
+            cpu.pc = addr;
+            17
+        },
+        0xD3 => {
+            let port = fetch(cpu);
+            io.output(port, cpu.a);
+            10
+        },
+        0xDB => {
+            let port = fetch(cpu);
+            cpu.a = io.input(port);
+            10
+        },
+        0xE3 => {
+            let (l, h) = (read(cpu, cpu.sp), read(cpu, cpu.sp.wrapping_add(1)));
+            write(cpu, cpu.sp, cpu.l);
+            write(cpu, cpu.sp.wrapping_add(1), cpu.h);
+            cpu.l = l;
+            cpu.h = h;
+            18
+        },
+        0xE9 => {
+            cpu.pc = hl(cpu);
+            5
+        },
+        0xEB => {
+            std::mem::swap(&mut cpu.h, &mut cpu.d);
+            std::mem::swap(&mut cpu.l, &mut cpu.e);
+            5
+        },
+        0xF3 => {
+            cpu.interrupts_enabled = false;
+            4
+        },
+        0xFB => {
+            cpu.interrupts_enabled = true;
+            4
+        },
+        0xF9 => {
+            cpu.sp = hl(cpu);
+            5
+        },
+        _ => unreachable!("every opcode is decoded above")
+    };
+
+    cpu.condition_codes = ConditionBitset::from_psw(flags);
+    cpu.cycles += cycles as u64;
+    cycles
+}
@@ -0,0 +1,288 @@
+use crate::{Cpu8080, ConditionBitset};
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const MAGIC: &[u8; 8] = b"I8080SAV";
+/**
+ * Bumped whenever a section's layout changes, older snapshots are then refused instead of misread
+ * */
+pub const VERSION: u16 = 1;
+
+const CPU: [u8; 4] = *b"CPU ";
+const MEMORY: [u8; 4] = *b"MEM ";
+
+/**
+ * A snapshot of a machine: tagged sections of state wrapped in a versioned, checksummed container.
+ * On disk it is MAGIC, VERSION, then every section as its 4 byte tag, a u32 length and the data, then the
+ * CRC-32 of everything before it. Integers are little endian throughout.
+ * */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaveState {
+    sections: Vec<([u8; 4], Vec<u8>)>
+}
+
+/**
+ * Something whose state can go into a SaveState and come back out. load_state leaves self untouched when the
+ * snapshot doesn't fit, as far as it can check before restoring
+ * */
+pub trait Snapshot {
+    fn save_state(&self, state: &mut SaveState);
+    fn load_state(&mut self, state: &SaveState) -> io::Result<()>;
+}
+
+impl SaveState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Snapshot of anything that implements Snapshot
+     * */
+    pub fn of<S: Snapshot + ?Sized>(machine: &S) -> Self {
+        let mut state = SaveState::new();
+        machine.save_state(&mut state);
+        state
+    }
+
+    /**
+     * Adds a section, replacing one with the same tag
+     * */
+    pub fn put(&mut self, tag: [u8; 4], data: Vec<u8>) {
+        match self.sections.iter_mut().find(|(t, _)| *t == tag) {
+            Some(section) => section.1 = data,
+            None => self.sections.push((tag, data))
+        }
+    }
+
+    pub fn get(&self, tag: [u8; 4]) -> Option<&[u8]> {
+        self.sections.iter().find(|(t, _)| *t == tag).map(|(_, data)| data.as_slice())
+    }
+
+    /**
+     * A section that has to be there, as a reader
+     * */
+    pub fn reader(&self, tag: [u8; 4]) -> io::Result<StateReader<'_>> {
+        match self.get(tag) {
+            Some(data) => Ok(StateReader { data, pos: 0 }),
+            None => Err(invalid(format!("save state has no {} section", String::from_utf8_lossy(&tag).trim_end())))
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        for (tag, data) in self.sections.iter() {
+            bytes.extend_from_slice(tag);
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(data);
+        }
+        let checksum = crc32(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(invalid("not a save state".to_string()));
+        }
+        if bytes.len() < MAGIC.len() + 6 {
+            return Err(invalid("save state is truncated".to_string()));
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - 4);
+        // checksum first, so a damaged version field isn't reported as a version mismatch
+        if crc32(body).to_le_bytes() != checksum {
+            return Err(invalid("save state is corrupt, checksum mismatch".to_string()));
+        }
+        let mut reader = StateReader { data: &body[MAGIC.len()..], pos: 0 };
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(invalid(format!("save state is version {}, this build reads version {}", version, VERSION)));
+        }
+        let mut state = SaveState::new();
+        while !reader.is_empty() {
+            let tag = reader.bytes(4)?.try_into().unwrap();
+            let len = reader.u32()? as usize;
+            state.sections.push((tag, reader.bytes(len)?.to_vec()));
+        }
+        Ok(state)
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+/**
+ * Builds the data of a section
+ * */
+#[derive(Clone, Debug, Default)]
+pub struct StateWriter(pub Vec<u8>);
+
+impl StateWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u8(&mut self, value: u8) -> &mut Self {
+        self.0.push(value);
+        self
+    }
+
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.u8(value as u8)
+    }
+
+    pub fn u16(&mut self, value: u16) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    /**
+     * Length prefixed bytes
+     * */
+    pub fn blob(&mut self, bytes: &[u8]) -> &mut Self {
+        self.u32(bytes.len() as u32);
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/**
+ * Reads back what a StateWriter wrote, failing cleanly on short data
+ * */
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        StateReader { data, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    pub fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + len).ok_or_else(|| invalid("save state is truncated".to_string()))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    pub fn blob(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+}
+
+pub fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/**
+ * Registers, flags, halt and interrupt state and the cycle count go in the CPU section, the 64K of RAM in MEM.
+ * Restoring clears the shadow call stack and memory log, which describe execution before the snapshot
+ * */
+impl Snapshot for Cpu8080 {
+    fn save_state(&self, state: &mut SaveState) {
+        let mut cpu = StateWriter::new();
+        cpu.u16(self.pc).u16(self.sp)
+            .u8(self.a).u8(self.b).u8(self.c).u8(self.d).u8(self.e).u8(self.h).u8(self.l)
+            .u8(self.condition_codes.to_psw())
+            .bool(self.halted).bool(self.interrupts_enabled)
+            .u64(self.cycles);
+        state.put(CPU, cpu.into_inner());
+        state.put(MEMORY, self.memory.clone());
+    }
+
+    fn load_state(&mut self, state: &SaveState) -> io::Result<()> {
+        let memory = state.get(MEMORY).ok_or_else(|| invalid("save state has no MEM section".to_string()))?;
+        if memory.len() != self.memory.len() {
+            return Err(invalid(format!("save state has {} bytes of memory, expected {}", memory.len(), self.memory.len())));
+        }
+        let mut r = state.reader(CPU)?;
+        let (pc, sp) = (r.u16()?, r.u16()?);
+        let registers = r.bytes(7)?;
+        let psw = r.u8()?;
+        let (halted, interrupts_enabled) = (r.bool()?, r.bool()?);
+        let cycles = r.u64()?;
+
+        self.pc = pc;
+        self.sp = sp;
+        self.a = registers[0];
+        self.b = registers[1];
+        self.c = registers[2];
+        self.d = registers[3];
+        self.e = registers[4];
+        self.h = registers[5];
+        self.l = registers[6];
+        self.condition_codes = ConditionBitset::from_psw(psw);
+        self.halted = halted;
+        self.interrupts_enabled = interrupts_enabled;
+        self.cycles = cycles;
+        self.port_access = None;
+        self.memory.copy_from_slice(memory);
+        if let Some(log) = &mut self.memory_log {
+            log.clear();
+        }
+        if let Some(call_stack) = &mut self.call_stack {
+            call_stack.clear();
+        }
+        Ok(())
+    }
+}
+
+/**
+ * CRC-32 as used by zip and PNG
+ * */
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,100 @@
+use crate::opcode::{Category, OpcodeTable, is_conditional};
+use std::io::{self, Write};
+
+/**
+ * Executions per opcode, kept by `Cpu8080::execute` while `Cpu8080::stats` is Some. Conditional jumps, calls and
+ * returns also count how often they were taken.
+ * */
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstructionStats {
+    counts: Vec<u64>,
+    taken: Vec<u64>
+}
+
+impl Default for InstructionStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstructionStats {
+    pub fn new() -> Self {
+        InstructionStats { counts: vec![0; 256], taken: vec![0; 256] }
+    }
+
+    pub fn record(&mut self, opcode: u8, taken: bool) {
+        self.counts[opcode as usize] += 1;
+        self.taken[opcode as usize] += taken as u64;
+    }
+
+    pub fn count(&self, opcode: u8) -> u64 {
+        self.counts[opcode as usize]
+    }
+
+    /**
+     * How often a conditional instruction was taken, always 0 for the others
+     * */
+    pub fn taken(&self, opcode: u8) -> u64 {
+        self.taken[opcode as usize]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.iter_mut().for_each(|count| *count = 0);
+        self.taken.iter_mut().for_each(|count| *count = 0);
+    }
+
+    pub fn category_count(&self, category: Category) -> u64 {
+        (0..=255u8).filter(|&op| Category::of(op) == category).map(|op| self.count(op)).sum()
+    }
+
+    /**
+     * Executed and taken counts of the conditional instructions whose opcode & 0xC7 is kind: 0xC2 for the
+     * jumps, 0xC4 for the calls and 0xC0 for the returns
+     * */
+    fn conditional_counts(&self, kind: u8) -> (u64, u64) {
+        (0..=255u8).filter(|&op| is_conditional(op) && op & 0xC7 == kind)
+            .fold((0, 0), |(count, taken), op| (count + self.count(op), taken + self.taken(op)))
+    }
+
+    /**
+     * Writes the counts per category, the taken ratios of the conditional instructions and the opcodes that ran,
+     * most executed first
+     * */
+    pub fn write_report<W: Write>(&self, sink: &mut W, opcode_table: &OpcodeTable) -> io::Result<()> {
+        let total = self.total();
+        let percent = |count: u64| if total == 0 { 0.0 } else { count as f64 * 100.0 / total as f64 };
+        let ratio = |taken: u64, count: u64| if count == 0 { 0.0 } else { taken as f64 * 100.0 / count as f64 };
+        writeln!(sink, "{} instructions", total)?;
+
+        writeln!(sink)?;
+        for category in Category::ALL.iter() {
+            let count = self.category_count(*category);
+            writeln!(sink, "{:<24}{:>14} {:>6.2}%", category.name(), count, percent(count))?;
+        }
+
+        writeln!(sink)?;
+        writeln!(sink, "{:<24}{:>14} {:>14} {:>7}", "conditional", "taken", "not taken", "taken")?;
+        for (name, kind) in [("jumps", 0xC2), ("calls", 0xC4), ("returns", 0xC0)].iter() {
+            let (count, taken) = self.conditional_counts(*kind);
+            writeln!(sink, "{:<24}{:>14} {:>14} {:>6.2}%", name, taken, count - taken, ratio(taken, count))?;
+        }
+
+        writeln!(sink)?;
+        let mut opcodes: Vec<u8> = (0..=255u8).filter(|&op| self.count(op) != 0).collect();
+        opcodes.sort_by(|a, b| self.count(*b).cmp(&self.count(*a)).then(a.cmp(b)));
+        for op in opcodes {
+            let name = opcode_table.get(op).map_or("?", |instruction| instruction.disassembly);
+            let count = self.count(op);
+            write!(sink, "{:02X}  {:<18}{:>14} {:>6.2}%", op, name, count, percent(count))?;
+            if is_conditional(op) {
+                write!(sink, "  taken {:.2}%", ratio(self.taken(op), count))?;
+            }
+            writeln!(sink)?;
+        }
+        Ok(())
+    }
+}
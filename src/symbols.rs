@@ -0,0 +1,182 @@
+use crate::debugger::expr::parse_number;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// describe only names an address this far past its nearest symbol
+const MAX_OFFSET: u16 = 0x100;
+
+/**
+ * Names for addresses, looked up case insensitively
+ * */
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    by_name: HashMap<String, (String, u16)>,
+    by_addr: BTreeMap<u16, Vec<String>>
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut table = SymbolTable::new();
+        table.load_file(path)?;
+        Ok(table)
+    }
+
+    /**
+     * Adds the symbols of a file to the table, see parse for the formats
+     * */
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        let text = fs::read_to_string(&path)?;
+        self.parse(&text).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.as_ref().display(), e)))
+    }
+
+    /**
+     * Adds the symbols in text, returning how many. Each line is either
+     *  `0100 START  0103 LOOP`    addr/name pairs, as in the .sym files of MAC, RMAC, M80 and zmac
+     *  `START 0100`               a name then its address
+     *  `START EQU 0100H`, `START = 0100`, `START: 0100`
+     * Addresses are hex, with an optional H suffix, 0x or $ prefix, or decimal after #. A line is read as
+     * addr/name pairs when it starts with four hex digits and has an even number of fields. A line like
+     * `FACE 1000` reads both ways, it takes the layout of the first line in the text that doesn't, or addr/name
+     * when there is none. Blank lines and lines starting with ; are skipped.
+     * */
+    pub fn parse(&mut self, text: &str) -> io::Result<usize> {
+        let lines = text.lines().map(str::trim).enumerate().filter(|(_, line)| !line.is_empty() && !line.starts_with(';'));
+        let pairs_by_default = lines.clone().find_map(|(_, line)| layout(line)).unwrap_or(Layout::Pairs) == Layout::Pairs;
+
+        let mut count = 0;
+        for (n, line) in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let error = || io::Error::new(io::ErrorKind::InvalidData, format!("line {}: can't read '{}'", n + 1, line));
+
+            let pairs = match layout(line) {
+                Some(layout) => layout == Layout::Pairs,
+                None => pairs_by_default
+            };
+            if pairs {
+                for pair in fields.chunks(2) {
+                    self.insert(pair[1], parse_addr(pair[0]).ok_or_else(error)?);
+                    count += 1;
+                }
+                continue;
+            }
+
+            let fields: Vec<&str> = fields.iter()
+                .map(|f| f.trim_end_matches(':'))
+                .filter(|f| !f.is_empty() && !f.eq_ignore_ascii_case("equ") && *f != "=")
+                .collect();
+            match fields.as_slice() {
+                [name, addr] => {
+                    self.insert(name, parse_addr(addr).ok_or_else(error)?);
+                    count += 1;
+                },
+                _ => return Err(error())
+            }
+        }
+        Ok(count)
+    }
+
+    /**
+     * Adds or moves a symbol
+     * */
+    pub fn insert(&mut self, name: &str, addr: u16) {
+        self.remove(name);
+        self.by_name.insert(name.to_ascii_uppercase(), (name.to_string(), addr));
+        self.by_addr.entry(addr).or_default().push(name.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<u16> {
+        let (name, addr) = self.by_name.remove(&name.to_ascii_uppercase())?;
+        if let Some(names) = self.by_addr.get_mut(&addr) {
+            names.retain(|n| *n != name);
+            if names.is_empty() {
+                self.by_addr.remove(&addr);
+            }
+        }
+        Some(addr)
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<u16> {
+        self.by_name.get(&name.to_ascii_uppercase()).map(|(_, addr)| *addr)
+    }
+
+    /**
+     * First symbol defined at exactly addr
+     * */
+    pub fn name_at(&self, addr: u16) -> Option<&str> {
+        self.by_addr.get(&addr).and_then(|names| names.first()).map(|name| name.as_str())
+    }
+
+    /**
+     * The symbol at or nearest below addr, however far below
+     * */
+    pub fn nearest(&self, addr: u16) -> Option<(u16, &str)> {
+        let (&base, names) = self.by_addr.range(..=addr).next_back()?;
+        names.first().map(|name| (base, name.as_str()))
+    }
+
+    /**
+     * addr as the nearest symbol at or below it, e.g. "PRINT" or "PRINT+$1A". The offset is hex with a $ so the
+     * text also reads back as a debugger expression
+     * */
+    pub fn describe(&self, addr: u16) -> Option<String> {
+        let (base, name) = self.nearest(addr)?;
+        let offset = addr - base;
+        if offset > MAX_OFFSET {
+            return None;
+        }
+        Some(if offset == 0 { name.to_string() } else { format!("{}+${:X}", name, offset) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    /**
+     * Symbols ordered by address
+     * */
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> {
+        self.by_addr.iter().flat_map(|(addr, names)| names.iter().map(move |name| (*addr, name.as_str())))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Layout {
+    /**
+     * addr/name pairs
+     * */
+    Pairs,
+    /**
+     * a name then its address
+     * */
+    NameFirst
+}
+
+/**
+ * How a line of a symbol file is laid out, None when it reads both ways
+ * */
+fn layout(line: &str) -> Option<Layout> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let addr_first = fields[0].len() == 4 && fields[0].chars().all(|c| c.is_ascii_hexdigit());
+    if !addr_first || !fields.len().is_multiple_of(2) {
+        Some(Layout::NameFirst)
+    } else if fields.len() == 2 && parse_addr(fields[1]).is_some() {
+        None
+    } else {
+        Some(Layout::Pairs)
+    }
+}
+
+fn parse_addr(text: &str) -> Option<u16> {
+    parse_number(text).and_then(|addr| u16::try_from(addr).ok())
+}
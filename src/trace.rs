@@ -0,0 +1,378 @@
+use crate::{Cpu8080, ConditionFlag, Reg16};
+use crate::io::IoBus;
+use crate::opcode::OpcodeTable;
+use crate::symbols::SymbolTable;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+/**
+ * Line layout of a trace
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TraceFormat {
+    /**
+     * `0100  C3 AB 01  JMP $01AB        A:00 BC:0000 DE:0000 HL:0000 SP:0000 F:S.A.C CYC:10`,
+     * flags are S Z A P C with a dot for every clear flag
+     * */
+    Native,
+    /**
+     * `PC: 0100, AF: 0002, BC: 0000, DE: 0000, HL: 0000, SP: 0000, CYC: 0` then a tab and `(C3 AB 01 00)`,
+     * the log format of the superzazu/8080 emulator that many other 8080 emulators also produce
+     * */
+    Superzazu
+}
+
+/**
+ * Writes one line per executed instruction to any `Write` sink, showing the state before the instruction runs.
+ * Logging starts when pc reaches the start trigger (immediately without one) and ends once pc reaches the stop trigger.
+ * */
+pub struct Tracer<W: Write> {
+    sink: W,
+    format: TraceFormat,
+    start: Option<u16>,
+    stop: Option<u16>,
+    symbols: Option<SymbolTable>,
+    active: bool,
+    lines: u64
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(sink: W, format: TraceFormat) -> Self {
+        Tracer { sink, format, start: None, stop: None, symbols: None, active: true, lines: 0 }
+    }
+
+    /**
+     * Only starts logging once pc reaches addr
+     * */
+    pub fn start_at(mut self, addr: u16) -> Self {
+        self.start = Some(addr);
+        self.active = false;
+        self
+    }
+
+    /**
+     * Stops logging when pc reaches addr, the instruction at addr is not logged
+     * */
+    pub fn stop_at(mut self, addr: u16) -> Self {
+        self.stop = Some(addr);
+        self
+    }
+
+    /**
+     * Shows 16 bit operands by name in the native format's disassembly
+     * */
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /**
+     * Number of lines written so far
+     * */
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+
+    /**
+     * Logs the instruction at pc if tracing is active. Call it right before executing each instruction
+     * */
+    pub fn trace(&mut self, cpu: &Cpu8080) -> io::Result<()> {
+        if !self.active && self.start == Some(cpu.pc) {
+            self.active = true;
+        }
+        if self.active && self.stop == Some(cpu.pc) {
+            self.active = false;
+            self.start = None;
+        }
+        if !self.active || cpu.halted {
+            return Ok(());
+        }
+
+        match self.format {
+            TraceFormat::Native => self.write_native(cpu)?,
+            TraceFormat::Superzazu => self.write_superzazu(cpu)?
+        }
+        self.lines += 1;
+        Ok(())
+    }
+
+    /**
+     * Traces then executes one instruction
+     * */
+    pub fn step(&mut self, cpu: &mut Cpu8080, io: &mut dyn IoBus) -> io::Result<u32> {
+        self.trace(cpu)?;
+        Ok(cpu.step(io))
+    }
+
+    fn write_native(&mut self, cpu: &Cpu8080) -> io::Result<()> {
+        let (disassembly, size) = match &self.symbols {
+            Some(symbols) => cpu.opcode_table.disassemble_symbolic(&cpu.memory, cpu.pc, symbols),
+            None => cpu.opcode_table.disassemble(&cpu.memory, cpu.pc)
+        };
+        let bytes: Vec<String> = (0..size as u16)
+            .map(|i| format!("{:02X}", cpu.memory[cpu.pc.wrapping_add(i) as usize]))
+            .collect();
+
+        writeln!(self.sink, "{:04X}  {:<8}  {:<16} A:{:02X} BC:{:04X} DE:{:04X} HL:{:04X} SP:{:04X} F:{} CYC:{}",
+            cpu.pc, bytes.join(" "), disassembly, cpu.a,
+            cpu.bc(), cpu.de(), cpu.hl(),
+            cpu.sp, flag_string(cpu), cpu.cycles)
+    }
+
+    fn write_superzazu(&mut self, cpu: &Cpu8080) -> io::Result<()> {
+        let byte = |i: u16| cpu.memory[cpu.pc.wrapping_add(i) as usize];
+        writeln!(self.sink, "PC: {:04X}, AF: {:04X}, BC: {:04X}, DE: {:04X}, HL: {:04X}, SP: {:04X}, CYC: {}\t({:02X} {:02X} {:02X} {:02X})",
+            cpu.pc, cpu.get(Reg16::PSW),
+            cpu.bc(), cpu.de(), cpu.hl(),
+            cpu.sp, cpu.cycles, byte(0), byte(1), byte(2), byte(3))
+    }
+}
+
+/**
+ * S Z A P C, each replaced by a dot when clear
+ * */
+pub fn flag_string(cpu: &Cpu8080) -> String {
+    let flags = [
+        (ConditionFlag::Sign, 'S'), (ConditionFlag::Zero, 'Z'), (ConditionFlag::AuxiliaryCarry, 'A'),
+        (ConditionFlag::Parity, 'P'), (ConditionFlag::Carry, 'C')
+    ];
+    flags.iter().map(|&(flag, c)| if cpu.condition_codes.is_set(flag) { c } else { '.' }).collect()
+}
+
+/**
+ * State of one trace line, parsed back from either format
+ * */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceRecord {
+    pub pc: u16,
+    pub a: u8,
+    /**
+     * Flags in the PSW layout, limited to the five real flags
+     * */
+    pub flags: u8,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub sp: u16,
+    pub cycles: Option<u64>,
+    pub bytes: Vec<u8>,
+    pub disassembly: Option<String>
+}
+
+// S Z AC P CY in the PSW layout
+const PSW_FLAGS: u8 = 0xD5;
+
+/**
+ * Names of the flags in a PSW byte, highest bit first
+ * */
+pub const PSW_FLAG_NAMES: [(u8, &str); 5] = [(0x80, "S"), (0x40, "Z"), (0x10, "AC"), (0x04, "P"), (0x01, "CY")];
+
+impl TraceRecord {
+    /**
+     * Parses a line in either trace format, None if it is neither
+     * */
+    pub fn parse(line: &str) -> Option<Self> {
+        if line.trim_start().starts_with("PC:") {
+            Self::parse_superzazu(line)
+        } else {
+            Self::parse_native(line)
+        }
+    }
+
+    fn parse_native(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        let (pc, rest) = line.split_once("  ")?;
+        let pc = u16::from_str_radix(pc.trim(), 16).ok()?;
+        let (bytes, rest) = rest.trim_start().split_once("  ")?;
+        let bytes = bytes.split_whitespace().map(|b| u8::from_str_radix(b, 16).ok()).collect::<Option<Vec<u8>>>()?;
+        let registers_at = rest.find(" A:")?;
+        let disassembly = rest[..registers_at].trim().to_string();
+
+        let mut record = TraceRecord { pc, a: 0, flags: 0, bc: 0, de: 0, hl: 0, sp: 0, cycles: None, bytes, disassembly: Some(disassembly) };
+        for token in rest[registers_at..].split_whitespace() {
+            let (key, value) = token.split_once(':')?;
+            match key {
+                "A" => record.a = u8::from_str_radix(value, 16).ok()?,
+                "BC" => record.bc = u16::from_str_radix(value, 16).ok()?,
+                "DE" => record.de = u16::from_str_radix(value, 16).ok()?,
+                "HL" => record.hl = u16::from_str_radix(value, 16).ok()?,
+                "SP" => record.sp = u16::from_str_radix(value, 16).ok()?,
+                "CYC" => record.cycles = value.parse().ok(),
+                "F" => {
+                    record.flags = PSW_FLAG_NAMES.iter().zip(value.chars())
+                        .filter(|(_, c)| *c != '.')
+                        .fold(0, |flags, ((bit, _), _)| flags | bit);
+                },
+                _ => {}
+            }
+        }
+        Some(record)
+    }
+
+    fn parse_superzazu(line: &str) -> Option<Self> {
+        let (registers, bytes) = match line.split_once('(') {
+            Some((registers, bytes)) => (registers, bytes.trim_end().trim_end_matches(')')),
+            None => (line, "")
+        };
+        let bytes = bytes.split_whitespace().filter_map(|b| u8::from_str_radix(b, 16).ok()).collect();
+
+        let mut record = TraceRecord { pc: 0, a: 0, flags: 0, bc: 0, de: 0, hl: 0, sp: 0, cycles: None, bytes, disassembly: None };
+        let mut pc = None;
+        for field in registers.split(',') {
+            let (key, value) = field.split_once(':')?;
+            let value = value.trim();
+            match key.trim() {
+                "PC" => pc = u16::from_str_radix(value, 16).ok(),
+                "AF" => {
+                    let af = u16::from_str_radix(value, 16).ok()?;
+                    record.a = (af >> 8) as u8;
+                    record.flags = af as u8 & PSW_FLAGS;
+                },
+                "BC" => record.bc = u16::from_str_radix(value, 16).ok()?,
+                "DE" => record.de = u16::from_str_radix(value, 16).ok()?,
+                "HL" => record.hl = u16::from_str_radix(value, 16).ok()?,
+                "SP" => record.sp = u16::from_str_radix(value, 16).ok()?,
+                "CYC" => record.cycles = value.parse().ok(),
+                _ => {}
+            }
+        }
+        record.pc = pc?;
+        Some(record)
+    }
+}
+
+/**
+ * The records in a trace, skipping lines that aren't trace records. Reads one line at a time
+ * */
+pub fn read_records<R: BufRead>(reader: R) -> impl Iterator<Item = io::Result<TraceRecord>> {
+    reader.lines().filter_map(|line| match line {
+        Ok(line) => TraceRecord::parse(&line).map(Ok),
+        Err(e) => Some(Err(e))
+    })
+}
+
+/**
+ * One line per field that differs between two records, with the flags that differ named
+ * */
+pub fn compare(ours: &TraceRecord, theirs: &TraceRecord, compare_cycles: bool) -> Vec<String> {
+    let mut differences = Vec::new();
+    let fields = [
+        ("PC", ours.pc, theirs.pc, 4), ("A", ours.a as u16, theirs.a as u16, 2),
+        ("BC", ours.bc, theirs.bc, 4), ("DE", ours.de, theirs.de, 4),
+        ("HL", ours.hl, theirs.hl, 4), ("SP", ours.sp, theirs.sp, 4)
+    ];
+    for &(name, a, b, width) in fields.iter() {
+        if a != b {
+            differences.push(format!("{:<6} ours {:0w$X}  theirs {:0w$X}", name, a, b, w = width));
+        }
+    }
+
+    let flags: Vec<String> = PSW_FLAG_NAMES.iter()
+        .filter(|(bit, _)| ours.flags & bit != theirs.flags & bit)
+        .map(|(bit, name)| format!("{} ours {} theirs {}", name, (ours.flags & bit != 0) as u8, (theirs.flags & bit != 0) as u8))
+        .collect();
+    if !flags.is_empty() {
+        differences.push(format!("flags  {}", flags.join(", ")));
+    }
+
+    if let (true, Some(a), Some(b)) = (compare_cycles, ours.cycles, theirs.cycles) {
+        if a != b {
+            differences.push(format!("CYC    ours {}  theirs {}", a, b));
+        }
+    }
+    differences
+}
+
+/**
+ * The record's own disassembly, or one made from its bytes for traces that only have those
+ * */
+pub fn describe_instruction(table: &OpcodeTable, record: &TraceRecord) -> String {
+    match &record.disassembly {
+        Some(text) => text.clone(),
+        None if !record.bytes.is_empty() => table.disassemble(&record.bytes, 0).0,
+        None => String::new()
+    }
+}
+
+/**
+ * A record on one line in the native layout, whichever format it was read from
+ * */
+pub fn describe(table: &OpcodeTable, record: &TraceRecord) -> String {
+    let disassembly = describe_instruction(table, record);
+    let flags: String = PSW_FLAG_NAMES.iter()
+        .map(|(bit, name)| if record.flags & bit != 0 { &name[..1] } else { "." })
+        .collect();
+    let cycles = record.cycles.map(|c| format!(" CYC:{}", c)).unwrap_or_default();
+    format!("{:04X}  {:<16} A:{:02X} BC:{:04X} DE:{:04X} HL:{:04X} SP:{:04X} F:{}{}",
+        record.pc, disassembly, record.a, record.bc, record.de, record.hl, record.sp, flags, cycles)
+}
+
+/**
+ * First instruction where two traces disagree
+ * */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /**
+     * Number of instructions the traces agree on before it
+     * */
+    pub index: u64,
+    /**
+     * Our records leading up to it, oldest first
+     * */
+    pub context: Vec<TraceRecord>,
+    pub ours: TraceRecord,
+    pub theirs: TraceRecord,
+    pub differences: Vec<String>
+}
+
+/**
+ * Outcome of lining two traces up by instruction count
+ * */
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceDiff {
+    /**
+     * Every instruction matched and both traces have this many
+     * */
+    Agree(u64),
+    Diverge(Box<Divergence>),
+    /**
+     * The traces agree on this many instructions, then the shorter one (ours when the flag is set) ends
+     * */
+    Ends { agreed: u64, ours_ended: bool }
+}
+
+/**
+ * Reads both traces together a line at a time, so only the last `context` records are ever held
+ * */
+pub fn diff_traces<A: BufRead, B: BufRead>(ours: A, theirs: B, context: usize, compare_cycles: bool) -> io::Result<TraceDiff> {
+    let mut ours = read_records(ours);
+    let mut theirs = read_records(theirs);
+    let mut recent = VecDeque::with_capacity(context + 1);
+    let mut index = 0;
+    loop {
+        let (a, b) = match (ours.next().transpose()?, theirs.next().transpose()?) {
+            (Some(a), Some(b)) => (a, b),
+            (None, None) => return Ok(TraceDiff::Agree(index)),
+            (a, _) => return Ok(TraceDiff::Ends { agreed: index, ours_ended: a.is_none() })
+        };
+        let differences = compare(&a, &b, compare_cycles);
+        if !differences.is_empty() {
+            return Ok(TraceDiff::Diverge(Box::new(Divergence { index, context: recent.into(), ours: a, theirs: b, differences })));
+        }
+        if context > 0 {
+            if recent.len() == context {
+                recent.pop_front();
+            }
+            recent.push_back(a);
+        }
+        index += 1;
+    }
+}
@@ -0,0 +1,72 @@
+use rustyi8080::console::BufferConsole;
+use rustyi8080::io::IoBus;
+use rustyi8080::machines::altair::*;
+
+#[test]
+fn sio_status_and_data() {
+    let mut bus = AltairBus::new(BufferConsole::new(b"hi"));
+    // active low: a byte waiting and room to send
+    assert_eq!(bus.input(SIO_STATUS), 0);
+    assert_eq!(bus.input(SIO_DATA), b'h');
+    assert_eq!(bus.input(SIO_STATUS), 0);
+    assert_eq!(bus.input(SIO_DATA), b'i');
+    assert_eq!(bus.input(SIO_STATUS), SIO_INPUT_NOT_READY);
+    assert_eq!(bus.input(SIO_DATA), 0, "nothing waiting");
+
+    bus.output(SIO_DATA, b'O' | 0x80);
+    bus.output(SIO_DATA, b'K');
+    assert_eq!(bus.console.output, b"OK", "the terminal gets 7 bits");
+}
+
+#[test]
+fn sio2_status_and_data() {
+    let mut bus = AltairBus::new(BufferConsole::new(b"x"));
+    assert_eq!(bus.input(SIO2_A_STATUS), ACIA_RECEIVE_FULL | ACIA_TRANSMIT_EMPTY);
+    assert_eq!(bus.input(SIO2_A_DATA), b'x');
+    assert_eq!(bus.input(SIO2_A_STATUS), ACIA_TRANSMIT_EMPTY);
+    bus.output(SIO2_A_DATA, b'y');
+    assert_eq!(bus.console.output, b"y");
+
+    // port B has nothing connected, writes to it go nowhere
+    assert_eq!((bus.input(SIO2_B_STATUS), bus.input(SIO2_B_DATA)), (ACIA_TRANSMIT_EMPTY, 0));
+    bus.output(SIO2_B_DATA, b'z');
+    assert_eq!(bus.console.output, b"y");
+
+    bus.output(SIO2_A_STATUS, 0x15);
+    bus.output(SIO2_B_STATUS, 0x11);
+    assert_eq!(bus.acia_control, [0x15, 0x11]);
+}
+
+#[test]
+fn sense_switches() {
+    let mut bus = AltairBus::new(BufferConsole::default());
+    assert_eq!(bus.input(SENSE_SWITCHES), 0);
+    bus.sense_switches = 0xA5;
+    assert_eq!(bus.input(SENSE_SWITCHES), 0xA5);
+    assert_eq!(bus.input(0x42), 0xFF, "nothing answers an empty port");
+}
+
+/**
+ * Echoes the terminal, polling the status port for each byte:
+ *
+ *  0000  IN status   0002  ANI ready   0004  JNZ/JZ $0000   0007  IN data   0009  OUT data   000B  JMP $0000
+ * */
+fn echo(status: u8, data: u8, ready: u8, wait: u8) -> [u8; 14] {
+    [0xDB, status, 0xE6, ready, wait, 0x00, 0x00, 0xDB, data, 0xD3, data, 0xC3, 0x00, 0x00]
+}
+
+#[test]
+fn run_stops_once_input_runs_out() {
+    const JNZ: u8 = 0xC2;
+    const JZ: u8 = 0xCA;
+    for program in [echo(SIO_STATUS, SIO_DATA, SIO_INPUT_NOT_READY, JNZ),
+                    echo(SIO2_A_STATUS, SIO2_A_DATA, ACIA_RECEIVE_FULL, JZ)].iter() {
+        let mut altair = Altair::new(BufferConsole::new(b"PRINT 2+2\r"));
+        altair.load(0x0000, program);
+        altair.run();
+        assert!(altair.cpu.halted);
+        assert!(altair.io.input_ended);
+        assert_eq!(altair.io.console.output, b"PRINT 2+2\r");
+        assert_eq!(altair.cpu.pc, 0x0002, "halted on polling the status port");
+    }
+}
@@ -0,0 +1,197 @@
+use rustyi8080::{Cpu8080, ConditionBitset, ConditionFlag};
+
+/**
+ * Flags as computed by the reference model, straight from the 8080 programmer's manual
+ * */
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Flags {
+    z: bool,
+    s: bool,
+    p: bool,
+    cy: bool,
+    ac: bool
+}
+
+impl Flags {
+    fn of(cpu: &Cpu8080) -> Self {
+        let cc = &cpu.condition_codes;
+        Flags {
+            z: cc.is_set(ConditionFlag::Zero),
+            s: cc.is_set(ConditionFlag::Sign),
+            p: cc.is_set(ConditionFlag::Parity),
+            cy: cc.is_set(ConditionFlag::Carry),
+            ac: cc.is_set(ConditionFlag::AuxiliaryCarry)
+        }
+    }
+
+    /**
+     * Z, S and P of a result, with CY and AC given
+     * */
+    fn result(value: u8, cy: bool, ac: bool) -> Self {
+        Flags { z: value == 0, s: value >= 0x80, p: value.count_ones().is_multiple_of(2), cy, ac }
+    }
+}
+
+/**
+ * Puts A and the carry flags in place, the CPU is reused across cases since building one is slow
+ * */
+fn prepare(cpu: &mut Cpu8080, a: u8, cy: bool, ac: bool) {
+    cpu.a = a;
+    cpu.pc = 0;
+    let mut flags = ConditionBitset::default();
+    flags.assign(ConditionFlag::Carry, cy);
+    flags.assign(ConditionFlag::AuxiliaryCarry, ac);
+    cpu.condition_codes = flags;
+}
+
+// Reference model: each operation written out on plain integers, independent of the emulator's helpers
+
+fn ref_add(a: u8, b: u8, carry_in: bool) -> (u8, Flags) {
+    let c = carry_in as u32;
+    let sum = a as u32 + b as u32 + c;
+    let half = (a as u32 & 0x0F) + (b as u32 & 0x0F) + c;
+    (sum as u8, Flags::result(sum as u8, sum > 0xFF, half > 0x0F))
+}
+
+/**
+ * Subtraction is A + !B + !borrow in, CY is the complemented carry out and AC the plain carry out of bit 3
+ * */
+fn ref_sub(a: u8, b: u8, borrow_in: bool) -> (u8, Flags) {
+    let c = !borrow_in as u32;
+    let sum = a as u32 + (!b) as u32 + c;
+    let half = (a as u32 & 0x0F) + (!b as u32 & 0x0F) + c;
+    (sum as u8, Flags::result(sum as u8, sum <= 0xFF, half > 0x0F))
+}
+
+fn ref_ana(a: u8, b: u8) -> (u8, Flags) {
+    let value = a & b;
+    (value, Flags::result(value, false, (a | b) & 0x08 != 0))
+}
+
+fn ref_xra(a: u8, b: u8) -> (u8, Flags) {
+    let value = a ^ b;
+    (value, Flags::result(value, false, false))
+}
+
+fn ref_ora(a: u8, b: u8) -> (u8, Flags) {
+    let value = a | b;
+    (value, Flags::result(value, false, false))
+}
+
+fn ref_daa(a: u8, cy: bool, ac: bool) -> (u8, Flags) {
+    let mut correction = 0u8;
+    let mut carry = cy;
+    if a & 0x0F > 9 || ac {
+        correction |= 0x06;
+    }
+    if a > 0x99 || cy {
+        correction |= 0x60;
+        carry = true;
+    }
+    let half = (a & 0x0F) + (correction & 0x0F) > 0x0F;
+    let value = a.wrapping_add(correction);
+    (value, Flags::result(value, carry, half))
+}
+
+/**
+ * Sweeps all 256 x 256 operand pairs with carry clear and set, comparing A and all five flags
+ * */
+fn sweep_binary(name: &str, op: fn(&mut Cpu8080, u8), reference: fn(u8, u8, bool) -> (u8, Flags), keeps_a: bool) {
+    let mut cpu = Cpu8080::new();
+    for a in 0..=255u8 {
+        for b in 0..=255u8 {
+            for &cy in [false, true].iter() {
+                prepare(&mut cpu, a, cy, false);
+                op(&mut cpu, b);
+                let (want, flags) = reference(a, b, cy);
+                let want = if keeps_a { a } else { want };
+                assert_eq!(cpu.a, want, "{} A={:02X} B={:02X} CY={}: result", name, a, b, cy);
+                assert_eq!(Flags::of(&cpu), flags, "{} A={:02X} B={:02X} CY={}: flags", name, a, b, cy);
+            }
+        }
+    }
+}
+
+#[test]
+fn add() {
+    sweep_binary("ADD", Cpu8080::add, |a, b, _| ref_add(a, b, false), false);
+}
+
+#[test]
+fn adc() {
+    sweep_binary("ADC", Cpu8080::adc, ref_add, false);
+}
+
+#[test]
+fn sub() {
+    sweep_binary("SUB", Cpu8080::sub, |a, b, _| ref_sub(a, b, false), false);
+}
+
+#[test]
+fn sbb() {
+    sweep_binary("SBB", Cpu8080::sbb, ref_sub, false);
+}
+
+#[test]
+fn cmp() {
+    sweep_binary("CMP", Cpu8080::cmp, |a, b, _| ref_sub(a, b, false), true);
+}
+
+#[test]
+fn ana() {
+    sweep_binary("ANA", Cpu8080::ana, |a, b, _| ref_ana(a, b), false);
+}
+
+#[test]
+fn xra() {
+    sweep_binary("XRA", Cpu8080::xra, |a, b, _| ref_xra(a, b), false);
+}
+
+#[test]
+fn ora() {
+    sweep_binary("ORA", Cpu8080::ora, |a, b, _| ref_ora(a, b), false);
+}
+
+/**
+ * INR and DCR leave CY alone, so both carry states are checked for it being preserved
+ * */
+#[test]
+fn inr_dcr() {
+    let mut cpu = Cpu8080::new();
+    for value in 0..=255u8 {
+        for &cy in [false, true].iter() {
+            prepare(&mut cpu, 0, cy, false);
+            let result = cpu.inr(value);
+            let (want, mut flags) = ref_add(value, 1, false);
+            flags.cy = cy;
+            assert_eq!(result, want, "INR {:02X} CY={}: result", value, cy);
+            assert_eq!(Flags::of(&cpu), flags, "INR {:02X} CY={}: flags", value, cy);
+
+            prepare(&mut cpu, 0, cy, false);
+            let result = cpu.dcr(value);
+            let (want, mut flags) = ref_sub(value, 1, false);
+            flags.cy = cy;
+            assert_eq!(result, want, "DCR {:02X} CY={}: result", value, cy);
+            assert_eq!(Flags::of(&cpu), flags, "DCR {:02X} CY={}: flags", value, cy);
+        }
+    }
+}
+
+/**
+ * DAA goes through the opcode table, covering every accumulator value with each CY/AC combination
+ * */
+#[test]
+fn daa() {
+    let mut cpu = Cpu8080::new();
+    for a in 0..=255u8 {
+        for &cy in [false, true].iter() {
+            for &ac in [false, true].iter() {
+                prepare(&mut cpu, a, cy, ac);
+                cpu.execute(0x27);
+                let (want, flags) = ref_daa(a, cy, ac);
+                assert_eq!(cpu.a, want, "DAA A={:02X} CY={} AC={}: result", a, cy, ac);
+                assert_eq!(Flags::of(&cpu), flags, "DAA A={:02X} CY={} AC={}: flags", a, cy, ac);
+            }
+        }
+    }
+}
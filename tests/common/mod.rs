@@ -0,0 +1,9 @@
+use std::io::{self, Write};
+
+/**
+ * Reports a test that passed without running because its data files aren't there. Goes straight to stderr:
+ * eprintln! output is captured by the test harness and only shown for failing tests
+ * */
+pub fn skipped(reason: std::fmt::Arguments) {
+    let _ = writeln!(io::stderr(), "SKIPPED: {}", reason);
+}
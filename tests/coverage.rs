@@ -0,0 +1,156 @@
+use rustyi8080::{AccessKind, Cpu8080};
+use rustyi8080::coverage::{self, Coverage, OPCODE, OPERAND, READ, WRITE};
+use rustyi8080::debugger::{Debugger, Machine, StopReason, WatchKind};
+use rustyi8080::io::NullBus;
+use rustyi8080::symbols::SymbolTable;
+
+/**
+ *  0100  LDA $0200     010B  MVI B,$05   never executed
+ *  0103  STA $0201     010D  HLT
+ *  0106  ORA A         0200  DB $11
+ *  0107  JZ $010B
+ *  010A  HLT
+ * */
+fn cpu() -> Cpu8080 {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0x0100, &[0x3A, 0x00, 0x02, 0x32, 0x01, 0x02, 0xB7, 0xCA, 0x0B, 0x01, 0x76, 0x06, 0x05, 0x76]);
+    cpu.load(0x0200, &[0x11]);
+    cpu.pc = 0x0100;
+    cpu
+}
+
+fn run(cpu: &mut Cpu8080) -> Coverage {
+    let mut coverage = Coverage::new();
+    while !cpu.halted {
+        coverage.step(cpu, &mut NullBus);
+    }
+    coverage
+}
+
+#[test]
+fn instructions_operands_and_data_are_marked() {
+    let coverage = run(&mut cpu());
+    assert_eq!(coverage.get(0x0100), OPCODE);
+    assert_eq!((coverage.get(0x0101), coverage.get(0x0102)), (OPERAND, OPERAND));
+    assert_eq!((coverage.get(0x0200), coverage.get(0x0201)), (READ, WRITE));
+    assert_eq!(coverage.get(0x010B), 0);
+    assert_eq!((coverage.count(OPCODE), coverage.count(OPERAND), coverage.count(READ | WRITE)), (5, 6, 2));
+
+    assert_eq!(coverage.ranges(), [
+        (0x0100..=0x0100, OPCODE), (0x0101..=0x0102, OPERAND),
+        (0x0103..=0x0103, OPCODE), (0x0104..=0x0105, OPERAND),
+        (0x0106..=0x0107, OPCODE), (0x0108..=0x0109, OPERAND),
+        (0x010A..=0x010A, OPCODE),
+        (0x0200..=0x0200, READ), (0x0201..=0x0201, WRITE)
+    ]);
+    assert_eq!(coverage::bit_string(OPCODE | WRITE), "X..W");
+}
+
+#[test]
+fn maps_merge_and_save() {
+    let first = run(&mut cpu());
+    let mut cpu = cpu();
+    cpu.memory[0x0200] = 0;
+    let second = run(&mut cpu);
+    assert_eq!(second.get(0x010B), OPCODE, "the branch is taken this time");
+    assert_eq!(second.get(0x010A), 0);
+
+    let mut merged = first.clone();
+    merged.merge(&second);
+    assert_eq!((merged.get(0x010A), merged.get(0x010B), merged.get(0x010C), merged.get(0x010D)), (OPCODE, OPCODE, OPERAND, OPCODE));
+    assert_eq!(merged.count(OPCODE), first.count(OPCODE) + 2);
+
+    assert_eq!(Coverage::from_bytes(merged.to_bytes()).unwrap(), merged);
+    assert!(Coverage::from_bytes(&merged.to_bytes()[1..]).is_err());
+}
+
+#[test]
+fn listing_marks_unexecuted_code_data_and_fill() {
+    let mut cpu = cpu();
+    let coverage = run(&mut cpu);
+    let mut symbols = SymbolTable::new();
+    symbols.insert("START", 0x0100);
+    symbols.insert("VALUE", 0x0200);
+    let mut listing = Vec::new();
+    coverage.write_listing(&mut listing, &cpu, &symbols, 0x0100..=0x021F).unwrap();
+    assert_eq!(String::from_utf8(listing).unwrap(), "\
+START:
+0100  3A 00 02  X...    LDA VALUE
+0103  32 01 02  X...    STA $0201
+0106  B7        X...    ORA A
+0107  CA 0B 01  X...    JZ $010B
+010A  76        X...    HLT
+010B  06 05     !       MVI B,$05
+010D  76        !       HLT
+010E-01FF  untouched $00
+VALUE:
+0200  11        ..R.    DB $11
+0201  11        ...W    DB $11
+0202-021F  untouched $00
+");
+}
+
+#[test]
+fn listing_data_runs_and_short_fills() {
+    let mut cpu = Cpu8080::new();
+    for (i, byte) in cpu.memory[0x0300..0x030B].iter_mut().enumerate() {
+        *byte = i as u8 + 1;
+    }
+    let mut map = vec![0; Cpu8080::MEMORY_SIZE];
+    map[0x0300..0x030B].iter_mut().for_each(|bits| *bits = READ);
+    let coverage = Coverage::from_bytes(&map).unwrap();
+    let mut listing = Vec::new();
+    // the data runs 8 to a line; the few zero bytes after it are too short a run to fold, so they list as NOPs
+    coverage.write_listing(&mut listing, &cpu, &SymbolTable::new(), 0x0300..=0x030D).unwrap();
+    assert_eq!(String::from_utf8(listing).unwrap(), "\
+0300  01 02 03  ..R.    DB $01,$02,$03,$04,$05,$06,$07,$08
+0308  09 0A 0B  ..R.    DB $09,$0A,$0B
+030B  00        !       NOP
+030C  00        !       NOP
+030D  00        !       NOP
+");
+}
+
+/**
+ * A CPU that keeps coverage from inside its step, as the monitor does
+ * */
+struct Covered {
+    cpu: Cpu8080,
+    coverage: Coverage
+}
+
+impl Machine for Covered {
+    fn cpu(&self) -> &Cpu8080 {
+        &self.cpu
+    }
+
+    fn cpu_mut(&mut self) -> &mut Cpu8080 {
+        &mut self.cpu
+    }
+
+    fn step(&mut self) -> u32 {
+        self.coverage.begin(&mut self.cpu);
+        let cycles = self.cpu.step(&mut NullBus);
+        self.coverage.end(&self.cpu);
+        cycles
+    }
+}
+
+#[test]
+fn coverage_and_watchpoints_share_the_memory_log() {
+    let mut debugger = Debugger::new(Covered { cpu: cpu(), coverage: Coverage::new() });
+    let id = debugger.add_watchpoint(0x0201..=0x0201, WatchKind::Write);
+    match debugger.run() {
+        StopReason::Watchpoint { id: hit, access } => {
+            assert_eq!((hit, access.addr, access.kind), (id, 0x0201, AccessKind::Write));
+        },
+        reason => panic!("stopped for {:?}", reason)
+    }
+    assert_eq!(debugger.machine.coverage.get(0x0201), WRITE);
+
+    // with the last watchpoint gone the debugger turns the log off, coverage turns it on again
+    debugger.remove(id);
+    assert!(debugger.cpu().memory_log.is_none());
+    assert_eq!(debugger.run(), StopReason::Halted);
+    assert_eq!(debugger.machine.coverage, run(&mut cpu()));
+}
@@ -0,0 +1,93 @@
+use rustyi8080::console::BufferConsole;
+use rustyi8080::cpm::{BiosCall, CpmMachine, MAX_CCP_BASE};
+use rustyi8080::cpm::disk::DiskImage;
+
+const RETURN_ADDR: u16 = 0x1234;
+
+/**
+ * Makes a BIOS call the way a program does, CALLing its jump table entry, and checks it returns to the caller
+ * */
+fn bios_call(machine: &mut CpmMachine<BufferConsole>, call: BiosCall) {
+    machine.cpu.sp = 0x8000;
+    machine.cpu.push_stack(RETURN_ADDR);
+    machine.cpu.pc = machine.bios_base() + call as u16 * 3;
+    assert_eq!(machine.pending_bios_call(), Some(call));
+    machine.step();
+    assert_eq!(machine.cpu.pc, RETURN_ADDR, "{:?} didn't return", call);
+    assert_eq!(machine.cpu.sp, 0x8000);
+}
+
+#[test]
+fn conout_writes_c_to_the_console() {
+    let mut machine = CpmMachine::new(BufferConsole::default());
+    for &c in b"OK\r\n" {
+        machine.cpu.c = c;
+        bios_call(&mut machine, BiosCall::ConOut);
+    }
+    assert_eq!(machine.console.output, b"OK\r\n");
+}
+
+#[test]
+fn seldsk_returns_the_dph_of_mounted_drives_only() {
+    let mut machine = CpmMachine::new(BufferConsole::default());
+    machine.mount(0, DiskImage::blank());
+    machine.mount(2, DiskImage::blank());
+
+    machine.cpu.c = 0;
+    bios_call(&mut machine, BiosCall::SelDsk);
+    let dph_a = machine.cpu.hl();
+    assert_ne!(dph_a, 0);
+
+    machine.cpu.c = 2;
+    bios_call(&mut machine, BiosCall::SelDsk);
+    assert_eq!(machine.cpu.hl(), dph_a + 2 * 16);
+    // the DPB pointer of both DPHs is the shared SSSD DPB, whose first word is 26 sectors per track
+    let dpb = machine.cpu.read_u16(machine.cpu.hl() + 10);
+    assert_eq!(machine.cpu.read_u16(dph_a + 10), dpb);
+    assert_eq!(machine.cpu.read_u16(dpb), 26);
+
+    for &disk in [1u8, 16, 0xFF].iter() {
+        machine.cpu.c = disk;
+        bios_call(&mut machine, BiosCall::SelDsk);
+        assert_eq!(machine.cpu.hl(), 0, "drive {} has no disk", disk);
+    }
+}
+
+#[test]
+fn sectran_uses_the_skew_table() {
+    let mut machine = CpmMachine::new(BufferConsole::default());
+    machine.mount(0, DiskImage::blank());
+    machine.cpu.c = 0;
+    bios_call(&mut machine, BiosCall::SelDsk);
+    let xlt = machine.cpu.read_u16(machine.cpu.hl());
+
+    let mut translated = Vec::new();
+    for sector in 0..26 {
+        machine.cpu.set_bc(sector);
+        machine.cpu.set_de(xlt);
+        bios_call(&mut machine, BiosCall::SecTran);
+        translated.push(machine.cpu.hl());
+    }
+    assert_eq!(&translated[..6], &[1, 7, 13, 19, 25, 5]);
+    let mut sorted = translated.clone();
+    sorted.sort_unstable();
+    assert_eq!(sorted, (1..=26).collect::<Vec<u16>>());
+
+    // no table, sectors are only made 1-based
+    machine.cpu.set_bc(9);
+    machine.cpu.set_de(0);
+    bios_call(&mut machine, BiosCall::SecTran);
+    assert_eq!(machine.cpu.hl(), 10);
+}
+
+#[test]
+fn highest_ccp_base_fits_the_bios() {
+    let machine = CpmMachine::with_ccp_base(BufferConsole::default(), MAX_CCP_BASE);
+    assert_eq!(machine.bios_base(), MAX_CCP_BASE + 0x1600);
+}
+
+#[test]
+#[should_panic(expected = "no room for the BDOS and BIOS")]
+fn ccp_base_too_high_is_refused() {
+    CpmMachine::with_ccp_base(BufferConsole::default(), 0xF000);
+}
@@ -0,0 +1,71 @@
+use rustyi8080::cpm::disk::DiskImage;
+use rustyi8080::io::IoBus;
+use rustyi8080::machines::altair::dcdd::*;
+
+/**
+ * Altair disk whose every byte is made from its track, sector and offset
+ * */
+fn patterned_image() -> DiskImage {
+    let sector_size = ALTAIR_8.sector_size;
+    let data = (0..ALTAIR_8.image_size()).map(|i| {
+        let (sector, byte) = (i / sector_size, i % sector_size);
+        (sector * 7 + byte) as u8
+    }).collect();
+    DiskImage::from_bytes_with(data, ALTAIR_8)
+}
+
+#[test]
+fn select_step_and_read_a_mounted_disk() {
+    let mut dcdd = Dcdd::new();
+    dcdd.mount(0, patterned_image());
+
+    dcdd.output(SELECT, 0);
+    assert_eq!(dcdd.track(), Some(0));
+    let status = dcdd.input(STATUS);
+    assert_eq!(status & STATUS_MOVE_HEAD, 0, "head can move");
+    assert_eq!(status & STATUS_TRACK_0, 0, "at track 0");
+    assert_ne!(status & STATUS_HEAD, 0, "head not loaded yet");
+    assert_eq!(dcdd.input(SECTOR_POSITION), 0xFF, "no sector position with the head unloaded");
+
+    dcdd.output(CONTROL, CONTROL_HEAD_LOAD);
+    dcdd.output(CONTROL, CONTROL_STEP_IN);
+    dcdd.output(CONTROL, CONTROL_STEP_IN);
+    dcdd.output(CONTROL, CONTROL_STEP_OUT);
+    assert_eq!(dcdd.track(), Some(1));
+    let status = dcdd.input(STATUS);
+    assert_eq!(status & (STATUS_HEAD | STATUS_NRDA), 0, "head loaded and data ready");
+    assert_ne!(status & STATUS_TRACK_0, 0, "off track 0");
+
+    // on to the start of sector 3, sector true is low at the start of a sector
+    dcdd.tick(CYCLES_PER_SECTOR * 3);
+    assert_eq!(dcdd.input(SECTOR_POSITION), 0xC0 | (3 << 1));
+    let read: Vec<u8> = (0..ALTAIR_8.sector_size).map(|_| dcdd.input(DATA)).collect();
+    assert_eq!(read.as_slice(), dcdd.image(0).unwrap().read_sector(1, 3).unwrap());
+}
+
+#[test]
+fn empty_drive_stays_deselected() {
+    let mut dcdd = Dcdd::new();
+    dcdd.mount(0, patterned_image());
+
+    dcdd.output(SELECT, 1);
+    assert_eq!(dcdd.track(), None);
+    assert_eq!(dcdd.input(STATUS), 0xFF);
+    dcdd.output(CONTROL, CONTROL_HEAD_LOAD);
+    dcdd.output(CONTROL, CONTROL_STEP_IN);
+    assert_eq!(dcdd.input(STATUS), 0xFF);
+    assert_eq!(dcdd.input(SECTOR_POSITION), 0xFF);
+    assert_eq!(dcdd.input(DATA), 0xFF);
+
+    // the mounted drive wasn't touched by the commands sent while nothing was selected
+    dcdd.output(SELECT, 0);
+    assert_eq!(dcdd.track(), Some(0));
+    assert_ne!(dcdd.input(STATUS) & STATUS_HEAD, 0);
+
+    dcdd.output(SELECT, SELECT_CLEAR);
+    assert_eq!(dcdd.input(STATUS), 0xFF);
+
+    dcdd.output(SELECT, 0);
+    dcdd.unmount(0);
+    assert_eq!(dcdd.input(STATUS), 0xFF, "unmounting deselects the drive");
+}
@@ -0,0 +1,247 @@
+use rustyi8080::{AccessKind, Cpu8080, MemoryAccess};
+use rustyi8080::debugger::{Debugger, PortDirection, StopReason, WatchKind};
+use rustyi8080::debugger::expr::Expr;
+use rustyi8080::debugger::history::History;
+
+const MAIN: u16 = 0x0000;
+const OUTER: u16 = 0x0010;
+const INNER: u16 = 0x0020;
+
+/**
+ * Main calls OUTER, which calls INNER:
+ *
+ *  0000  LXI SP,$1000      0010  CALL $0020      0020  MVI D,$03
+ *  0003  CALL $0010        0013  MVI C,$02       0022  RET
+ *  0006  MVI B,$01         0015  RET
+ *  0008  HLT
+ * */
+fn debugger() -> Debugger<Cpu8080> {
+    let mut cpu = Cpu8080::new();
+    cpu.load(MAIN, &[0x31, 0x00, 0x10, 0xCD, 0x10, 0x00, 0x06, 0x01, 0x76]);
+    cpu.load(OUTER, &[0xCD, 0x20, 0x00, 0x0E, 0x02, 0xC9]);
+    cpu.load(INNER, &[0x16, 0x03, 0xC9]);
+    Debugger::new(cpu)
+}
+
+#[test]
+fn step_over_runs_a_call_to_its_return() {
+    let mut debugger = debugger();
+    assert_eq!(debugger.step_over(), StopReason::Step, "LXI isn't a call, it is a plain step");
+    assert_eq!(debugger.cpu().pc, 0x0003);
+
+    assert_eq!(debugger.step_over(), StopReason::Step);
+    let cpu = debugger.cpu();
+    assert_eq!((cpu.pc, cpu.sp), (0x0006, 0x1000));
+    assert_eq!((cpu.c, cpu.d), (0x02, 0x03), "both subroutines ran");
+}
+
+#[test]
+fn step_over_stops_at_a_breakpoint_inside_the_call() {
+    let mut debugger = debugger();
+    debugger.step();
+    let id = debugger.add_breakpoint(INNER);
+    assert_eq!(debugger.step_over(), StopReason::Breakpoint { id, addr: INNER });
+    assert_eq!(debugger.cpu().sp, 0x0FFC);
+}
+
+#[test]
+fn step_out_skips_nested_returns() {
+    let mut debugger = debugger();
+    debugger.step();
+    debugger.step();
+    assert_eq!(debugger.cpu().pc, OUTER);
+
+    assert_eq!(debugger.step_out(), StopReason::Step);
+    let cpu = debugger.cpu();
+    assert_eq!((cpu.pc, cpu.sp), (0x0006, 0x1000));
+    assert_eq!(cpu.d, 0x03, "the nested call ran to its return first");
+}
+
+#[test]
+fn step_out_with_the_stack_at_the_top_of_memory() {
+    let mut debugger = debugger();
+    let cpu = debugger.cpu_mut();
+    // inside INNER, called with the stack right at the top: its RET wraps SP round to 0000
+    cpu.sp = 0xFFFE;
+    cpu.write_u16(0xFFFE, 0x0008);
+    cpu.pc = INNER;
+
+    assert_eq!(debugger.step_out(), StopReason::Step);
+    let cpu = debugger.cpu();
+    assert_eq!((cpu.pc, cpu.sp), (0x0008, 0x0000));
+}
+
+#[test]
+fn run_to_stops_at_the_address_and_removes_its_breakpoint() {
+    let mut debugger = debugger();
+    assert_eq!(debugger.run_to(0x0013), StopReason::Temporary { addr: 0x0013 });
+    assert!(debugger.points().is_empty());
+    assert_eq!(debugger.run(), StopReason::Halted);
+}
+
+#[test]
+fn reverse_continue_goes_back_to_the_previous_breakpoint() {
+    let mut debugger = debugger();
+    debugger.enable_history(History::default());
+    let id = debugger.add_breakpoint(INNER);
+    assert_eq!(debugger.run(), StopReason::Breakpoint { id, addr: INNER });
+    assert_eq!(debugger.run(), StopReason::Halted);
+
+    assert_eq!(debugger.reverse_continue(), StopReason::Breakpoint { id, addr: INNER });
+    let cpu = debugger.cpu();
+    assert_eq!((cpu.pc, cpu.sp), (INNER, 0x0FFC));
+    assert_eq!((cpu.b, cpu.c, cpu.d), (0, 0, 0), "everything after the breakpoint undone");
+
+    assert_eq!(debugger.reverse_continue(), StopReason::HistoryStart);
+    assert_eq!((debugger.cpu().pc, debugger.cpu().sp), (MAIN, 0));
+}
+
+/**
+ * Touches memory every way the request lists, then does I/O:
+ *
+ *  0000  LXI SP,$3000      000A  LXI B,$2001       0013  IN $10
+ *  0003  MVI A,$42         000D  STAX B            0015  OUT $11
+ *  0005  LXI H,$2000       000E  LDAX B            0017  HLT
+ *  0008  MOV M,A           000F  PUSH B
+ *  0009  MOV E,M           0010  CALL $0020        0020  RET
+ * */
+fn accesses() -> Debugger<Cpu8080> {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0x0000, &[0x31, 0x00, 0x30, 0x3E, 0x42, 0x21, 0x00, 0x20, 0x77, 0x5E, 0x01, 0x01, 0x20, 0x02, 0x0A,
+        0xC5, 0xCD, 0x20, 0x00, 0xDB, 0x10, 0xD3, 0x11, 0x76]);
+    cpu.load(0x0020, &[0xC9]);
+    Debugger::new(cpu)
+}
+
+/**
+ * Runs to the first stop of a single watchpoint, returning the access and pc after the instruction that made it
+ * */
+fn watch(range: std::ops::RangeInclusive<u16>, kind: WatchKind) -> Option<(MemoryAccess, u16)> {
+    let mut debugger = accesses();
+    let id = debugger.add_watchpoint(range, kind);
+    match debugger.run() {
+        StopReason::Watchpoint { id: hit, access } if hit == id => Some((access, debugger.cpu().pc)),
+        StopReason::Halted => None,
+        reason => panic!("stopped for {:?}", reason)
+    }
+}
+
+fn access(addr: u16, value: u8, kind: AccessKind) -> MemoryAccess {
+    MemoryAccess { addr, value, kind }
+}
+
+#[test]
+fn watchpoints_catch_m_operands() {
+    assert_eq!(watch(0x2000..=0x2000, WatchKind::Write), Some((access(0x2000, 0x42, AccessKind::Write), 0x0009)));
+    assert_eq!(watch(0x2000..=0x2000, WatchKind::Read), Some((access(0x2000, 0x42, AccessKind::Read), 0x000A)));
+
+    let mut debugger = accesses();
+    let id = debugger.add_watchpoint(0x2000..=0x2000, WatchKind::Access);
+    assert!(matches!(debugger.run(), StopReason::Watchpoint { id: hit, access } if hit == id && access.kind == AccessKind::Write));
+    assert!(matches!(debugger.run(), StopReason::Watchpoint { id: hit, access } if hit == id && access.kind == AccessKind::Read));
+    assert_eq!(debugger.run(), StopReason::Halted);
+    assert_eq!(debugger.point(id).unwrap().hits, 2);
+}
+
+#[test]
+fn watchpoints_catch_stax_and_ldax() {
+    assert_eq!(watch(0x2001..=0x2001, WatchKind::Write), Some((access(0x2001, 0x42, AccessKind::Write), 0x000E)));
+    assert_eq!(watch(0x2001..=0x2001, WatchKind::Read), Some((access(0x2001, 0x42, AccessKind::Read), 0x000F)));
+}
+
+#[test]
+fn watchpoints_catch_the_stack() {
+    // PUSH B puts 2001 at 2FFE, CALL its return address at 2FFC, which RET reads back
+    let (pushed, pc) = watch(0x2FFE..=0x2FFF, WatchKind::Write).unwrap();
+    assert_eq!((pushed.kind, pc), (AccessKind::Write, 0x0010));
+    assert!([access(0x2FFF, 0x20, AccessKind::Write), access(0x2FFE, 0x01, AccessKind::Write)].contains(&pushed));
+    let (called, pc) = watch(0x2FFC..=0x2FFD, WatchKind::Write).unwrap();
+    assert_eq!((called.kind, pc), (AccessKind::Write, 0x0020));
+    assert!([access(0x2FFD, 0x00, AccessKind::Write), access(0x2FFC, 0x13, AccessKind::Write)].contains(&called));
+    let (returned, pc) = watch(0x2FFC..=0x2FFD, WatchKind::Read).unwrap();
+    assert_eq!((returned.kind, pc), (AccessKind::Read, 0x0013));
+    assert_eq!(watch(0x2FFE..=0x2FFF, WatchKind::Read), None, "nothing pops B");
+}
+
+#[test]
+fn watchpoint_ranges_include_both_ends() {
+    assert_eq!(watch(0x1F00..=0x2000, WatchKind::Write).map(|(access, _)| access.addr), Some(0x2000));
+    assert_eq!(watch(0x2001..=0x2FFB, WatchKind::Access).map(|(access, _)| access.addr), Some(0x2001));
+    assert_eq!(watch(0x2002..=0x2FFB, WatchKind::Access), None);
+    assert_eq!(watch(0xFFFF..=0xFFFF, WatchKind::Access), None);
+}
+
+#[test]
+fn port_breakpoints_stop_after_in_and_out() {
+    let mut debugger = accesses();
+    let input = debugger.add_port_breakpoint(0x10, PortDirection::In);
+    let output = debugger.add_port_breakpoint(0x11, PortDirection::Out);
+    // the wrong direction on either port never stops
+    debugger.add_port_breakpoint(0x10, PortDirection::Out);
+    debugger.add_port_breakpoint(0x11, PortDirection::In);
+    // nothing is on the bus, IN reads FF and OUT sends it straight back
+    assert_eq!(debugger.run(), StopReason::Port { id: input, port: 0x10, value: 0xFF, direction: PortDirection::In });
+    assert_eq!(debugger.cpu().pc, 0x0015);
+    assert_eq!(debugger.run(), StopReason::Port { id: output, port: 0x11, value: 0xFF, direction: PortDirection::Out });
+    assert_eq!(debugger.cpu().pc, 0x0017);
+    assert_eq!(debugger.run(), StopReason::Halted);
+
+    let mut debugger = accesses();
+    let both = debugger.add_port_breakpoint(0x11, PortDirection::Both);
+    assert!(matches!(debugger.run(), StopReason::Port { id, direction: PortDirection::Out, .. } if id == both));
+}
+
+/**
+ * Counts B down from 5:
+ *
+ *  0000  MVI B,$05     0002  DCR B     0003  JNZ $0002     0006  HLT
+ * */
+fn countdown() -> Debugger<Cpu8080> {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0x0000, &[0x06, 0x05, 0x05, 0xC2, 0x02, 0x00, 0x76]);
+    Debugger::new(cpu)
+}
+
+#[test]
+fn conditional_breakpoints_stop_only_when_true() {
+    let mut debugger = countdown();
+    let id = debugger.add_conditional_breakpoint(0x0002, Expr::parse("B == 2").unwrap());
+    assert_eq!(debugger.run(), StopReason::Breakpoint { id, addr: 0x0002 });
+    assert_eq!(debugger.cpu().b, 2);
+    assert_eq!(debugger.run(), StopReason::Halted);
+    assert_eq!(debugger.point(id).unwrap().hits, 1, "a false condition isn't a hit");
+
+    let mut debugger = countdown();
+    let never = debugger.add_conditional_breakpoint(0x0002, Expr::parse("B == 7 || Z").unwrap());
+    assert_eq!(debugger.run(), StopReason::Halted);
+    assert_eq!(debugger.point(never).unwrap().hits, 0);
+}
+
+#[test]
+fn ignore_counts_skip_the_first_hits() {
+    let mut debugger = countdown();
+    let id = debugger.add_breakpoint(0x0003);
+    debugger.point_mut(id).unwrap().ignore_count = 2;
+    assert_eq!(debugger.run(), StopReason::Breakpoint { id, addr: 0x0003 });
+    assert_eq!((debugger.cpu().b, debugger.point(id).unwrap().hits), (2, 3));
+    assert_eq!(debugger.run(), StopReason::Breakpoint { id, addr: 0x0003 });
+    assert_eq!(debugger.run(), StopReason::Breakpoint { id, addr: 0x0003 });
+    assert_eq!(debugger.cpu().b, 0);
+    assert_eq!(debugger.run(), StopReason::Halted);
+    assert_eq!(debugger.point(id).unwrap().hits, 5);
+
+    // the same for watchpoints, and a disabled point counts nothing
+    let mut debugger = accesses();
+    let id = debugger.add_watchpoint(0x2000..=0x2000, WatchKind::Access);
+    debugger.point_mut(id).unwrap().ignore_count = 1;
+    let disabled = debugger.add_port_breakpoint(0x10, PortDirection::Both);
+    debugger.point_mut(disabled).unwrap().enabled = false;
+    assert!(matches!(debugger.run(), StopReason::Watchpoint { access, .. } if access.kind == AccessKind::Read));
+    assert_eq!(debugger.run(), StopReason::Halted);
+    assert_eq!((debugger.point(id).unwrap().hits, debugger.point(disabled).unwrap().hits), (2, 0));
+
+    // the memory log is only kept while a watchpoint needs it
+    assert!(debugger.cpu().memory_log.is_some());
+    debugger.remove(id);
+    assert!(debugger.cpu().memory_log.is_none());
+}
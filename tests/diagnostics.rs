@@ -0,0 +1,183 @@
+mod common;
+
+use rustyi8080::Cpu8080;
+use rustyi8080::io::NullBus;
+use std::fs;
+use std::path::PathBuf;
+
+/**
+ * CP/M diagnostic programs (cpudiag, TST8080, 8080PRE, 8080EXM) are not redistributed with the crate.
+ * Put them in tests/roms/ or point I8080_ROMS at a directory holding them; a test whose ROM is missing passes
+ * after printing a SKIPPED line.
+ * */
+const ROMS_ENV: &str = "I8080_ROMS";
+
+const BDOS: u16 = 0x0005;
+const TPA: u16 = 0x0100;
+
+fn rom_dir() -> PathBuf {
+    match std::env::var_os(ROMS_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("roms")
+    }
+}
+
+/**
+ * Finds a ROM by any of its usual names, ignoring case
+ * */
+fn find_rom(names: &[&str]) -> Option<Vec<u8>> {
+    let entries = fs::read_dir(rom_dir()).ok()?;
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        if names.iter().any(|name| name.to_lowercase() == file_name) {
+            return fs::read(entry.path()).ok();
+        }
+    }
+    None
+}
+
+/**
+ * Runs a .COM program at 0x100 with just enough of a BDOS for console output: C=2 prints E, C=9 prints the
+ * $ terminated string at DE. A jump to 0 (warm boot) ends the run. Returns everything printed.
+ * */
+fn run_com(program: &[u8], max_cycles: u64) -> String {
+    let mut cpu = Cpu8080::new();
+    cpu.load(TPA, program);
+    // BDOS entry returns straight away, the call itself is handled below
+    cpu.memory[BDOS as usize] = 0xC9;
+    cpu.sp = 0xF000;
+    cpu.pc = TPA;
+
+    let mut output = String::new();
+    while cpu.cycles < max_cycles {
+        match cpu.pc {
+            0x0000 => return output,
+            BDOS => match cpu.c {
+                2 => output.push(cpu.e as char),
+                9 => {
+                    let mut addr = cpu.de();
+                    while cpu.memory[addr as usize] != b'$' {
+                        output.push(cpu.memory[addr as usize] as char);
+                        addr = addr.wrapping_add(1);
+                    }
+                },
+                _ => {}
+            },
+            _ => {}
+        }
+        if cpu.halted {
+            panic!("CPU halted at {:04X}, output so far:\n{}", cpu.pc, output);
+        }
+        cpu.step(&mut NullBus);
+    }
+    panic!("no warm boot within {} cycles, output so far:\n{}", max_cycles, output);
+}
+
+/**
+ * Runs a ROM if it is present, None when the test should be skipped
+ * */
+fn run_rom(names: &[&str], max_cycles: u64) -> Option<String> {
+    match find_rom(names) {
+        Some(program) => {
+            let output = run_com(&program, max_cycles);
+            println!("{}", output);
+            Some(output)
+        },
+        None => {
+            common::skipped(format_args!("none of {:?} found in {} (set {})", names, rom_dir().display(), ROMS_ENV));
+            None
+        }
+    }
+}
+
+/**
+ * Group names of every failing test in 8080EXM style output, where each line looks like
+ * `<group>....  PASS! crc is:xxxxxxxx` or `<group>....  ERROR **** crc expected:xxxxxxxx found:xxxxxxxx`
+ * */
+fn failed_groups(output: &str) -> Vec<String> {
+    output.lines()
+        .filter(|line| line.contains("ERROR"))
+        .map(|line| line.split("....").next().unwrap_or(line).trim().to_string())
+        .collect()
+}
+
+/**
+ * Verdict of cpudiag and TST8080, which end with one of two messages
+ * */
+fn operational(output: &str) -> Result<(), &'static str> {
+    if output.contains("CPU HAS FAILED") {
+        Err("failed")
+    } else if !output.contains("CPU IS OPERATIONAL") {
+        Err("did not pass")
+    } else {
+        Ok(())
+    }
+}
+
+/**
+ * A .COM program that prints message with BDOS 9 and one character with BDOS 2, then warm boots:
+ *
+ *  0100  MVI C,$09     0105  CALL $0005    010A  MVI E,'!'     010F  JMP $0000
+ *  0102  LXI D,$0112   0108  MVI C,$02     010C  CALL $0005    0112  message
+ * */
+fn printing(message: &str) -> Vec<u8> {
+    let mut program = vec![0x0E, 0x09, 0x11, 0x12, 0x01, 0xCD, 0x05, 0x00, 0x0E, 0x02, 0x1E, b'!',
+        0xCD, 0x05, 0x00, 0xC3, 0x00, 0x00];
+    program.extend_from_slice(message.as_bytes());
+    program.push(b'$');
+    program
+}
+
+#[test]
+fn harness_runs_a_com_program() {
+    let output = run_com(&printing("\r\n CPU IS OPERATIONAL"), 10_000);
+    assert_eq!(output, "\r\n CPU IS OPERATIONAL!");
+    assert_eq!(operational(&output), Ok(()));
+
+    let output = run_com(&printing("\r\n CPU HAS FAILED! ERROR EXIT="), 10_000);
+    assert_eq!(operational(&output), Err("failed"));
+    assert_eq!(operational(&run_com(&printing("ABORTED"), 10_000)), Err("did not pass"));
+
+    let output = run_com(&printing("dad <b,d,h,sp>................  ERROR **** crc expected:14474ba6 found:14474ba7\r\n\
+        aluop nn......................  PASS! crc is:9e922f9e\r\n"), 10_000);
+    assert_eq!(failed_groups(&output), ["dad <b,d,h,sp>"]);
+}
+
+#[test]
+fn cpudiag() {
+    if let Some(output) = run_rom(&["cpudiag.bin", "cpudiag.com"], 10_000_000) {
+        if let Err(verdict) = operational(&output) {
+            panic!("cpudiag {}:\n{}", verdict, output);
+        }
+    }
+}
+
+#[test]
+fn tst8080() {
+    if let Some(output) = run_rom(&["TST8080.COM"], 10_000_000) {
+        if let Err(verdict) = operational(&output) {
+            panic!("TST8080 {}:\n{}", verdict, output);
+        }
+    }
+}
+
+#[test]
+fn prelim_8080() {
+    if let Some(output) = run_rom(&["8080PRE.COM"], 10_000_000) {
+        assert!(!output.contains("ERROR") && !output.contains("Error"), "8080PRE failed:\n{}", output);
+        assert!(output.contains("Preliminary tests complete"), "8080PRE did not complete:\n{}", output);
+    }
+}
+
+/**
+ * Takes around 23 billion cycles, run with `cargo test --release -- --ignored`
+ * */
+#[test]
+#[ignore]
+fn exerciser_8080() {
+    if let Some(output) = run_rom(&["8080EXM.COM"], 50_000_000_000) {
+        let failed = failed_groups(&output);
+        assert!(failed.is_empty(), "8080EXM CRC mismatch in: {}", failed.join(", "));
+        assert!(output.contains("Tests complete"), "8080EXM did not complete:\n{}", output);
+    }
+}
@@ -0,0 +1,121 @@
+use proptest::prelude::*;
+use rustyi8080::{Cpu8080, ConditionBitset, reference};
+use rustyi8080::io::IoBus;
+
+/**
+ * Random starting state: registers, flags, an instruction stream placed at pc and a seed for the rest of memory
+ * */
+#[derive(Clone, Debug)]
+struct State {
+    registers: [u8; 7],
+    psw: u8,
+    sp: u16,
+    pc: u16,
+    code: Vec<u8>,
+    memory_seed: u64,
+    steps: usize
+}
+
+fn state() -> impl Strategy<Value = State> {
+    (any::<[u8; 7]>(), any::<u8>(), any::<u16>(), any::<u16>(), prop::collection::vec(any::<u8>(), 1..48), any::<u64>(), 1..32usize)
+        .prop_map(|(registers, psw, sp, pc, code, memory_seed, steps)| State { registers, psw, sp, pc, code, memory_seed, steps })
+}
+
+/**
+ * Ports answer with a value derived from the port number and every OUT is recorded
+ * */
+#[derive(Default)]
+struct RecordingBus {
+    outputs: Vec<(u8, u8)>
+}
+
+impl IoBus for RecordingBus {
+    fn input(&mut self, port: u8) -> u8 {
+        port.rotate_left(3) ^ 0xA5
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.outputs.push((port, value));
+    }
+}
+
+fn build(state: &State) -> Cpu8080 {
+    let mut cpu = Cpu8080::new();
+
+    // xorshift fill so memory reads and stray jumps see varied but reproducible data
+    let mut x = state.memory_seed | 1;
+    for byte in cpu.memory.iter_mut() {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        *byte = x as u8;
+    }
+    cpu.load(state.pc, &state.code);
+
+    let [a, b, c, d, e, h, l] = state.registers;
+    cpu.a = a;
+    cpu.b = b;
+    cpu.c = c;
+    cpu.d = d;
+    cpu.e = e;
+    cpu.h = h;
+    cpu.l = l;
+    cpu.sp = state.sp;
+    cpu.pc = state.pc;
+    cpu.condition_codes = ConditionBitset::from_psw(state.psw);
+    cpu
+}
+
+/**
+ * Describes the first difference between the two CPUs, if any
+ * */
+fn divergence(cpu: &Cpu8080, model: &Cpu8080) -> Option<String> {
+    let registers = [
+        ("pc", cpu.pc, model.pc), ("sp", cpu.sp, model.sp),
+        ("a", cpu.a as u16, model.a as u16), ("psw", cpu.condition_codes.to_psw() as u16, model.condition_codes.to_psw() as u16),
+        ("b", cpu.b as u16, model.b as u16), ("c", cpu.c as u16, model.c as u16),
+        ("d", cpu.d as u16, model.d as u16), ("e", cpu.e as u16, model.e as u16),
+        ("h", cpu.h as u16, model.h as u16), ("l", cpu.l as u16, model.l as u16)
+    ];
+    for (name, got, want) in registers.iter() {
+        if got != want {
+            return Some(format!("{}: cpu {:04X}, reference {:04X}", name, got, want));
+        }
+    }
+    if cpu.cycles != model.cycles {
+        return Some(format!("cycles: cpu {}, reference {}", cpu.cycles, model.cycles));
+    }
+    if cpu.halted != model.halted || cpu.interrupts_enabled != model.interrupts_enabled {
+        return Some(format!("halted/inte: cpu {}/{}, reference {}/{}",
+            cpu.halted, cpu.interrupts_enabled, model.halted, model.interrupts_enabled));
+    }
+    if let Some(addr) = (0..cpu.memory.len()).find(|&i| cpu.memory[i] != model.memory[i]) {
+        return Some(format!("memory [{:04X}]: cpu {:02X}, reference {:02X}", addr, cpu.memory[addr], model.memory[addr]));
+    }
+    None
+}
+
+proptest! {
+    /**
+     * Runs the same random program on Cpu8080 and the reference interpreter, checking the full state after every
+     * instruction. proptest shrinks a failure down to a minimal program and state.
+     * */
+    #[test]
+    fn matches_reference(state in state()) {
+        let mut cpu = build(&state);
+        let mut model = build(&state);
+        let (mut cpu_bus, mut model_bus) = (RecordingBus::default(), RecordingBus::default());
+
+        for step in 0..state.steps {
+            let pc = cpu.pc;
+            let opcode = cpu.memory[pc as usize];
+            cpu.step(&mut cpu_bus);
+            reference::step(&mut model, &mut model_bus);
+
+            if let Some(difference) = divergence(&cpu, &model) {
+                prop_assert!(false, "step {} opcode {:02X} at {:04X}: {}", step, opcode, pc, difference);
+            }
+            prop_assert_eq!(&cpu_bus.outputs, &model_bus.outputs, "step {} opcode {:02X} at {:04X}: port output", step, opcode, pc);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use rustyi8080::{Cpu8080, ConditionFlag};
+use rustyi8080::debugger::expr::{BinaryOp, Expr, Operand, parse_number};
+
+fn eval(text: &str, cpu: &Cpu8080) -> u32 {
+    Expr::parse(text).unwrap_or_else(|e| panic!("{}: {}", text, e)).eval(cpu)
+}
+
+#[test]
+fn numbers_are_hex_unless_prefixed_with_hash() {
+    for &(text, value) in [("10", 0x10), ("#10", 10), ("$FF", 0xFF), ("0x1F", 0x1F), ("0FFh", 0xFF), ("#65535", 0xFFFF)].iter() {
+        assert_eq!(parse_number(text), Some(value), "{}", text);
+    }
+    assert_eq!(parse_number("#1A"), None);
+    assert_eq!(parse_number("G"), None);
+}
+
+#[test]
+fn operators_bind_from_loosest_to_tightest() {
+    let cpu = Cpu8080::new();
+    // || looser than &&
+    assert_eq!(eval("1 || 0 && 0", &cpu), 1);
+    // && looser than comparisons
+    assert_eq!(eval("2 == 2 && 3 == 3", &cpu), 1);
+    // comparisons looser than & and |
+    assert_eq!(eval("4 | 1 == 5", &cpu), 1);
+    // & and | looser than + and -
+    assert_eq!(eval("2 + 3 & 1", &cpu), 1);
+    // ! tightest
+    assert_eq!(eval("!0 + 1", &cpu), 2);
+    // left associative
+    assert_eq!(eval("#10 - 3 - 2", &cpu), 5);
+
+    let expr = Expr::parse("B == 2 && [HL] != #10").unwrap();
+    let Expr::Binary(BinaryOp::And, left, right) = expr else { panic!("&& isn't at the top") };
+    assert_eq!(*left, Expr::Binary(BinaryOp::Eq, Box::new(Expr::Operand(Operand::B)), Box::new(Expr::Number(2))));
+    assert_eq!(*right, Expr::Binary(BinaryOp::Ne,
+        Box::new(Expr::Memory(Box::new(Expr::Operand(Operand::HL)))), Box::new(Expr::Number(10))));
+}
+
+#[test]
+fn registers_flags_and_memory() {
+    let mut cpu = Cpu8080::new();
+    cpu.set_hl(0x2000);
+    cpu.b = 2;
+    cpu.sp = 0xF000;
+    cpu.memory[0x2000] = 0x0A;
+    cpu.memory[0x2001] = 0x30;
+    cpu.memory[0x300A] = 0x77;
+    cpu.condition_codes.set(ConditionFlag::Zero);
+
+    assert_eq!(eval("B == 2 && [HL] != #10", &cpu), 0);
+    assert_eq!(eval("B == 2 && [HL] == #10", &cpu), 1);
+    assert_eq!(eval("[HL + 1]", &cpu), 0x30);
+    assert_eq!(eval("[[HL] + 3000]", &cpu), 0x77);
+    assert_eq!(eval("h", &cpu), 0x20);
+    assert_eq!(eval("SP - 1", &cpu), 0xEFFF);
+    assert_eq!(eval("Z && !CY", &cpu), 1);
+}
+
+#[test]
+fn and_or_short_circuit() {
+    let mut cpu = Cpu8080::new();
+    // any memory read beyond this panics, so the right hand sides below must never be evaluated
+    cpu.memory.truncate(0x100);
+    assert_eq!(eval("0 && [8000]", &cpu), 0);
+    assert_eq!(eval("1 || [8000]", &cpu), 1);
+    assert_eq!(eval("5 && 7", &cpu), 1, "&& and || give 0 or 1");
+}
+
+#[test]
+fn symbols_resolve_after_registers() {
+    let symbol = |name: &str| match name {
+        "BUFFER" => Some(0x2000),
+        "B" => Some(0x1234),
+        _ => None
+    };
+    let mut cpu = Cpu8080::new();
+    cpu.b = 7;
+    cpu.memory[0x2000] = 0x42;
+    assert_eq!(Expr::parse_with("[BUFFER]", &symbol).unwrap().eval(&cpu), 0x42);
+    assert_eq!(Expr::parse_with("B", &symbol).unwrap().eval(&cpu), 7);
+    assert!(Expr::parse("BUFFER").is_err());
+}
+
+#[test]
+fn bad_expressions_are_errors() {
+    for text in ["", "B ==", "[HL", "(1)", "1 2", "#1A", "FOO", "B = 2"].iter() {
+        assert!(Expr::parse(text).is_err(), "'{}' parsed", text);
+    }
+}
@@ -0,0 +1,172 @@
+use rustyi8080::{Cpu8080, ConditionFlag};
+use rustyi8080::gdbstub::{GdbStub, Transport};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+/**
+ * In-memory client connection: input is everything the client sends, output collects the replies
+ * */
+struct Pipe {
+    input: VecDeque<u8>,
+    output: Vec<u8>
+}
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match (buf.first_mut(), self.input.pop_front()) {
+            (Some(first), Some(byte)) => {
+                *first = byte;
+                Ok(1)
+            },
+            _ => Ok(0)
+        }
+    }
+}
+
+impl Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for Pipe {
+    fn poll_byte(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.input.pop_front())
+    }
+}
+
+fn packet(data: &str) -> String {
+    let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+    format!("${}#{:02x}", data, checksum)
+}
+
+/**
+ * Runs a session over the given client bytes, returning what the stub wrote back
+ * */
+fn exchange(stub: &mut GdbStub<Cpu8080>, input: &[u8]) -> String {
+    let mut pipe = Pipe { input: input.iter().copied().collect(), output: Vec::new() };
+    stub.session_on(&mut pipe).unwrap();
+    String::from_utf8(pipe.output).unwrap()
+}
+
+/**
+ * Sends each packet in turn, returning the replies without the acks
+ * */
+fn replies(stub: &mut GdbStub<Cpu8080>, packets: &[&str]) -> Vec<String> {
+    let input: String = packets.iter().map(|p| packet(p)).collect();
+    let output = exchange(stub, input.as_bytes());
+    output.split('$').skip(1).map(|reply| reply.split('#').next().unwrap().to_string()).collect()
+}
+
+#[test]
+fn packets_are_acked_and_checksummed() {
+    let mut stub = GdbStub::new(Cpu8080::new());
+    let output = exchange(&mut stub, format!("+{}", packet("?")).as_bytes());
+    assert_eq!(output, "+$S05#b8");
+
+    // a wrong checksum is nacked, the retransmission answered
+    let output = exchange(&mut stub, format!("$?#00{}", packet("?")).as_bytes());
+    assert_eq!(output, "-+$S05#b8");
+
+    // no acks either way once the client turns them off
+    let output = exchange(&mut stub, format!("{}{}", packet("QStartNoAckMode"), packet("?")).as_bytes());
+    assert_eq!(output, format!("+{}{}", packet("OK"), packet("S05")));
+}
+
+#[test]
+fn escaped_bytes_are_unescaped() {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0x0100, &[0xAB, 0xCD]);
+    let mut stub = GdbStub::new(cpu);
+    // m0100,2 with the 0 escaped as }, 0x10; the checksum covers the escape as sent
+    let data = b"m}\x10100,2";
+    let checksum = data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    let mut input = vec![b'$'];
+    input.extend_from_slice(data);
+    input.extend_from_slice(format!("#{:02x}", checksum).as_bytes());
+    assert_eq!(exchange(&mut stub, &input), format!("+{}", packet("abcd")));
+}
+
+#[test]
+fn registers_are_hex_with_16_bit_registers_little_endian() {
+    let mut cpu = Cpu8080::new();
+    cpu.a = 0x12;
+    cpu.set_bc(0x3456);
+    cpu.set_de(0x789A);
+    cpu.set_hl(0xBCDE);
+    cpu.sp = 0xF000;
+    cpu.pc = 0x0102;
+    cpu.condition_codes.set(ConditionFlag::Carry);
+    let psw = cpu.condition_codes.to_psw();
+    let mut stub = GdbStub::new(cpu);
+
+    let expected = format!("12{:02x}3456789abcde00f00201", psw);
+    assert_eq!(replies(&mut stub, &["g", "p8", "p9", "p1", "pa"]), [expected, "00f0".into(), "0201".into(), format!("{:02x}", psw), "E01".into()]);
+
+    assert_eq!(replies(&mut stub, &["G01c3020304050607341200ff", "g"]), ["OK", "01c3020304050607341200ff"]);
+    let cpu = stub.debugger.cpu();
+    assert_eq!((cpu.a, cpu.bc(), cpu.de(), cpu.hl(), cpu.sp, cpu.pc), (0x01, 0x0203, 0x0405, 0x0607, 0x1234, 0xFF00));
+    assert!(cpu.condition_codes.is_set(ConditionFlag::Sign) && cpu.condition_codes.is_set(ConditionFlag::Carry));
+
+    assert_eq!(replies(&mut stub, &["P8=3412", "P0=aa", "P9=12", "G00"]), ["OK", "OK", "E01", "E01"]);
+    assert_eq!((stub.debugger.cpu().sp, stub.debugger.cpu().a), (0x1234, 0xAA));
+}
+
+#[test]
+fn memory_reads_and_writes_wrap() {
+    let mut stub = GdbStub::new(Cpu8080::new());
+    assert_eq!(replies(&mut stub, &["Mfffe,4:01020304", "mfffe,4", "m0,2", "M0,2:01", "m10"]), ["OK", "01020304", "0304", "E01", "E01"]);
+    assert_eq!(stub.debugger.cpu().memory[0xFFFF], 0x02);
+}
+
+#[test]
+fn breakpoints_stop_continue_and_are_removed_at_detach() {
+    let mut cpu = Cpu8080::new();
+    // NOP; NOP; STA $2000; HLT
+    cpu.load(0, &[0x00, 0x00, 0x32, 0x00, 0x20, 0x76]);
+    let mut stub = GdbStub::new(cpu);
+
+    assert_eq!(replies(&mut stub, &["Z0,1,1", "Z0,1,1", "c"]), ["OK", "OK", "S05"]);
+    assert_eq!(stub.debugger.cpu().pc, 0x0001);
+    assert!(stub.debugger.points().is_empty(), "the session's points go when it ends");
+
+    assert_eq!(replies(&mut stub, &["Z2,2000,1", "c", "z2,2000,1", "c"]), ["OK", "T05watch:2000;", "OK", "S05"]);
+    assert!(stub.debugger.cpu().halted);
+    assert_eq!(replies(&mut stub, &["Z9,0,1", "Z0,x,1", "z0,10,1"]), ["", "E01", "OK"]);
+}
+
+/**
+ * LXI B,$4000; DCX B; MOV A,B; ORA C; JNZ $0003; HLT: longer than one slice of continue
+ * */
+const COUNTDOWN: [u8; 10] = [0x01, 0x00, 0x40, 0x0B, 0x78, 0xB1, 0xC2, 0x03, 0x00, 0x76];
+
+#[test]
+fn interrupt_stops_a_continue() {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0, &COUNTDOWN);
+    let mut stub = GdbStub::new(cpu);
+    let mut input = packet("c").into_bytes();
+    input.push(0x03);
+    let mut pipe = Pipe { input: input.into(), output: Vec::new() };
+    stub.session_on(&mut pipe).unwrap();
+    assert_eq!(String::from_utf8(pipe.output).unwrap(), format!("+{}", packet("S02")));
+    assert!(!stub.debugger.cpu().halted);
+}
+
+#[test]
+fn bytes_sent_during_a_continue_reach_the_next_packet() {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0, &COUNTDOWN);
+    let mut stub = GdbStub::new(cpu);
+    // the m packet is already waiting at the first check for ^C, continue must leave it alone
+    let input = format!("{}{}", packet("c"), packet("m0,1"));
+    let mut pipe = Pipe { input: input.into_bytes().into(), output: Vec::new() };
+    stub.session_on(&mut pipe).unwrap();
+    assert_eq!(String::from_utf8(pipe.output).unwrap(), format!("+{}+{}", packet("S05"), packet("01")));
+    assert!(stub.debugger.cpu().halted);
+}
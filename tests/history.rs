@@ -0,0 +1,125 @@
+use rustyi8080::Cpu8080;
+use rustyi8080::callstack::{CallStack, Frame};
+use rustyi8080::debugger::{Debugger, StopReason};
+use rustyi8080::debugger::history::History;
+
+/**
+ * A loop that writes memory every way an instruction can, and calls two levels deep:
+ *
+ *  0000  LXI SP,$1000      0020  MOV M,A           0030  PUSH H
+ *  0003  LXI H,$2000       0021  INX H             0031  POP H
+ *  0006  INR A             0022  CALL $0030        0032  RET
+ *  0007  STA $3000         0025  RET
+ *  000A  PUSH PSW
+ *  000B  CALL $0020
+ *  000E  POP PSW
+ *  000F  JMP $0006
+ * */
+fn debugger(history: History) -> Debugger<Cpu8080> {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0x0000, &[0x31, 0x00, 0x10, 0x21, 0x00, 0x20, 0x3C, 0x32, 0x00, 0x30, 0xF5, 0xCD, 0x20, 0x00, 0xF1, 0xC3, 0x06, 0x00]);
+    cpu.load(0x0020, &[0x77, 0x23, 0xCD, 0x30, 0x00, 0xC9]);
+    cpu.load(0x0030, &[0xE5, 0xE1, 0xC9]);
+    cpu.call_stack = Some(CallStack::new());
+    let mut debugger = Debugger::new(cpu);
+    debugger.enable_history(history);
+    debugger
+}
+
+#[derive(Debug, PartialEq)]
+struct State {
+    registers: (u16, u16, u8, u16, u16, u16, u8, u64),
+    memory: Vec<u8>,
+    frames: Vec<Frame>
+}
+
+fn state(debugger: &Debugger<Cpu8080>) -> State {
+    let cpu = debugger.cpu();
+    State {
+        registers: (cpu.pc, cpu.sp, cpu.a, cpu.bc(), cpu.de(), cpu.hl(), cpu.condition_codes.to_psw(), cpu.cycles),
+        memory: cpu.memory.clone(),
+        frames: cpu.call_stack.as_ref().unwrap().frames().to_vec()
+    }
+}
+
+/**
+ * Steps count instructions, returning the state before each of them and the state after the last
+ * */
+fn run(debugger: &mut Debugger<Cpu8080>, count: usize) -> Vec<State> {
+    let mut states = Vec::new();
+    for _ in 0..count {
+        states.push(state(debugger));
+        assert_eq!(debugger.step(), StopReason::Step);
+    }
+    states.push(state(debugger));
+    states
+}
+
+#[test]
+fn step_back_undoes_registers_memory_and_calls() {
+    let mut debugger = debugger(History::default());
+    let states = run(&mut debugger, 100);
+    assert!(states.iter().any(|s| s.frames.len() == 2), "the nested call was made");
+
+    for i in (60..100).rev() {
+        assert_eq!(debugger.step_back(), StopReason::Step);
+        assert_eq!(state(&debugger), states[i], "stepping back to instruction {}", i);
+    }
+    // running forward again repeats the same states
+    assert_eq!(run(&mut debugger, 40), states[60..]);
+
+    while debugger.step_back() == StopReason::Step {}
+    assert_eq!(state(&debugger), states[0]);
+    let history = debugger.history().unwrap();
+    assert!(history.is_empty() && history.position() == 0);
+}
+
+#[test]
+fn seek_back_across_snapshots() {
+    let mut history = History::default();
+    history.set_snapshot_interval(16);
+    let mut debugger = debugger(history);
+    let states = run(&mut debugger, 200);
+
+    // from 200 back to 150 restores the snapshot taken before instruction 160 and undoes ten records after it
+    assert_eq!(debugger.seek_back(50), 50);
+    assert_eq!(state(&debugger), states[150]);
+    assert_eq!(debugger.history().unwrap().position(), 150);
+    assert_eq!(debugger.seek_back(1), 1);
+    assert_eq!(state(&debugger), states[149]);
+    // and again, now from just before a snapshot boundary to just after one
+    assert_eq!(debugger.seek_back(149 - 33), 149 - 33);
+    assert_eq!(state(&debugger), states[33]);
+
+    assert_eq!(run(&mut debugger, 167), states[33..]);
+    assert_eq!(debugger.seek_back(1000), 200, "as far as the history reaches");
+    assert_eq!(state(&debugger), states[0]);
+}
+
+#[test]
+fn small_budgets_drop_the_oldest_history() {
+    // the snapshot before the first instruction is over budget on its own, it goes with the records before it
+    let budget = 4096;
+    let mut debugger = debugger(History::new(budget));
+    let states = run(&mut debugger, 1000);
+    let history = debugger.history().unwrap();
+    assert!(history.memory_used() <= budget);
+    let kept = history.len();
+    assert!(kept > 0 && kept < 1000, "{} instructions kept", kept);
+
+    assert_eq!(debugger.seek_back(1000), kept as u64);
+    assert_eq!(state(&debugger), states[1000 - kept]);
+    assert_eq!(debugger.step_back(), StopReason::HistoryStart);
+}
+
+#[test]
+fn evicting_snapshots_and_records_together() {
+    let mut history = History::new(200_000);
+    history.set_snapshot_interval(50);
+    let mut debugger = debugger(history);
+    run(&mut debugger, 1000);
+    let history = debugger.history_mut().unwrap();
+    assert!(history.memory_used() <= 200_000);
+    history.set_budget(0);
+    assert_eq!((history.memory_used(), history.len()), (0, 0));
+}
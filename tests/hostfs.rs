@@ -0,0 +1,164 @@
+use rustyi8080::Cpu8080;
+use rustyi8080::cpm::hostfs::{HostFs, cpm_name, host_name};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FCB: u16 = 0x005C;
+const DMA: u16 = 0x0080;
+
+const OPEN_FILE: u8 = 15;
+const READ_SEQUENTIAL: u8 = 20;
+const WRITE_SEQUENTIAL: u8 = 21;
+const MAKE_FILE: u8 = 22;
+
+/**
+ * Empty scratch directory under the system temp directory, unique per test
+ * */
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rusty-i8080-hostfs-{}-{}", std::process::id(), test));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/**
+ * Host file system with drive B mapped onto dir
+ * */
+fn host_fs(dir: &Path) -> HostFs {
+    let mut host_fs = HostFs::new();
+    host_fs.map(1, dir.to_path_buf());
+    host_fs
+}
+
+/**
+ * Fills in a cleared FCB for a file on drive B
+ * */
+fn set_fcb(cpu: &mut Cpu8080, fcb: u16, name: &[u8; 11]) {
+    cpu.load(fcb, &[0; 36]);
+    cpu.load(fcb, &[2]);
+    cpu.load(fcb.wrapping_add(1), name);
+}
+
+/**
+ * Makes a BDOS call, returning A. Panics if it fell through to the real BDOS
+ * */
+fn call(host_fs: &mut HostFs, cpu: &mut Cpu8080, function: u8, fcb: u16) -> u8 {
+    cpu.c = function;
+    cpu.set_de(fcb);
+    assert!(host_fs.bdos(cpu, DMA), "function {} not handled", function);
+    assert_eq!(cpu.a, cpu.l);
+    cpu.a
+}
+
+#[test]
+fn host_names_are_mangled_to_8_3() {
+    let name = |host: &str| cpm_name(host).map(|name| String::from_utf8(name.to_vec()).unwrap());
+    assert_eq!(name("readme.txt").as_deref(), Some("README  TXT"));
+    assert_eq!(name("Makefile").as_deref(), Some("MAKEFILE   "));
+    assert_eq!(name("longfilename.text").as_deref(), Some("LONGFILETEX"));
+    assert_eq!(name("archive.tar.gz").as_deref(), Some("ARCHIVE_GZ "));
+    assert_eq!(name("a b+c.$$$").as_deref(), Some("A_B_C   $$$"));
+    assert_eq!(name(".profile").as_deref(), Some("_PROFILE   "));
+    assert_eq!(name(""), None);
+}
+
+#[test]
+fn cpm_names_become_lower_case_host_names() {
+    assert_eq!(host_name(b"README  TXT"), "readme.txt");
+    assert_eq!(host_name(b"MAKEFILE   "), "makefile");
+    assert_eq!(host_name(b"A       B  "), "a.b");
+    assert_eq!(cpm_name(&host_name(b"STAT    COM")), Some(*b"STAT    COM"));
+}
+
+#[test]
+fn open_and_read_sequential() {
+    let dir = temp_dir("read");
+    let contents: Vec<u8> = (0..200).map(|i| i as u8).collect();
+    fs::write(dir.join("Hello.Txt"), &contents).unwrap();
+    let mut host_fs = host_fs(&dir);
+    let mut cpu = Cpu8080::new();
+
+    set_fcb(&mut cpu, FCB, b"MISSING TXT");
+    assert_eq!(call(&mut host_fs, &mut cpu, OPEN_FILE, FCB), 0xFF);
+
+    set_fcb(&mut cpu, FCB, b"HELLO   TXT");
+    assert_eq!(call(&mut host_fs, &mut cpu, OPEN_FILE, FCB), 0);
+    // record count of the first extent
+    assert_eq!(cpu.memory[FCB as usize + 15], 2);
+
+    assert_eq!(call(&mut host_fs, &mut cpu, READ_SEQUENTIAL, FCB), 0);
+    assert_eq!(&cpu.memory[DMA as usize..DMA as usize + 128], &contents[..128]);
+    assert_eq!(cpu.memory[FCB as usize + 32], 1);
+
+    // the partial last record is padded with ^Z
+    assert_eq!(call(&mut host_fs, &mut cpu, READ_SEQUENTIAL, FCB), 0);
+    assert_eq!(&cpu.memory[DMA as usize..DMA as usize + 72], &contents[128..]);
+    assert!(cpu.memory[DMA as usize + 72..DMA as usize + 128].iter().all(|&b| b == 0x1A));
+
+    assert_eq!(call(&mut host_fs, &mut cpu, READ_SEQUENTIAL, FCB), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn make_and_write_sequential() {
+    let dir = temp_dir("write");
+    let mut host_fs = host_fs(&dir);
+    let mut cpu = Cpu8080::new();
+
+    set_fcb(&mut cpu, FCB, b"NEW?    DAT");
+    assert_eq!(call(&mut host_fs, &mut cpu, MAKE_FILE, FCB), 0xFF, "wildcards can't be created");
+
+    set_fcb(&mut cpu, FCB, b"NEW     DAT");
+    assert_eq!(call(&mut host_fs, &mut cpu, MAKE_FILE, FCB), 0);
+    assert_eq!(fs::read(dir.join("new.dat")).unwrap(), Vec::<u8>::new());
+
+    for record in 0..2u8 {
+        cpu.load(DMA, &[b'A' + record; 128]);
+        assert_eq!(call(&mut host_fs, &mut cpu, WRITE_SEQUENTIAL, FCB), 0);
+    }
+    let written = fs::read(dir.join("new.dat")).unwrap();
+    assert_eq!(written.len(), 256);
+    assert!(written[..128].iter().all(|&b| b == b'A') && written[128..].iter().all(|&b| b == b'B'));
+    assert_eq!(cpu.memory[FCB as usize + 15], 2);
+
+    set_fcb(&mut cpu, FCB, b"NEW     DAT");
+    assert_eq!(call(&mut host_fs, &mut cpu, OPEN_FILE, FCB), 0);
+    assert_eq!(call(&mut host_fs, &mut cpu, READ_SEQUENTIAL, FCB), 0);
+    assert_eq!(cpu.memory[DMA as usize], b'A');
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn make_leaves_an_existing_file_alone() {
+    let dir = temp_dir("exists");
+    let mut host_fs = host_fs(&dir);
+    let mut cpu = Cpu8080::new();
+    fs::write(dir.join("keep.txt"), b"contents").unwrap();
+    fs::write(dir.join("Upper.TXT"), b"more").unwrap();
+
+    set_fcb(&mut cpu, FCB, b"KEEP    TXT");
+    assert_eq!(call(&mut host_fs, &mut cpu, MAKE_FILE, FCB), 0xFF);
+    assert_eq!(fs::read(dir.join("keep.txt")).unwrap(), b"contents");
+    // nor one whose host name only mangles to the same CP/M name
+    set_fcb(&mut cpu, FCB, b"UPPER   TXT");
+    assert_eq!(call(&mut host_fs, &mut cpu, MAKE_FILE, FCB), 0xFF);
+    assert_eq!(fs::read(dir.join("Upper.TXT")).unwrap(), b"more");
+    assert!(!dir.join("upper.txt").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fcb_at_the_top_of_memory_wraps() {
+    let dir = temp_dir("wrap");
+    fs::write(dir.join("wrap.txt"), [1u8; 10]).unwrap();
+    let mut host_fs = host_fs(&dir);
+    let mut cpu = Cpu8080::new();
+
+    let fcb = 0xFFF0;
+    set_fcb(&mut cpu, fcb, b"WRAP    TXT");
+    assert_eq!(call(&mut host_fs, &mut cpu, OPEN_FILE, fcb), 0);
+    assert_eq!(call(&mut host_fs, &mut cpu, READ_SEQUENTIAL, fcb), 0);
+    // current record is FCB byte 32, which wrapped round to 0x0010
+    assert_eq!(cpu.memory[0x0010], 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,93 @@
+use rustyi8080::Cpu8080;
+use rustyi8080::ihex::HexImage;
+
+/**
+ * One record with its byte count and checksum worked out
+ * */
+fn record(kind: u8, addr: u16, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8, (addr >> 8) as u8, addr as u8, kind];
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    bytes.push(sum.wrapping_neg());
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    format!(":{}\n", hex)
+}
+
+const EOF: &str = ":00000001FF\n";
+
+fn error(text: &str) -> String {
+    HexImage::parse(text).unwrap_err().to_string()
+}
+
+#[test]
+fn data_records_merge_when_consecutive() {
+    let text = [record(0x00, 0x0100, &[1, 2, 3]), record(0x00, 0x0103, &[4, 5]),
+        record(0x00, 0x0200, &[6]), record(0x00, 0x0201, &[7]), EOF.to_string()].concat();
+    let image = HexImage::parse(&text).unwrap();
+    assert_eq!(image.chunks, [(0x0100, vec![1, 2, 3, 4, 5]), (0x0200, vec![6, 7])]);
+
+    let mut cpu = Cpu8080::new();
+    image.load_into(&mut cpu);
+    assert_eq!(&cpu.memory[0x0100..0x0105], &[1, 2, 3, 4, 5]);
+    assert_eq!(&cpu.memory[0x01FF..0x0203], &[0, 6, 7, 0]);
+}
+
+#[test]
+fn end_of_file_stops_parsing() {
+    let text = [record(0x00, 0x0000, &[0xAA]), EOF.to_string(), "anything at all\n".to_string()].concat();
+    assert_eq!(HexImage::parse(&text).unwrap().chunks, [(0x0000, vec![0xAA])]);
+}
+
+#[test]
+fn entry_is_the_start_record_or_the_first_byte() {
+    let data = record(0x00, 0x0100, &[0x76]);
+    assert_eq!(HexImage::parse(&[data.clone(), EOF.to_string()].concat()).unwrap().entry(), Some(0x0100));
+    assert_eq!(HexImage::parse(EOF).unwrap().entry(), None);
+
+    // segment (CS:IP) and linear start addresses, only the low 16 bits are kept
+    for &(kind, start) in [(0x03, [0x00, 0x00, 0x12, 0x34]), (0x05, [0x00, 0x00, 0xBE, 0xEF])].iter() {
+        let image = HexImage::parse(&[data.clone(), record(kind, 0, &start), EOF.to_string()].concat()).unwrap();
+        assert_eq!(image.entry(), Some(u16::from_be_bytes([start[2], start[3]])), "record type {:02X}", kind);
+    }
+}
+
+#[test]
+fn extended_addresses_stay_in_64k() {
+    let data = record(0x00, 0x0100, &[0x76]);
+    for &kind in [0x02, 0x04].iter() {
+        let text = [record(kind, 0, &[0x00, 0x00]), data.clone(), EOF.to_string()].concat();
+        assert_eq!(HexImage::parse(&text).unwrap().chunks, [(0x0100, vec![0x76])]);
+
+        let text = [record(kind, 0, &[0x00, 0x01]), data.clone(), EOF.to_string()].concat();
+        assert_eq!(error(&text), "line 1: extended address beyond 64K");
+    }
+    assert_eq!(error(&record(0x06, 0, &[])), "line 1: unsupported record type");
+    assert_eq!(error(&record(0x03, 0, &[0x12, 0x34])), "line 1: unsupported record type");
+}
+
+#[test]
+fn damaged_records_are_rejected() {
+    let good = record(0x00, 0x0100, &[1, 2, 3]);
+    let mut bad_sum = good.clone();
+    bad_sum.replace_range(9..11, "00");
+    assert_eq!(error(&[good.clone(), bad_sum].concat()), "line 2: bad checksum");
+
+    // claims three bytes of data, has two, with a checksum that adds up
+    let short = record(0x00, 0x0100, &[1, 2]);
+    let short = format!(":03{}", &short[3..]);
+    let sum = u8::from_str_radix(&short[short.len() - 3..short.len() - 1], 16).unwrap().wrapping_sub(1);
+    let short = format!("{}{:02X}\n", &short[..short.len() - 3], sum);
+    assert_eq!(error(&short), "line 1: byte count doesn't match record length");
+
+    assert_eq!(error("0300000001FF\n"), "line 1: record doesn't start with ':'");
+    assert_eq!(error(":0000001FF\n"), "line 1: bad record length");
+    assert_eq!(error(":00000G01FF\n"), "line 1: bad hex digit");
+}
+
+#[test]
+fn non_ascii_is_an_error_not_a_panic() {
+    // what from_utf8_lossy makes of a stray byte, at an odd and at an even offset
+    assert_eq!(error(":0\u{FFFD}000001FF\n"), "line 1: bad hex digit");
+    assert_eq!(error(":00\u{FFFD}00001FF\n"), "line 1: bad hex digit");
+    assert_eq!(error(":0000000é1FF\n"), "line 1: bad hex digit");
+}
@@ -0,0 +1,92 @@
+use rustyi8080::inspect::{self, Pattern};
+
+fn hexdump(memory: &[u8], range: std::ops::RangeInclusive<u16>) -> String {
+    let mut dump = Vec::new();
+    inspect::write_hexdump(&mut dump, memory, range).unwrap();
+    String::from_utf8(dump).unwrap()
+}
+
+#[test]
+fn hexdump_rows_stop_at_the_end_of_memory() {
+    let mut memory = vec![0; 0x30];
+    memory[0x10..0x15].copy_from_slice(b"HELLO");
+    assert_eq!(hexdump(&memory, 0x10..=0x17), "0010  48 45 4C 4C 4F 00 00 00                          HELLO...\n");
+    // an image shorter than the range is dumped up to its end instead of panicking
+    assert_eq!(hexdump(&memory, 0x28..=0xFFFF), "0028  00 00 00 00 00 00 00 00                          ........\n");
+    assert_eq!(hexdump(&memory, 0x40..=0x50), "");
+    assert_eq!(hexdump(&[], 0..=0xFFFF), "");
+}
+
+#[test]
+fn hexdump_with_a_reader_wraps_round_to_0000() {
+    let mut dump = Vec::new();
+    inspect::write_hexdump_with(&mut dump, 0xFFF8, 0x18, |addr| addr as u8).unwrap();
+    assert_eq!(String::from_utf8(dump).unwrap(), "\
+FFF8  F8 F9 FA FB FC FD FE FF 00 01 02 03 04 05 06 07  ................
+0008  08 09 0A 0B 0C 0D 0E 0F                          ........
+");
+}
+
+#[test]
+fn patterns_and_diffs() {
+    let mut memory = vec![0; 0x100];
+    memory[0x20..0x23].copy_from_slice(&[0xCD, 0x05, 0x00]);
+    memory[0x80..0x83].copy_from_slice(&[0xCD, 0x10, 0x00]);
+    assert_eq!(Pattern::parse("CD ?? 00").unwrap().find(&memory, 0..=0xFFFF), [0x20, 0x80]);
+    assert_eq!(Pattern::parse("CD05").unwrap().find(&memory, 0..=0xFFFF), [0x20]);
+    assert!(Pattern::parse("CD 0").is_none() && Pattern::parse("\"\"").is_none());
+
+    let mut new = memory.clone();
+    new[0x21] = 0x06;
+    new[0x90..0x92].copy_from_slice(&[1, 2]);
+    assert_eq!(inspect::diff(&memory, &new), [0x21..=0x21, 0x90..=0x91]);
+}
+
+/**
+ * 60K of RAM with a 2K ROM at F800 that the RAM image knows nothing about, and a counter port mapped at F000
+ * */
+fn bus(ram: &[u8], addr: u16) -> u8 {
+    match addr {
+        0xF800..=0xFFFF => b"ROM MONITOR"[(addr as usize - 0xF800) % 11],
+        0xF000 => 0x42,
+        _ => ram[addr as usize]
+    }
+}
+
+#[test]
+fn find_and_diff_through_a_reader() {
+    let ram = vec![0; 0x10000];
+    let read = |addr| bus(&ram, addr);
+    assert_eq!(Pattern::text("MONITOR").find_with(0xF800..=0xF80A, read), [0xF804]);
+    assert!(Pattern::text("MONITOR").find(&ram, 0..=0xFFFF).is_empty(), "the RAM image has no ROM in it");
+    assert!(Pattern::parse("42").unwrap().matches_at_with(0xF000, read));
+    // a match may wrap round to 0000, as a hexdump does
+    assert_eq!(Pattern::parse("FF 00 ??").unwrap().find_with(0xFF00..=0xFFFF, |addr| addr as u8), [0xFFFF]);
+
+    let mut snapshot = ram.clone();
+    snapshot[0x0100] = 1;
+    let changed = inspect::diff_with(&snapshot, read);
+    assert_eq!(changed.first(), Some(&(0x0100..=0x0100)));
+    assert!(changed.contains(&(0xF000..=0xF000)) && changed.last() == Some(&(0xF800..=0xFFFF)));
+
+    let mut lines = Vec::new();
+    inspect::write_diff_with(&mut lines, &snapshot[..0xF001], read).unwrap();
+    assert_eq!(String::from_utf8(lines).unwrap(), "0100-0100  (1)  01 -> 00\nF000-F000  (1)  00 -> 42\n");
+}
+
+#[test]
+fn diffs_of_different_lengths_count_the_tail() {
+    let old = [0, 0, 0, 0];
+    let new = [0, 1, 0, 0, 5, 6];
+    assert_eq!(inspect::diff(&old, &new), [0x01..=0x01, 0x04..=0x05]);
+    assert_eq!(inspect::diff(&new, &old), [0x01..=0x01, 0x04..=0x05]);
+
+    let mut lines = Vec::new();
+    inspect::write_diff(&mut lines, &old, &new).unwrap();
+    assert_eq!(String::from_utf8(lines).unwrap(), "0001-0001  (1)  00 -> 01\n0004-0005  (2)  -- -- -> 05 06\n");
+
+    let mut lines = Vec::new();
+    inspect::write_diff(&mut lines, &[0; 4], &[1; 12]).unwrap();
+    assert_eq!(String::from_utf8(lines).unwrap(),
+        "0000-000B  (12)  00 00 00 00 -- -- -- -- ... -> 01 01 01 01 01 01 01 01 ...\n");
+}
@@ -0,0 +1,115 @@
+use rustyi8080::Cpu8080;
+use rustyi8080::io::{IoBus, NullBus};
+use rustyi8080::movie::{Movie, Player, Recorder};
+
+/**
+ * Stores 32 reads of port 1 from $2000 up:
+ *
+ *  0000  LXI H,$2000       0007  MOV M,A           000A  JNZ $0005
+ *  0003  MVI C,$20         0008  INX H             000D  HLT
+ *  0005  IN $01            0009  DCR C
+ * */
+const PROGRAM: [u8; 14] = [0x21, 0x00, 0x20, 0x0E, 0x20, 0xDB, 0x01, 0x77, 0x23, 0x0D, 0xC2, 0x05, 0x00, 0x76];
+
+fn cpu() -> Cpu8080 {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0, &PROGRAM);
+    cpu
+}
+
+/**
+ * Controls that change every third read, as a player's would now and then
+ * */
+#[derive(Default)]
+struct Controls {
+    reads: u32
+}
+
+impl IoBus for Controls {
+    fn input(&mut self, _port: u8) -> u8 {
+        self.reads += 1;
+        (self.reads / 3) as u8 * 5
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
+
+fn record(cpu: &mut Cpu8080, mut recorder: Recorder<Controls>) -> Movie {
+    recorder.set_check_interval(40);
+    while !cpu.halted {
+        recorder.step(cpu);
+    }
+    recorder.finish(cpu).0
+}
+
+fn play(movie: Movie, cpu: &mut Cpu8080) -> std::io::Result<()> {
+    let mut player = Player::new(movie, cpu, NullBus)?;
+    while !player.is_finished() {
+        player.step(cpu)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn playback_reproduces_the_recording() {
+    let mut cpu = cpu();
+    let recorder = Recorder::power_on(&cpu, Controls::default(), &[1]);
+    let movie = record(&mut cpu, recorder);
+    assert_eq!(movie.events.len(), 11, "a read of 0 then a change every third read");
+    assert!(movie.checks.len() > 5);
+
+    // nothing on the bus: every value stored comes from the movie
+    let mut replay = self::cpu();
+    play(movie.clone(), &mut replay).unwrap();
+    assert!(replay.halted);
+    assert_eq!(replay.cycles, movie.length);
+    assert_eq!(&replay.memory[0x2000..0x2020], &cpu.memory[0x2000..0x2020]);
+}
+
+#[test]
+fn a_changed_input_is_a_desync() {
+    let mut cpu = cpu();
+    let recorder = Recorder::power_on(&cpu, Controls::default(), &[1]);
+    let mut movie = record(&mut cpu, recorder);
+    movie.events[4].value ^= 0x10;
+    let error = play(movie, &mut self::cpu()).unwrap_err();
+    assert!(error.to_string().starts_with("desync"), "{}", error);
+}
+
+#[test]
+fn movies_from_a_save_state_restore_it() {
+    let mut cpu = cpu();
+    cpu.step(&mut NullBus);
+    cpu.step(&mut NullBus);
+    let recorder = Recorder::from_state(&cpu, Controls::default(), &[1]);
+    let movie = record(&mut cpu, recorder);
+    assert_eq!(movie.checks[0].cycle, 0);
+
+    // the machine it plays on doesn't even have the program loaded
+    let mut replay = Cpu8080::new();
+    play(movie, &mut replay).unwrap();
+    assert_eq!(&replay.memory[0x2000..0x2020], &cpu.memory[0x2000..0x2020]);
+}
+
+#[test]
+fn movies_round_trip_through_bytes() {
+    let mut cpu = cpu();
+    let recorder = Recorder::power_on(&cpu, Controls::default(), &[1, 7]);
+    let power_on = record(&mut cpu, recorder);
+    let mut cpu = self::cpu();
+    cpu.step(&mut NullBus);
+    let recorder = Recorder::from_state(&cpu, Controls::default(), &[1]);
+    let from_state = record(&mut cpu, recorder);
+    for movie in [power_on, from_state].iter() {
+        let bytes = movie.to_bytes();
+        assert_eq!(&Movie::from_bytes(&bytes).unwrap(), movie);
+
+        assert!(Movie::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        for &i in [8, 20, bytes.len() - 1].iter() {
+            let mut damaged = bytes.clone();
+            damaged[i] ^= 0x01;
+            let error = Movie::from_bytes(&damaged).unwrap_err();
+            assert!(error.to_string().contains("checksum"), "byte {}: {}", i, error);
+        }
+    }
+}
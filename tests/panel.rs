@@ -0,0 +1,74 @@
+use rustyi8080::console::BufferConsole;
+use rustyi8080::machines::altair::Altair;
+use rustyi8080::machines::altair::panel::FrontPanel;
+
+// MVI A,$2A; STA $2000; HLT
+const PROGRAM: [u8; 6] = [0x3E, 0x2A, 0x32, 0x00, 0x20, 0x76];
+const ORIGIN: u16 = 0x0100;
+
+fn panel() -> FrontPanel<BufferConsole> {
+    FrontPanel::new(Altair::new(BufferConsole::default()))
+}
+
+/**
+ * Toggles a program in the way an operator does: EXAMINE the start address, DEPOSIT the first byte, then
+ * DEPOSIT NEXT the rest
+ * */
+fn toggle_in(panel: &mut FrontPanel<BufferConsole>, origin: u16, program: &[u8]) {
+    panel.set_switches(origin);
+    panel.examine();
+    for (i, &byte) in program.iter().enumerate() {
+        panel.set_switches(byte as u16);
+        if i == 0 { panel.deposit() } else { panel.deposit_next() }
+        assert_eq!(panel.leds().address, origin + i as u16);
+        assert_eq!(panel.leds().data, byte);
+    }
+}
+
+#[test]
+fn examine_and_deposit() {
+    let mut panel = panel();
+    toggle_in(&mut panel, ORIGIN, &PROGRAM);
+    assert_eq!(&panel.altair.cpu.memory[ORIGIN as usize..ORIGIN as usize + PROGRAM.len()], &PROGRAM);
+
+    // reading it back: EXAMINE then EXAMINE NEXT walks memory with the instruction fetch status lit
+    panel.set_switches(ORIGIN);
+    panel.examine();
+    let mut read = Vec::new();
+    for i in 0..PROGRAM.len() {
+        if i > 0 {
+            panel.examine_next();
+        }
+        let leds = panel.leds();
+        assert!(leds.wait && leds.m1() && leds.memr());
+        assert_eq!(leds.address, ORIGIN + i as u16);
+        read.push(leds.data);
+    }
+    assert_eq!(read, PROGRAM);
+}
+
+#[test]
+fn single_step_then_run_to_halt() {
+    let mut panel = panel();
+    toggle_in(&mut panel, ORIGIN, &PROGRAM);
+    panel.set_switches(ORIGIN);
+    panel.examine();
+
+    panel.single_step();
+    assert_eq!(panel.altair.cpu.a, 0x2A);
+    assert_eq!(panel.leds().address, ORIGIN + 2);
+
+    panel.run();
+    panel.run_for(1000);
+    panel.stop();
+    assert_eq!(panel.altair.cpu.memory[0x2000], 0x2A);
+    assert!(panel.leds().hlta());
+
+    // switches have no effect on memory while running
+    panel.run();
+    let pc = panel.altair.cpu.pc as usize;
+    let before = panel.altair.cpu.memory[pc];
+    panel.set_switches(0x55);
+    panel.deposit();
+    assert_eq!(panel.altair.cpu.memory[pc], before);
+}
@@ -0,0 +1,84 @@
+use rustyi8080::Cpu8080;
+use rustyi8080::callstack::CallStack;
+use rustyi8080::io::NullBus;
+use rustyi8080::profile::{Counts, Profiler, Routine};
+use rustyi8080::symbols::SymbolTable;
+
+/**
+ * Runs the program from 0000 to its HLT with the call stack on
+ * */
+fn profile(program: &[(u16, &[u8])], symbols: &str) -> (Profiler, SymbolTable) {
+    let mut cpu = Cpu8080::new();
+    for (addr, code) in program.iter() {
+        cpu.load(*addr, code);
+    }
+    cpu.call_stack = Some(CallStack::new());
+    let mut profiler = Profiler::new();
+    while !cpu.halted {
+        profiler.step(&mut cpu, &mut NullBus);
+    }
+    let mut table = SymbolTable::new();
+    table.parse(symbols).unwrap();
+    (profiler, table)
+}
+
+fn routine(name: &str, own: (u64, u64), total: (u64, u64)) -> Routine {
+    Routine {
+        name: name.to_string(),
+        own: Counts { instructions: own.0, cycles: own.1 },
+        total: Counts { instructions: total.0, cycles: total.1 }
+    }
+}
+
+fn folded(profiler: &Profiler, symbols: &SymbolTable) -> Vec<String> {
+    let mut folded = Vec::new();
+    profiler.write_folded(&mut folded, symbols).unwrap();
+    String::from_utf8(folded).unwrap().lines().map(str::to_string).collect()
+}
+
+/**
+ *  0000  LXI SP,$1000  10    0010  MVI A,$02  7     0020  NOP  4
+ *  0003  CALL $0010    17    0012  CALL $0020 17    0021  RET  10
+ *  0006  CALL $0020    17    0015  RET        10
+ *  0009  HLT           7
+ * */
+#[test]
+fn cycles_go_to_the_routine_and_its_callers() {
+    let (profiler, symbols) = profile(&[
+        (0x0000, &[0x31, 0x00, 0x10, 0xCD, 0x10, 0x00, 0xCD, 0x20, 0x00, 0x76]),
+        (0x0010, &[0x3E, 0x02, 0xCD, 0x20, 0x00, 0xC9]),
+        (0x0020, &[0x00, 0xC9])
+    ], "0000 MAIN 0010 SUB 0020 LEAF");
+    assert_eq!(profiler.total(), Counts { instructions: 11, cycles: 113 });
+
+    // the CALL belongs to the caller, the RET to the routine returning
+    assert_eq!(profiler.routines(&symbols), [
+        routine("MAIN", (4, 51), (11, 113)),
+        routine("SUB", (3, 34), (5, 48)),
+        routine("LEAF", (4, 28), (4, 28))
+    ]);
+    assert_eq!(folded(&profiler, &symbols), ["MAIN 51", "MAIN;LEAF 14", "MAIN;SUB 34", "MAIN;SUB;LEAF 14"]);
+
+    // without symbols routines are named by address
+    let unnamed = SymbolTable::new();
+    assert_eq!(folded(&profiler, &unnamed), ["(top) 51", "(top);0010 34", "(top);0010;0020 14", "(top);0020 14"]);
+}
+
+/**
+ *  0000  LXI SP,$1000  10    0030  DCR B       5
+ *  0003  MVI B,$02     7     0031  CNZ $0030   17 taken, 11 not
+ *  0005  CALL $0030    17    0034  RET         10
+ *  0008  HLT           7
+ * */
+#[test]
+fn recursion_counts_once_in_the_total() {
+    let (profiler, symbols) = profile(&[
+        (0x0000, &[0x31, 0x00, 0x10, 0x06, 0x02, 0xCD, 0x30, 0x00, 0x76]),
+        (0x0030, &[0x05, 0xC4, 0x30, 0x00, 0xC9])
+    ], "MAIN 0000\nREC 0030\n");
+    assert_eq!(profiler.routines(&symbols), [
+        routine("REC", (6, 58), (6, 58)),
+        routine("MAIN", (4, 41), (10, 99))
+    ]);
+    assert_eq!(folded(&profiler, &symbols), ["MAIN 41", "MAIN;REC 32", "MAIN;REC;REC 26"]);
+}
@@ -0,0 +1,93 @@
+use rustyi8080::{AccessKind, ConditionBitset, Cpu8080, Reg16, Reg8};
+use rustyi8080::io::NullBus;
+
+#[test]
+fn reg8_codes_and_names() {
+    for (code, reg) in Reg8::ALL.iter().enumerate() {
+        assert_eq!(Reg8::from_code(code as u8), *reg);
+        assert_eq!(*reg as u8, code as u8);
+        assert_eq!(Reg8::parse(reg.name()), Some(*reg));
+        assert_eq!(Reg8::parse(&reg.name().to_ascii_lowercase()), Some(*reg));
+    }
+    // MOV A,M is 0x7E: destination in bits 3-5, source in bits 0-2, anything above ignored
+    assert_eq!((Reg8::from_code(0x7E >> 3), Reg8::from_code(0x7E)), (Reg8::A, Reg8::M));
+    assert_eq!(Reg8::parse("HL"), None);
+    assert_eq!(Reg8::parse(""), None);
+
+    for reg in Reg16::ALL.iter() {
+        assert_eq!(Reg16::parse(reg.name()), Some(*reg));
+    }
+    assert_eq!(Reg16::parse("psw"), Some(Reg16::PSW));
+    assert_eq!(Reg16::parse("AF"), None);
+}
+
+#[test]
+fn get_and_set_bytes() {
+    let mut cpu = Cpu8080::new();
+    for (i, reg) in Reg8::ALL.iter().enumerate().filter(|(_, reg)| **reg != Reg8::M) {
+        cpu.set(*reg, 0x10 + i as u8);
+    }
+    assert_eq!((cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l, cpu.a), (0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x17));
+    for (i, reg) in Reg8::ALL.iter().enumerate().filter(|(_, reg)| **reg != Reg8::M) {
+        assert_eq!(cpu.get(*reg), 0x10 + i as u8, "{}", reg.name());
+    }
+}
+
+#[test]
+fn m_is_the_byte_at_hl() {
+    let mut cpu = Cpu8080::new();
+    cpu.memory_log = Some(Vec::new());
+    cpu.set_hl(0x2000);
+    cpu.set(Reg8::M, 0x5A);
+    assert_eq!(cpu.memory[0x2000], 0x5A);
+    let log = cpu.memory_log.as_ref().unwrap();
+    assert_eq!((log.len(), log[0].addr, log[0].kind), (1, 0x2000, AccessKind::Write), "writes go through write_byte");
+
+    assert_eq!(cpu.get(Reg8::M), 0x5A);
+    assert_eq!(cpu.memory_log.as_ref().unwrap().len(), 1, "reads aren't logged");
+
+    // moving H moves M
+    cpu.memory[0x3000] = 0xA5;
+    cpu.set(Reg8::H, 0x30);
+    assert_eq!(cpu.get(Reg8::M), 0xA5);
+}
+
+#[test]
+fn get_and_set_words() {
+    let mut cpu = Cpu8080::new();
+    for (i, reg) in Reg16::ALL.iter().enumerate().filter(|(_, reg)| **reg != Reg16::PSW) {
+        let value = 0x1111 * (i as u16 + 1);
+        cpu.set(*reg, value);
+        assert_eq!(cpu.get(*reg), value, "{}", reg.name());
+    }
+    assert_eq!((cpu.bc(), cpu.b, cpu.c), (0x1111, 0x11, 0x11));
+    assert_eq!((cpu.d, cpu.e, cpu.h, cpu.l), (0x22, 0x22, 0x33, 0x33));
+    assert_eq!((cpu.sp, cpu.pc), (0x4444, 0x6666));
+
+    cpu.set_de(0xBEEF);
+    assert_eq!((cpu.d, cpu.e, cpu.get(Reg16::DE)), (0xBE, 0xEF, 0xBEEF));
+}
+
+#[test]
+fn psw_round_trips_through_the_flags_byte() {
+    let mut cpu = Cpu8080::new();
+    for flags in 0..=255u8 {
+        cpu.set(Reg16::PSW, 0x3400 | flags as u16);
+        let expected = ConditionBitset::from_psw(flags).to_psw();
+        assert_eq!(cpu.get(Reg16::PSW), 0x3400 | expected as u16, "flags {:02X}", flags);
+        assert_eq!(cpu.condition_codes.to_psw(), expected);
+    }
+    // only the bits the 8080 keeps survive, with bit 1 always set
+    cpu.set(Reg16::PSW, 0x00FF);
+    assert_eq!(cpu.get(Reg16::PSW), 0x00D7);
+    cpu.set(Reg16::PSW, 0x0000);
+    assert_eq!(cpu.get(Reg16::PSW), 0x0002);
+
+    // and it is what PUSH PSW pushes
+    cpu.set(Reg16::PSW, 0x12C5);
+    cpu.sp = 0x1000;
+    cpu.load(0, &[0xF5]);
+    cpu.pc = 0;
+    cpu.step(&mut NullBus);
+    assert_eq!(cpu.read_u16(0x0FFE), cpu.get(Reg16::PSW));
+}
@@ -0,0 +1,153 @@
+use rustyi8080::{Cpu8080, ConditionFlag};
+use rustyi8080::console::BufferConsole;
+use rustyi8080::cpm::{BiosCall, CpmMachine};
+use rustyi8080::cpm::disk::DiskImage;
+use rustyi8080::io::NullBus;
+use rustyi8080::machines::altair::Altair;
+use rustyi8080::machines::altair::dcdd::{self, Dcdd};
+use rustyi8080::savestate::{SaveState, Snapshot};
+
+/**
+ * Saves machine, restores the bytes into fresh and checks fresh saves the very same bytes
+ * */
+fn round_trip<S: Snapshot>(machine: &S, fresh: &mut S) -> Vec<u8> {
+    let bytes = SaveState::of(machine).to_bytes();
+    fresh.load_state(&SaveState::from_bytes(&bytes).unwrap()).unwrap();
+    assert_eq!(SaveState::of(fresh).to_bytes(), bytes);
+    bytes
+}
+
+fn cpu() -> Cpu8080 {
+    let mut cpu = Cpu8080::new();
+    // LXI SP,$2000; LXI B,$1234; PUSH B; MVI A,$80; ADD A; EI; HLT
+    cpu.load(0x0100, &[0x31, 0x00, 0x20, 0x01, 0x34, 0x12, 0xC5, 0x3E, 0x80, 0x87, 0xFB, 0x76]);
+    cpu.pc = 0x0100;
+    while !cpu.halted {
+        cpu.step(&mut NullBus);
+    }
+    cpu
+}
+
+#[test]
+fn cpu_restores_byte_for_byte() {
+    let cpu = cpu();
+    let mut restored = Cpu8080::new();
+    round_trip(&cpu, &mut restored);
+    assert_eq!((restored.pc, restored.sp, restored.bc(), restored.cycles), (cpu.pc, 0x1FFE, 0x1234, cpu.cycles));
+    assert!(restored.halted && restored.interrupts_enabled);
+    assert!(restored.condition_codes.is_set(ConditionFlag::Carry) && restored.condition_codes.is_set(ConditionFlag::Zero));
+    assert_eq!(restored.memory, cpu.memory);
+}
+
+fn altair() -> Altair<BufferConsole> {
+    let mut altair = Altair::new(BufferConsole::default());
+    let mut controller = Dcdd::new();
+    let image = (0..dcdd::ALTAIR_8.image_size()).map(|i| (i * 13) as u8).collect();
+    controller.mount(1, DiskImage::from_bytes_with(image, dcdd::ALTAIR_8));
+    altair.io.plug(dcdd::PORTS, Box::new(controller));
+    altair
+}
+
+#[test]
+fn altair_with_a_disk_controller_restores_byte_for_byte() {
+    let mut altair = altair();
+    altair.cpu = cpu();
+    altair.io.sense_switches = 0x5A;
+    // drive 1 selected, head loaded and moved to track 2, partway into a sector
+    let controller = &mut altair.io.cards[0].device;
+    controller.output(dcdd::SELECT, 1);
+    controller.output(dcdd::CONTROL, dcdd::CONTROL_HEAD_LOAD);
+    controller.output(dcdd::CONTROL, dcdd::CONTROL_STEP_IN);
+    controller.output(dcdd::CONTROL, dcdd::CONTROL_STEP_IN);
+    controller.tick(dcdd::CYCLES_PER_SECTOR * 5 + 100);
+    controller.input(dcdd::DATA);
+
+    // restored into an Altair whose controller has a different disk in drive 1 and one in drive 0
+    let mut restored = Altair::new(BufferConsole::default());
+    let mut controller = Dcdd::new();
+    controller.mount(0, DiskImage::blank_with(dcdd::ALTAIR_8));
+    controller.mount(1, DiskImage::blank_with(dcdd::ALTAIR_8));
+    restored.io.plug(dcdd::PORTS, Box::new(controller));
+    round_trip(&altair, &mut restored);
+    assert_eq!(restored.io.sense_switches, 0x5A);
+    let controller = &mut restored.io.cards[0].device;
+    assert_eq!(controller.input(dcdd::STATUS), altair.io.cards[0].device.input(dcdd::STATUS));
+    assert_eq!(controller.input(dcdd::DATA), altair.io.cards[0].device.input(dcdd::DATA));
+}
+
+/**
+ * Makes a BIOS call the way a program does, CALLing its jump table entry
+ * */
+fn bios_call(machine: &mut CpmMachine<BufferConsole>, call: BiosCall, bc: u16) {
+    machine.cpu.sp = 0x8000;
+    machine.cpu.push_stack(0x0100);
+    machine.cpu.set_bc(bc);
+    machine.cpu.pc = machine.bios_base() + call as u16 * 3;
+    machine.step();
+}
+
+#[test]
+fn cpm_machine_restores_byte_for_byte() {
+    let mut machine = CpmMachine::new(BufferConsole::default());
+    machine.mount(0, DiskImage::blank());
+    machine.mount(1, DiskImage::blank());
+    // write a sector of B: from 0x0080 so the image differs from a blank one, leaving the BIOS state set
+    machine.cpu.load(0x0080, &[0xA5; 128]);
+    bios_call(&mut machine, BiosCall::SelDsk, 1);
+    bios_call(&mut machine, BiosCall::SetTrk, 5);
+    bios_call(&mut machine, BiosCall::SetSec, 9);
+    bios_call(&mut machine, BiosCall::SetDma, 0x0080);
+    bios_call(&mut machine, BiosCall::Write, 0);
+    assert_eq!(machine.cpu.a, 0);
+
+    let mut restored = CpmMachine::new(BufferConsole::default());
+    restored.mount(0, DiskImage::blank());
+    restored.mount(3, DiskImage::blank());
+    round_trip(&machine, &mut restored);
+    assert_eq!(restored.drives[1].as_ref().map(DiskImage::as_bytes), machine.drives[1].as_ref().map(DiskImage::as_bytes));
+    assert!(restored.drives[3].is_none());
+
+    // reading the sector back goes through the restored disk, track, sector and DMA
+    restored.cpu.load(0x0080, &[0; 128]);
+    bios_call(&mut restored, BiosCall::Read, 0);
+    assert_eq!(restored.cpu.a, 0);
+    assert_eq!(&restored.cpu.memory[0x0080..0x0100], &[0xA5; 128][..]);
+}
+
+#[test]
+fn damaged_files_are_refused() {
+    let bytes = SaveState::of(&cpu()).to_bytes();
+    for len in [0, 8, 12, bytes.len() / 2, bytes.len() - 1].iter() {
+        assert!(SaveState::from_bytes(&bytes[..*len]).is_err(), "truncated to {} bytes", len);
+    }
+    // every byte after the magic is covered by the checksum, the version field too
+    for &i in [8, 9, 12, 30, bytes.len() / 2, bytes.len() - 1].iter() {
+        let mut damaged = bytes.clone();
+        damaged[i] ^= 0x04;
+        let error = SaveState::from_bytes(&damaged).unwrap_err();
+        assert!(error.to_string().contains("checksum"), "byte {}: {}", i, error);
+    }
+    let mut damaged = bytes;
+    damaged[0] = b'X';
+    assert_eq!(SaveState::from_bytes(&damaged).unwrap_err().to_string(), "not a save state");
+}
+
+#[test]
+fn differently_configured_machines_are_refused() {
+    let mut machine = CpmMachine::new(BufferConsole::default());
+    machine.mount(0, DiskImage::blank());
+    let state = SaveState::of(&machine);
+    let mut other = CpmMachine::with_ccp_base(BufferConsole::default(), 0xC400);
+    other.cpu.a = 0x77;
+    assert!(other.load_state(&state).is_err());
+    assert_eq!(other.cpu.a, 0x77, "left untouched");
+
+    let state = SaveState::of(&altair());
+    let mut bare = Altair::new(BufferConsole::default());
+    bare.cpu.a = 0x77;
+    assert!(bare.load_state(&state).is_err(), "no disk controller plugged in");
+    assert_eq!(bare.cpu.a, 0x77);
+
+    // a snapshot of the bare CPU has no Altair section
+    assert!(altair().load_state(&SaveState::of(&cpu())).is_err());
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use rustyi8080::{Cpu8080, ConditionBitset};
+use rustyi8080::io::IoBus;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/**
+ * Per-instruction vectors in the SingleStepTests format, one file per opcode named after it (00.json ... ff.json).
+ * They are not included with the crate: put them in tests/single_step/ or point I8080_SINGLE_STEP at them.
+ * Without them the test passes after printing a SKIPPED line.
+ * */
+const VECTORS_ENV: &str = "I8080_SINGLE_STEP";
+
+// failing cases listed per opcode before the rest are only counted
+const REPORTED_PER_OPCODE: usize = 3;
+
+fn vector_dir() -> PathBuf {
+    match std::env::var_os(VECTORS_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("single_step")
+    }
+}
+
+/**
+ * Answers IN with the values listed in the test's "ports" entries, OUT is ignored
+ * */
+struct PortList(HashMap<u8, u8>);
+
+impl IoBus for PortList {
+    fn input(&mut self, port: u8) -> u8 {
+        self.0.get(&port).copied().unwrap_or(0xFF)
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
+
+fn field(state: &Value, name: &str) -> u64 {
+    state[name].as_u64().unwrap_or_else(|| panic!("missing or invalid field {}", name))
+}
+
+fn ram(state: &Value) -> Vec<(u16, u8)> {
+    state["ram"].as_array().map(|entries| entries.iter()
+        .map(|entry| (entry[0].as_u64().unwrap() as u16, entry[1].as_u64().unwrap() as u8))
+        .collect()
+    ).unwrap_or_default()
+}
+
+fn setup(initial: &Value) -> Cpu8080 {
+    let mut cpu = Cpu8080::new();
+    cpu.pc = field(initial, "pc") as u16;
+    cpu.sp = field(initial, "sp") as u16;
+    cpu.a = field(initial, "a") as u8;
+    cpu.b = field(initial, "b") as u8;
+    cpu.c = field(initial, "c") as u8;
+    cpu.d = field(initial, "d") as u8;
+    cpu.e = field(initial, "e") as u8;
+    cpu.h = field(initial, "h") as u8;
+    cpu.l = field(initial, "l") as u8;
+    cpu.condition_codes = ConditionBitset::from_psw(field(initial, "f") as u8);
+    for (addr, value) in ram(initial) {
+        cpu.memory[addr as usize] = value;
+    }
+    cpu
+}
+
+const FLAGS: [(&str, u8); 5] = [("S", 0x80), ("Z", 0x40), ("AC", 0x10), ("P", 0x04), ("CY", 0x01)];
+
+/**
+ * Names the flags that differ, e.g. `(AC should be set, P should be clear)`
+ * */
+fn flag_diff(want: u8, actual: u8) -> String {
+    let named: Vec<String> = FLAGS.iter()
+        .filter(|(_, bit)| (want ^ actual) & bit != 0)
+        .map(|(name, bit)| format!("{} should be {}", name, if want & bit != 0 { "set" } else { "clear" }))
+        .collect();
+    if named.is_empty() {
+        "(unused bits only)".to_string()
+    } else {
+        format!("({})", named.join(", "))
+    }
+}
+
+/**
+ * Every difference between the CPU and the expected final state
+ * */
+fn diff(cpu: &Cpu8080, test: &Value) -> Vec<String> {
+    let expected = &test["final"];
+    let mut mismatches = Vec::new();
+
+    let registers: [(&str, u64); 10] = [
+        ("pc", cpu.pc as u64), ("sp", cpu.sp as u64),
+        ("a", cpu.a as u64), ("f", cpu.condition_codes.to_psw() as u64),
+        ("b", cpu.b as u64), ("c", cpu.c as u64),
+        ("d", cpu.d as u64), ("e", cpu.e as u64),
+        ("h", cpu.h as u64), ("l", cpu.l as u64)
+    ];
+    for (name, actual) in registers.iter() {
+        let want = field(expected, name);
+        if want == *actual {
+            continue;
+        }
+        let mut mismatch = format!("{}: expected {:02X} got {:02X}", name, want, actual);
+        if *name == "f" {
+            mismatch = format!("{} {}", mismatch, flag_diff(want as u8, *actual as u8));
+        }
+        mismatches.push(mismatch);
+    }
+
+    for (addr, want) in ram(expected) {
+        let actual = cpu.memory[addr as usize];
+        if actual != want {
+            mismatches.push(format!("[{:04X}]: expected {:02X} got {:02X}", addr, want, actual));
+        }
+    }
+
+    if let Some(cycles) = test["cycles"].as_array() {
+        if cycles.len() as u64 != cpu.cycles {
+            mismatches.push(format!("cycles: expected {} got {}", cycles.len(), cpu.cycles));
+        }
+    }
+    mismatches
+}
+
+fn run_test(test: &Value) -> Vec<String> {
+    let mut cpu = setup(&test["initial"]);
+    let mut ports = PortList(HashMap::new());
+    if let Some(entries) = test["ports"].as_array() {
+        for entry in entries {
+            ports.0.insert(entry[0].as_u64().unwrap() as u8, entry[1].as_u64().unwrap() as u8);
+        }
+    }
+    cpu.step(&mut ports);
+    diff(&cpu, test)
+}
+
+/**
+ * Runs one file, returning a report line per failing case (up to REPORTED_PER_OPCODE) and the number of failures
+ * */
+fn run_file(contents: &str) -> (Vec<String>, usize, usize) {
+    let tests: Value = serde_json::from_str(contents).expect("malformed test vector file");
+    let tests = tests.as_array().expect("test vector file is not an array");
+
+    let mut report = Vec::new();
+    let mut failures = 0;
+    for test in tests {
+        let mismatches = run_test(test);
+        if mismatches.is_empty() {
+            continue;
+        }
+        failures += 1;
+        if report.len() < REPORTED_PER_OPCODE {
+            let name = test["name"].as_str().unwrap_or("?");
+            report.push(format!("    {}: {}", name, mismatches.join(", ")));
+        }
+    }
+    (report, failures, tests.len())
+}
+
+/**
+ * IN $20 and MOV M,A as the vectors have them, then ADD M with a wrong AC flag and a wrong sum in memory.
+ * Only the number of cycles is checked, not what each one puts on the bus
+ * */
+const VECTORS: &str = r#"[
+    {"name": "db 0000",
+     "initial": {"pc": 256, "sp": 0, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+                 "ram": [[256, 219], [257, 32]]},
+     "final": {"pc": 258, "sp": 0, "a": 90, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 0, "l": 0,
+               "ram": [[256, 219], [257, 32]]},
+     "cycles": [[], [], [], [], [], [], [], [], [], []],
+     "ports": [[32, 90, "r"]]},
+    {"name": "77 0001",
+     "initial": {"pc": 256, "sp": 0, "a": 119, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 32, "l": 0,
+                 "ram": [[256, 119], [8192, 0]]},
+     "final": {"pc": 257, "sp": 0, "a": 119, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 32, "l": 0,
+               "ram": [[256, 119], [8192, 119]]},
+     "cycles": [[], [], [], [], [], [], []]},
+    {"name": "86 0002",
+     "initial": {"pc": 256, "sp": 0, "a": 15, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 32, "l": 0,
+                 "ram": [[256, 134], [8192, 1]]},
+     "final": {"pc": 257, "sp": 0, "a": 16, "b": 0, "c": 0, "d": 0, "e": 0, "f": 2, "h": 32, "l": 0,
+               "ram": [[256, 134], [8192, 2]]},
+     "cycles": [[], [], [], [], [], [], []]}
+]"#;
+
+#[test]
+fn runner_reports_mismatches() {
+    let (report, failures, total) = run_file(VECTORS);
+    assert_eq!((failures, total), (1, 3));
+    assert_eq!(report, ["    86 0002: f: expected 02 got 12 (AC should be clear), [2000]: expected 02 got 01"]);
+    assert_eq!(flag_diff(0xC5, 0x40), "(S should be set, P should be set, CY should be set)");
+    assert_eq!(flag_diff(0x02, 0x0A), "(unused bits only)");
+}
+
+#[test]
+fn single_step_vectors() {
+    let dir = vector_dir();
+    let mut files: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => Vec::new()
+    };
+    if files.is_empty() {
+        common::skipped(format_args!("no test vectors in {} (set {})", dir.display(), VECTORS_ENV));
+        return;
+    }
+    files.sort();
+
+    let mut report = Vec::new();
+    let mut failed_opcodes = 0;
+    for path in files.iter() {
+        let contents = fs::read_to_string(path).unwrap();
+        let (lines, failures, total) = run_file(&contents);
+        if failures > 0 {
+            failed_opcodes += 1;
+            let opcode = path.file_stem().unwrap().to_string_lossy();
+            report.push(format!("opcode {}: {} of {} cases failed", opcode, failures, total));
+            report.extend(lines);
+        }
+    }
+
+    assert!(failed_opcodes == 0, "{} of {} opcodes failed\n{}", failed_opcodes, files.len(), report.join("\n"));
+}
@@ -0,0 +1,72 @@
+use rustyi8080::Cpu8080;
+use rustyi8080::io::NullBus;
+use rustyi8080::opcode::{Category, OpcodeTable};
+use rustyi8080::stats::InstructionStats;
+
+/**
+ * Each conditional kind once taken and once not:
+ *
+ *  0000  LXI SP,$1000      000C  CNZ $0030   taken       0030  RC    not taken
+ *  0003  XRA A             000F  XRA A                   0031  STC
+ *  0004  JZ $0008  taken   0010  CNZ $0030   not taken   0032  RC    taken
+ *  0007  HLT               0013  HLT
+ *  0008  INR A
+ *  0009  JZ $0000  not taken
+ * */
+#[test]
+fn conditionals_count_when_taken() {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0x0000, &[0x31, 0x00, 0x10, 0xAF, 0xCA, 0x08, 0x00, 0x76, 0x3C, 0xCA, 0x00, 0x00,
+        0xC4, 0x30, 0x00, 0xAF, 0xC4, 0x30, 0x00, 0x76]);
+    cpu.load(0x0030, &[0xD8, 0x37, 0xD8]);
+    cpu.stats = Some(InstructionStats::new());
+    while !cpu.halted {
+        cpu.step(&mut NullBus);
+    }
+    assert_eq!(cpu.pc, 0x0014);
+
+    let stats = cpu.stats.as_ref().unwrap();
+    assert_eq!(stats.total(), 12);
+    for &(opcode, name) in [(0xCA, "JZ"), (0xC4, "CNZ"), (0xD8, "RC")].iter() {
+        assert_eq!((stats.count(opcode), stats.taken(opcode)), (2, 1), "{}", name);
+    }
+    assert_eq!((stats.count(0xAF), stats.taken(0xAF)), (2, 0), "only conditionals are ever taken");
+    assert_eq!(stats.category_count(Category::Branch), 6);
+
+    let mut report = Vec::new();
+    stats.write_report(&mut report, &cpu.opcode_table).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    for kind in ["jumps", "calls", "returns"].iter() {
+        let line = report.lines().find(|line| line.starts_with(kind)).unwrap();
+        assert_eq!(line.split_whitespace().collect::<Vec<_>>(), [kind, "1", "1", "50.00%"]);
+    }
+}
+
+/**
+ * The groups of chapter 4 of the Intel 8080 Assembly Language Programming Manual, by mnemonic
+ * */
+fn manual_category(mnemonic: &str) -> Category {
+    match mnemonic {
+        "MOV" | "MVI" | "LXI" | "LDA" | "STA" | "LHLD" | "SHLD" | "LDAX" | "STAX" | "XCHG" => Category::DataTransfer,
+        "ADD" | "ADI" | "ADC" | "ACI" | "SUB" | "SUI" | "SBB" | "SBI" | "INR" | "DCR" | "INX" | "DCX" | "DAD"
+            | "DAA" => Category::Arithmetic,
+        "ANA" | "ANI" | "XRA" | "XRI" | "ORA" | "ORI" | "CMP" | "CPI" | "RLC" | "RRC" | "RAL" | "RAR" | "CMA"
+            | "CMC" | "STC" => Category::Logical,
+        "JMP" | "JNZ" | "JZ" | "JNC" | "JC" | "JPO" | "JPE" | "JP" | "JM"
+            | "CALL" | "CNZ" | "CZ" | "CNC" | "CC" | "CPO" | "CPE" | "CP" | "CM"
+            | "RET" | "RNZ" | "RZ" | "RNC" | "RC" | "RPO" | "RPE" | "RP" | "RM" | "RST" | "PCHL" => Category::Branch,
+        "PUSH" | "POP" | "XTHL" | "SPHL" | "IN" | "OUT" | "EI" | "DI" | "HLT" | "NOP" => Category::Control,
+        _ => panic!("{} isn't an 8080 instruction", mnemonic)
+    }
+}
+
+#[test]
+fn every_opcode_is_in_its_manual_group() {
+    let table = OpcodeTable::new();
+    for opcode in 0..=255u8 {
+        let disassembly = table.get(opcode).unwrap().disassembly;
+        // undocumented opcodes are marked * and act as the instruction they alias
+        let mnemonic = disassembly.trim_start_matches('*').split_whitespace().next().unwrap();
+        assert_eq!(Category::of(opcode), manual_category(mnemonic), "{:02X} {}", opcode, disassembly);
+    }
+}
@@ -0,0 +1,62 @@
+use rustyi8080::symbols::SymbolTable;
+
+fn table(text: &str) -> SymbolTable {
+    let mut table = SymbolTable::new();
+    table.parse(text).unwrap_or_else(|e| panic!("{}", e));
+    table
+}
+
+#[test]
+fn assembler_sym_files() {
+    // MAC and RMAC: several pairs a line
+    let mac = table("0100 START   0103 LOOP    010A PRINT\n0200 BUFFER\n");
+    // M80 and zmac write lowercase hex, with comments and blank lines in between
+    let m80 = table("; symbols\n\n0100 START\n0103 Loop\r\n010a PRINT 0200 BUFFER\n");
+    for table in [mac, m80].iter() {
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.lookup("start"), Some(0x0100));
+        assert_eq!(table.lookup("LOOP"), Some(0x0103));
+        assert_eq!(table.lookup("print"), Some(0x010A));
+        assert_eq!(table.name_at(0x0200), Some("BUFFER"));
+    }
+}
+
+#[test]
+fn name_then_address_lists() {
+    let table = table("START EQU 0100H\nLOOP equ $103\nPRINT = 0x10A\nBUFFER: 0200\nCOUNT #16\nTOP 0FFFFH\n");
+    let symbols: Vec<(u16, &str)> = table.iter().collect();
+    assert_eq!(symbols, [(0x0010, "COUNT"), (0x0100, "START"), (0x0103, "LOOP"), (0x010A, "PRINT"),
+        (0x0200, "BUFFER"), (0xFFFF, "TOP")]);
+}
+
+#[test]
+fn hex_looking_names_follow_the_rest_of_the_file() {
+    // FACE 1000 reads as both layouts; the other lines settle it
+    let sym = table("0100 START\nFACE 1000\n");
+    assert_eq!((sym.lookup("1000"), sym.lookup("FACE")), (Some(0xFACE), None));
+    let list = table("START 0100\nFACE 1000\n");
+    assert_eq!((list.lookup("FACE"), list.lookup("1000")), (Some(0x1000), None));
+    let equ = table("FACE 1000\nBEEF EQU 2000\n");
+    assert_eq!(equ.lookup("FACE"), Some(0x1000));
+    // nothing to go by: a .sym pair
+    assert_eq!(table("FACE 1000").lookup("1000"), Some(0xFACE));
+    assert_eq!(table("FACE: 1000").lookup("FACE"), Some(0x1000));
+}
+
+#[test]
+fn unreadable_lines_are_errors() {
+    for text in ["START", "START 10000", "0100 START 0103", "A B C"].iter() {
+        let error = SymbolTable::new().parse(text).unwrap_err();
+        assert!(error.to_string().starts_with("line 1:"), "{}: {}", text, error);
+    }
+    assert!(SymbolTable::new().parse("0100 START\nSTART 10000\n").unwrap_err().to_string().starts_with("line 2:"));
+}
+
+#[test]
+fn describe_names_nearby_addresses() {
+    let table = table("0100 START 0200 BUFFER");
+    assert_eq!(table.describe(0x0100).as_deref(), Some("START"));
+    assert_eq!(table.describe(0x011A).as_deref(), Some("START+$1A"));
+    assert_eq!(table.describe(0x0400), None);
+    assert_eq!(table.describe(0x0050), None);
+}
@@ -0,0 +1,204 @@
+use rustyi8080::Cpu8080;
+use rustyi8080::io::NullBus;
+use rustyi8080::symbols::SymbolTable;
+use rustyi8080::Reg16;
+use rustyi8080::opcode::OpcodeTable;
+use rustyi8080::trace::{Divergence, TraceDiff, TraceFormat, TraceRecord, Tracer, compare, describe, diff_traces};
+
+/**
+ * Sets every register pair, runs ADD, STC, CALL/RET and a compare so the flags take several values
+ * */
+const PROGRAM: [u8; 24] = [
+    0x01, 0x34, 0x12,   // LXI B,$1234
+    0x11, 0xCD, 0xAB,   // LXI D,$ABCD
+    0x21, 0xFF, 0xFF,   // LXI H,$FFFF
+    0x31, 0x00, 0x20,   // LXI SP,$2000
+    0x3E, 0x80,         // MVI A,$80
+    0x87,               // ADD A
+    0x37,               // STC
+    0xCD, 0x16, 0x00,   // CALL $0016
+    0xFE, 0x01,         // CPI $01
+    0x76,               // HLT
+    0xAF,               // $0016: XRA A
+    0xC9                // RET
+];
+
+/**
+ * What a line traced from the CPU's current state should parse back to
+ * */
+fn expected(cpu: &Cpu8080, format: TraceFormat) -> TraceRecord {
+    let byte = |i: u16| cpu.memory[cpu.pc.wrapping_add(i) as usize];
+    let (bytes, disassembly) = match format {
+        TraceFormat::Native => {
+            let (disassembly, size) = cpu.opcode_table.disassemble(&cpu.memory, cpu.pc);
+            ((0..size as u16).map(byte).collect(), Some(disassembly))
+        },
+        TraceFormat::Superzazu => ((0..4).map(byte).collect(), None)
+    };
+    TraceRecord {
+        pc: cpu.pc,
+        a: cpu.a,
+        flags: cpu.condition_codes.to_psw() & 0xD5,
+        bc: cpu.bc(),
+        de: cpu.de(),
+        hl: cpu.hl(),
+        sp: cpu.sp,
+        cycles: Some(cpu.cycles),
+        bytes,
+        disassembly
+    }
+}
+
+/**
+ * Traces the program to its HLT, checking every line parses back to the state it was written from
+ * */
+fn round_trip(format: TraceFormat) {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0, &PROGRAM);
+    let mut tracer = Tracer::new(Vec::new(), format);
+    let mut records = Vec::new();
+    while !cpu.halted {
+        records.push(expected(&cpu, format));
+        tracer.step(&mut cpu, &mut NullBus).unwrap();
+    }
+    assert_eq!(tracer.lines(), records.len() as u64);
+
+    let log = String::from_utf8(tracer.into_inner()).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), records.len());
+    for (line, record) in lines.iter().zip(records.iter()) {
+        assert_eq!(TraceRecord::parse(line).as_ref(), Some(record), "line: {}", line);
+    }
+    assert!(records.iter().map(|record| record.flags).any(|flags| flags != 0 && flags != records[0].flags));
+}
+
+#[test]
+fn native_lines_parse_back() {
+    round_trip(TraceFormat::Native);
+}
+
+#[test]
+fn superzazu_lines_parse_back() {
+    round_trip(TraceFormat::Superzazu);
+}
+
+#[test]
+fn symbolic_disassembly_parses_back() {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0, &PROGRAM);
+    cpu.pc = 0x10;
+    let mut symbols = SymbolTable::new();
+    symbols.insert("CLEAR_A", 0x16);
+    let mut tracer = Tracer::new(Vec::new(), TraceFormat::Native).with_symbols(symbols);
+    tracer.trace(&cpu).unwrap();
+
+    let log = String::from_utf8(tracer.into_inner()).unwrap();
+    let record = TraceRecord::parse(log.trim_end()).unwrap();
+    assert_eq!(record.pc, 0x10);
+    assert_eq!(record.bytes, vec![0xCD, 0x16, 0x00]);
+    assert_eq!(record.disassembly.as_deref(), Some("CALL CLEAR_A"));
+}
+
+#[test]
+fn other_lines_are_rejected() {
+    assert_eq!(TraceRecord::parse(""), None);
+    assert_eq!(TraceRecord::parse("breakpoint 1 at 0100"), None);
+    assert_eq!(TraceRecord::parse("PC: 01G0, AF: 0002"), None);
+}
+
+/**
+ * Traces the program to its HLT, letting tamper change the CPU before the instruction with each index is traced
+ * */
+fn trace(format: TraceFormat, tamper: impl Fn(usize, &mut Cpu8080)) -> String {
+    let mut cpu = Cpu8080::new();
+    cpu.load(0, &PROGRAM);
+    let mut tracer = Tracer::new(Vec::new(), format);
+    for i in 0.. {
+        if cpu.halted {
+            break;
+        }
+        tamper(i, &mut cpu);
+        tracer.step(&mut cpu, &mut NullBus).unwrap();
+    }
+    String::from_utf8(tracer.into_inner()).unwrap()
+}
+
+fn diff(ours: &str, theirs: &str, context: usize, compare_cycles: bool) -> TraceDiff {
+    diff_traces(ours.as_bytes(), theirs.as_bytes(), context, compare_cycles).unwrap()
+}
+
+fn diverged(result: TraceDiff) -> Divergence {
+    match result {
+        TraceDiff::Diverge(divergence) => *divergence,
+        other => panic!("expected a divergence, got {:?}", other)
+    }
+}
+
+#[test]
+fn matching_traces_agree_across_formats() {
+    let ours = trace(TraceFormat::Native, |_, _| {});
+    let theirs = trace(TraceFormat::Superzazu, |_, _| {});
+    let count = ours.lines().count() as u64;
+    assert_eq!(diff(&ours, &theirs, 3, true), TraceDiff::Agree(count));
+
+    // lines that aren't records don't count, a trace that stops early does
+    let noisy = format!("starting\n{}breakpoint hit\n", theirs);
+    assert_eq!(diff(&ours, &noisy, 3, true), TraceDiff::Agree(count));
+    let short: String = theirs.lines().take(4).map(|line| format!("{}\n", line)).collect();
+    assert_eq!(diff(&ours, &short, 3, true), TraceDiff::Ends { agreed: 4, ours_ended: false });
+    assert_eq!(diff(&short, &ours, 3, true), TraceDiff::Ends { agreed: 4, ours_ended: true });
+}
+
+#[test]
+fn first_divergence_names_the_flags_and_keeps_context() {
+    let ours = trace(TraceFormat::Native, |_, _| {});
+    // from instruction 7 on their AC and CY are the other way round
+    let theirs = trace(TraceFormat::Superzazu, |i, cpu| if i == 7 {
+        let psw = cpu.get(Reg16::PSW);
+        cpu.set(Reg16::PSW, psw ^ 0x0011);
+    });
+    let records: Vec<TraceRecord> = ours.lines().filter_map(TraceRecord::parse).collect();
+    let divergence = diverged(diff(&ours, &theirs, 3, false));
+    assert_eq!(divergence.index, 7);
+    assert_eq!(divergence.ours, records[7]);
+    assert_eq!(divergence.context, records[4..7]);
+
+    let (ac, cy) = (records[7].flags & 0x10 != 0, records[7].flags & 0x01 != 0);
+    assert_eq!(divergence.differences, [format!("flags  AC ours {} theirs {}, CY ours {} theirs {}",
+        ac as u8, !ac as u8, cy as u8, !cy as u8)]);
+
+    // the context never reaches back before the first instruction
+    let theirs = trace(TraceFormat::Native, |i, cpu| if i == 1 { cpu.b = 0x77 });
+    let divergence = diverged(diff(&ours, &theirs, 5, false));
+    assert_eq!((divergence.index, divergence.context.len()), (1, 1));
+    assert_eq!(divergence.differences, ["BC     ours 1234  theirs 7734"]);
+    assert!(diverged(diff(&ours, &theirs, 0, false)).context.is_empty());
+}
+
+#[test]
+fn cycles_only_count_when_asked() {
+    let ours = trace(TraceFormat::Native, |_, _| {});
+    let theirs = trace(TraceFormat::Native, |i, cpu| if i == 5 { cpu.cycles += 1 });
+    assert!(matches!(diff(&ours, &theirs, 2, false), TraceDiff::Agree(_)));
+    let divergence = diverged(diff(&ours, &theirs, 2, true));
+    let cycles = divergence.ours.cycles.unwrap();
+    assert_eq!((divergence.index, divergence.differences), (5, vec![format!("CYC    ours {}  theirs {}", cycles, cycles + 1)]));
+}
+
+#[test]
+fn compare_and_describe_records() {
+    let ours = TraceRecord::parse("PC: 0100, AF: 12C7, BC: 0000, DE: 0000, HL: 0000, SP: 2000, CYC: 40\t(C3 00 02 00)").unwrap();
+    let mut theirs = ours.clone();
+    theirs.pc = 0x0103;
+    theirs.a = 0x13;
+    theirs.flags ^= 0x80 | 0x04;
+    assert_eq!(compare(&ours, &theirs, true), [
+        "PC     ours 0100  theirs 0103",
+        "A      ours 12  theirs 13",
+        "flags  S ours 1 theirs 0, P ours 1 theirs 0"
+    ]);
+    assert!(compare(&ours, &ours, true).is_empty());
+
+    let table = OpcodeTable::new();
+    assert_eq!(describe(&table, &ours), "0100  JMP $0200        A:12 BC:0000 DE:0000 HL:0000 SP:2000 F:SZ.PC CYC:40");
+}